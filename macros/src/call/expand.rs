@@ -1,9 +1,9 @@
-use super::parse::CallDef;
-use quote::quote;
+use super::parse::{CallDef, CallReceiver, CallerArg};
+use quote::{format_ident, quote};
 
 /// See the `fn call` docs at the `lib.rs` of this crate for a high level definition.
 pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
-	let CallDef { pallet_struct, methods } = def;
+	let CallDef { self_ty, generics, methods } = def;
 
 	// This is a vector of all the callable function names.
 	let fn_name = methods.iter().map(|method| &method.name).collect::<Vec<_>>();
@@ -17,46 +17,360 @@ pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
 		.collect::<Vec<_>>();
 
 	// This is a nested vector of all the types for all the arguments for each of the functions in
-	// `fn_name`. It has the same assumptions as `args_name`.
+	// `fn_name`. It has the same assumptions as `args_name`. Arguments of type `T::AccountId` are
+	// held as addresses, see `lookup_args`.
 	let args_type = methods
 		.iter()
-		.map(|method| method.args.iter().map(|(_, type_)| type_.clone()).collect::<Vec<_>>())
+		.map(|method| {
+			method
+				.args
+				.iter()
+				.map(|(name, type_)| {
+					if method.lookup_args.contains(name) {
+						quote! { <T as crate::support::Lookup>::Source }
+					} else {
+						quote! { #type_ }
+					}
+				})
+				.collect::<Vec<_>>()
+		})
 		.collect::<Vec<_>>();
 
+	// This is a nested vector of the arguments of type `T::AccountId` for each of the functions in
+	// `fn_name`. They are resolved from addresses to accounts with `support::Lookup` before the
+	// function is called, so calls can refer to accounts by a shorter address like an index.
+	let lookup_args = methods.iter().map(|method| &method.lookup_args).collect::<Vec<_>>();
+
+	// This is a flat vector of the types of all arguments, used to bound the trait implementations
+	// on the `Call` enum.
+	let all_args_type = args_type.iter().flatten().collect::<Vec<_>>();
+
+	// This is a vector of the index of each of the functions in `fn_name`, from `#[call_index(n)]`,
+	// which identifies it in the encoding of a `Call`.
+	let call_index = methods.iter().map(|method| method.index).collect::<Vec<_>>();
+
+	// This is a vector of the position of each of the functions in `fn_name` among the functions
+	// marked with `#[inherent]`, or `None` for the functions which are not inherents.
+	let mut inherent_count = 0usize;
+	let inherent_position = methods
+		.iter()
+		.map(|method| {
+			if method.inherent {
+				let position = inherent_count;
+				inherent_count += 1;
+				quote! { Some(#position) }
+			} else {
+				quote! { None }
+			}
+		})
+		.collect::<Vec<_>>();
+
+	// These are vectors of the names of the functions in `fn_name` and of their arguments as
+	// strings, and of their doc comments, for the metadata of the calls.
+	let fn_name_str = fn_name.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+	let args_name_str = args_name
+		.iter()
+		.map(|names| names.iter().map(|name| name.to_string()).collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+	let fn_docs = methods.iter().map(|method| &method.docs).collect::<Vec<_>>();
+
+	// This is a vector of the expressions which call each of the functions in `fn_name`, depending
+	// on whether the function takes `self` or `runtime: &mut T`.
+	let fn_call = methods
+		.iter()
+		.map(|method| {
+			let name = &method.name;
+			match method.receiver {
+				CallReceiver::Pallet => quote! {
+					crate::support::GetPallet::<#self_ty>::pallet_mut(runtime).#name
+				},
+				CallReceiver::Runtime => quote! {
+					<#self_ty>::#name
+				},
+			}
+		})
+		.collect::<Vec<_>>();
+
+	// The `runtime` is passed as the first argument to functions which take `runtime: &mut T`.
+	let runtime_arg = methods
+		.iter()
+		.map(|method| match method.receiver {
+			CallReceiver::Pallet => quote! {},
+			CallReceiver::Runtime => quote! { runtime, },
+		})
+		.collect::<Vec<_>>();
+
+	// This is a vector of the `caller` passed to each of the functions in `fn_name`. Functions
+	// which take `caller: T::AccountId` can only be dispatched by a signed origin.
+	let caller_arg = methods
+		.iter()
+		.map(|method| match method.caller {
+			CallerArg::Caller => quote! { crate::support::ensure_signed(origin)? },
+			CallerArg::Origin => quote! { origin },
+		})
+		.collect::<Vec<_>>();
+
+	// The generics of `Call` are those of the `impl` block, with the instance parameter of
+	// instantiable pallets defaulting to `()`, so that the calls of the default instance are
+	// `Call<T>`, like `Pallet<T>`. Arguments of type `T::AccountId` are resolved with `T`, so it
+	// must implement `support::Lookup`.
+	let mut call_generics = generics.clone();
+	let instance = crate::instance::instance_param(&generics).map(|param| param.ident.clone());
+	for param in call_generics.type_params_mut() {
+		if Some(&param.ident) == instance.as_ref() {
+			param.eq_token = Some(Default::default());
+			param.default = Some(syn::parse_quote!(()));
+		}
+	}
+	call_generics.make_where_clause().predicates.push(syn::parse_quote!(T: crate::support::Lookup));
+	let call_where = &call_generics.where_clause;
+	let (impl_generics, ty_generics, _) = call_generics.split_for_impl();
+	let type_params = generics.type_params().map(|param| &param.ident).collect::<Vec<_>>();
+
+	// The trait implementations on `Call` require the types of all arguments to implement them.
+	let bounded = |bound: proc_macro2::TokenStream| {
+		let mut generics = call_generics.clone();
+		let where_clause = generics.make_where_clause();
+		for ty in &all_args_type {
+			where_clause.predicates.push(syn::parse_quote!(#ty: #bound));
+		}
+		generics.where_clause
+	};
+	let clone_where = bounded(quote! { core::clone::Clone });
+	let hash_where = bounded(quote! { core::hash::Hash });
+	let encode_where = bounded(quote! { crate::codec::Encode });
+	let decode_where = bounded(quote! { crate::codec::Decode });
+	let debug_where = bounded(quote! { core::fmt::Debug });
+	let eq_where = bounded(quote! { core::cmp::Eq });
+	let partial_eq_where = bounded(quote! { core::cmp::PartialEq });
+	let type_info_where =
+		crate::type_info::with_bounds(&call_generics, &all_args_type).where_clause;
+
+	// This is a nested vector of the names the arguments of the other call are bound to when two
+	// calls are compared, and a vector of the comparison of each of the functions in `fn_name`. The
+	// names are suffixed, so that arguments like `_value` still give snake case names.
+	let other_args_name = args_name
+		.iter()
+		.map(|names| names.iter().map(|name| format_ident!("{}_other", name)).collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+	let args_eq = args_name.iter().zip(&other_args_name).map(|(names, other_names)| {
+		if names.is_empty() {
+			quote! { true }
+		} else {
+			quote! { #( #names == #other_names )&&* }
+		}
+	});
+
 	// This quote block creates an `enum Call` which contains all the calls exposed by our pallet,
-	// and the `Dispatch` trait logic to route a `caller` to access those functions.
+	// and the dispatch logic to route a `caller` to access those functions.
 	let dispatch_impl = quote! {
 		// The callable functions exposed by this pallet.
 		//
 		// The parsed function names will be `snake_case`, and that will show up in the enum.
 		#[allow(non_camel_case_types)]
-		pub enum Call<T: Config> {
+		pub enum Call #call_generics #call_where {
 			#(
 				#fn_name { #( #args_name: #args_type),* },
 			)*
+			// Uses `T` for pallets whose calls do not otherwise mention it. This variant can never
+			// be constructed.
+			#[doc(hidden)]
+			__Ignore(core::marker::PhantomData<(#( #type_params ),*)>, core::convert::Infallible),
 		}
 
 		// Dispatch logic at the pallet level, mapping each of the items in the `Call` enum to the
-		// appropriate function call with all arguments, including the `caller`.
-		impl<T: Config> crate::support::Dispatch for #pallet_struct<T> {
-			type Caller = T::AccountId;
-			type Call = Call<T>;
-
-			fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> crate::support::DispatchResult {
-				match call {
+		// appropriate function call with all arguments, including the `caller` or the `origin`.
+		//
+		// The pallet is reached through the `runtime`, so that calls which need other pallets can
+		// be given the whole runtime.
+		//
+		// For pallets without calls, only the hidden variant is matched, which cannot be built, so
+		// the end of the function is unreachable.
+		//
+		// The origin holds the `T::AccountId` of the config of the pallet, which extends the config
+		// of whichever system pallet the runtime uses, so the system pallet is not named here.
+		impl #impl_generics Call #ty_generics #call_where {
+			#[allow(unreachable_code)]
+			pub fn dispatch(
+				self,
+				runtime: &mut T,
+				origin: crate::support::RawOrigin<T::AccountId>,
+			) -> crate::support::DispatchResult
+			where
+				T: crate::support::GetPallet<#self_ty>
+					+ crate::support::Lookup<Target = T::AccountId>,
+			{
+				match self {
 					#(
 						Call::#fn_name { #( #args_name ),* } => {
-							self.#fn_name(
-								// Note that we assume the first argument of every call is the `caller`.
-								caller,
+							#(
+								let #lookup_args = crate::support::Lookup::lookup(&*runtime, #lookup_args)?;
+							)*
+							#fn_call(
+								#runtime_arg
+								// Note that we assume the first argument of every call is the `caller`
+								// or the `origin`.
+								#caller_arg,
 								#( #args_name ),*
 							)?;
 						},
 					)*
+					Call::__Ignore(_, never) => match never {},
 				}
 				Ok(())
 			}
 		}
+
+		impl #impl_generics Call #ty_generics #type_info_where {
+			/// The metadata of each call, by index. Arguments of type `T::AccountId` are described
+			/// as the addresses they are given as.
+			pub fn metadata(
+				registry: &mut crate::metadata::TypeRegistry,
+			) -> Vec<crate::metadata::VariantMetadata> {
+				vec![
+					#(
+						crate::metadata::VariantMetadata {
+							name: #fn_name_str,
+							index: #call_index,
+							fields: vec![
+								#(
+									crate::metadata::FieldMetadata {
+										name: Some(#args_name_str),
+										ty: registry.register::<#args_type>(),
+									}
+								),*
+							],
+							docs: #fn_docs,
+						}
+					),*
+				]
+			}
+		}
+
+		impl #impl_generics crate::metadata::TypeInfo for Call #ty_generics #type_info_where {
+			fn type_info(
+				registry: &mut crate::metadata::TypeRegistry,
+			) -> crate::metadata::TypeMetadata {
+				crate::metadata::TypeMetadata {
+					path: concat!(module_path!(), "::Call"),
+					def: crate::metadata::TypeDef::Variant(Self::metadata(registry)),
+				}
+			}
+		}
+
+		// Calls are printed by pallets which store them, and compared in tests. Like the other
+		// traits, these require the types of the arguments to implement them, but not `T`.
+		impl #impl_generics core::fmt::Debug for Call #ty_generics #debug_where {
+			fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => f
+							.debug_struct(#fn_name_str)
+							#( .field(#args_name_str, #args_name) )*
+							.finish(),
+					)*
+					Call::__Ignore(_, never) => match *never {},
+				}
+			}
+		}
+
+		impl #impl_generics core::cmp::PartialEq for Call #ty_generics #partial_eq_where {
+			fn eq(&self, other: &Self) -> bool {
+				match (self, other) {
+					#(
+						(
+							Call::#fn_name { #( #args_name ),* },
+							Call::#fn_name { #( #args_name: #other_args_name ),* },
+						) => #args_eq,
+					)*
+					_ => false,
+				}
+			}
+		}
+
+		impl #impl_generics core::cmp::Eq for Call #ty_generics #eq_where {}
+
+		// Calls are cloned by pallets which dispatch the same call more than once, for example
+		// periodic tasks.
+		impl #impl_generics core::clone::Clone for Call #ty_generics #clone_where {
+			fn clone(&self) -> Self {
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => Call::#fn_name {
+							#( #args_name: #args_name.clone() ),*
+						},
+					)*
+					Call::__Ignore(_, never) => match *never {},
+				}
+			}
+		}
+
+		// Every call has the same weight for now.
+		impl #impl_generics crate::support::GetWeight for Call #ty_generics #call_where {
+			fn weight(&self) -> crate::support::Weight {
+				crate::support::CALL_WEIGHT
+			}
+		}
+
+		// Blocks must include every inherent exactly once, and only inherents can be unsigned. See
+		// `support::Inherents`.
+		impl #impl_generics crate::support::Inherents for Call #ty_generics #call_where {
+			const COUNT: usize = #inherent_count;
+
+			fn inherent(&self) -> Option<usize> {
+				match self {
+					#(
+						Call::#fn_name { .. } => #inherent_position,
+					)*
+					Call::__Ignore(_, never) => match *never {},
+				}
+			}
+		}
+
+		// Calls are hashed by pallets which refer to a call before it is dispatched, for example to
+		// collect approvals for it. Like the encoding, the hash starts with the index of the call.
+		impl #impl_generics core::hash::Hash for Call #ty_generics #hash_where {
+			fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => {
+							state.write_u8(#call_index);
+							#( #args_name.hash(state); )*
+						},
+					)*
+					Call::__Ignore(_, never) => match *never {},
+				}
+			}
+		}
+
+		// Calls are encoded by pallets which store them as bytes, for example preimages. See
+		// `codec`.
+		impl #impl_generics crate::codec::Encode for Call #ty_generics #encode_where {
+			fn encode_to(&self, dest: &mut Vec<u8>) {
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => {
+							crate::codec::Encode::encode_to(&#call_index, dest);
+							#( crate::codec::Encode::encode_to(#args_name, dest); )*
+						},
+					)*
+					Call::__Ignore(_, never) => match *never {},
+				}
+			}
+		}
+
+		impl #impl_generics crate::codec::Decode for Call #ty_generics #decode_where {
+			fn decode(input: &mut &[u8]) -> Result<Self, String> {
+				match <u8 as crate::codec::Decode>::decode(input)? {
+					#(
+						#call_index => Ok(Call::#fn_name {
+							#( #args_name: crate::codec::Decode::decode(input)? ),*
+						}),
+					)*
+					_ => Err("invalid call index".to_string()),
+				}
+			}
+		}
 	};
 
 	// Return the generated code.
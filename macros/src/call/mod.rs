@@ -6,20 +6,38 @@ pub fn call(
 	_attr: proc_macro::TokenStream,
 	item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-	// The final expanded code will be placed here.
-	// Since our macro only adds new code, our final product will contain all of our old code too,
-	// hence we clone `item`.
-	let mut finished = item.clone();
 	let item_mod = syn::parse_macro_input!(item as syn::Item);
+	expand(item_mod).into()
+}
+
+/// Expand the callable functions in `item_mod`, with any errors in the generated code. This is
+/// also used by `#[macros::pallet]` for its `#[pallet::call]` section.
+pub fn expand(item_mod: syn::Item) -> proc_macro2::TokenStream {
+	// The final expanded code will be placed here. It contains all of our old code too, without the
+	// `#[call_index]` and `#[inherent]` attributes, which are not real attributes. They are removed
+	// even when parsing fails, so that the errors are not hidden by errors about unknown
+	// attributes.
+	let mut finished = item_mod.clone();
+	if let syn::Item::Impl(item_impl) = &mut finished {
+		for item in item_impl.items.iter_mut() {
+			if let syn::ImplItem::Fn(method) = item {
+				method.attrs.retain(|attr| {
+					!attr.path().is_ident("call_index") && !attr.path().is_ident("inherent")
+				});
+			}
+		}
+	}
 
 	// First we parse the call functions implemented for the pallet...
-	let generated: proc_macro::TokenStream = match parse::CallDef::try_from(item_mod.clone()) {
+	let generated = match parse::CallDef::try_from(item_mod) {
 		// ..then we generate our new code.
-		Ok(def) => expand::expand_call(def).into(),
-		Err(e) => e.to_compile_error().into(),
+		Ok(def) => expand::expand_call(def),
+		Err(e) => e.to_compile_error(),
 	};
 
 	// Add our generated code to the end, and return the final result.
-	finished.extend(generated);
-	return finished;
+	quote::quote! {
+		#finished
+		#generated
+	}
 }
@@ -5,15 +5,19 @@ use syn::spanned::Spanned;
 mod keyword {
 	syn::custom_keyword!(T);
 	syn::custom_keyword!(AccountId);
+	syn::custom_keyword!(Origin);
 }
 
 /// This object will collect all the information we need to keep while parsing the callable
 /// functions.
 #[derive(Debug)]
 pub struct CallDef {
-	/// This is the name of the pallet struct where the callable functions are implemented. We
-	/// mostly assume it is `Pallet`.
-	pub pallet_struct: syn::Ident,
+	/// This is the type of the pallet struct where the callable functions are implemented, like
+	/// `Pallet<T>` or `Pallet<T, I>` for instantiable pallets.
+	pub self_ty: Box<syn::Type>,
+	/// The generics and the where clause of the `impl` block, which are also those of `Call`. The
+	/// first type parameter is the runtime, `T`.
+	pub generics: syn::Generics,
 	/// This is a list of the callable functions exposed by this pallet. See `CallVariantDef`.
 	pub methods: Vec<CallVariantDef>,
 }
@@ -23,8 +27,41 @@ pub struct CallDef {
 pub struct CallVariantDef {
 	/// The function name.
 	pub name: syn::Ident,
+	/// What the function is called on. See `CallReceiver`.
+	pub receiver: CallReceiver,
+	/// Who the function is called by. See `CallerArg`.
+	pub caller: CallerArg,
 	/// Information on args of the function: `(name, type)`.
 	pub args: Vec<(syn::Ident, Box<syn::Type>)>,
+	/// The names of the args of type `T::AccountId`. Calls hold addresses for these instead, which
+	/// are resolved to accounts with `support::Lookup` before the function is called.
+	pub lookup_args: Vec<syn::Ident>,
+	/// The doc comment of the function.
+	pub docs: String,
+	/// The index of the call, from `#[call_index(n)]`, which identifies it in the encoding of a
+	/// `Call`.
+	pub index: u8,
+	/// Whether the call is an inherent, from `#[inherent]`. See `support::Inherents`.
+	pub inherent: bool,
+}
+
+/// The first argument of a callable function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallReceiver {
+	/// The function takes a variant of `self`, and only has access to the state of its own pallet.
+	Pallet,
+	/// The function takes `runtime: &mut T`, and can access any pallet in the runtime through
+	/// `support::GetPallet`, as well as dispatch other calls.
+	Runtime,
+}
+
+/// The second argument of a callable function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallerArg {
+	/// The function takes `caller: T::AccountId`, and can only be called by a signed origin.
+	Caller,
+	/// The function takes `origin: Origin<T>`, and checks the origin itself.
+	Origin,
 }
 
 impl CallDef {
@@ -36,44 +73,94 @@ impl CallDef {
 			return Err(syn::Error::new(item.span(), "Invalid pallet::call, expected item impl"))
 		};
 
-		// Extract the name of the struct. We mostly assume it is `Pallet`, but we can handle it
-		// when it isn't.
-		let pallet_struct = match &*item_impl.self_ty {
-			syn::Type::Path(tp) => tp.path.segments.first().unwrap().ident.clone(),
-			_ => panic!("not supported tokens"),
+		if let Some((_, path, _)) = &item_impl.trait_ {
+			let msg = "Invalid pallet::call, expected an inherent impl of the pallet struct";
+			return Err(syn::Error::new(path.span(), msg))
+		}
+
+		// Extract the type of the struct. We mostly assume it is `Pallet<T>`, but we can handle
+		// other names and generics.
+		let self_ty = match &*item_impl.self_ty {
+			syn::Type::Path(tp) if tp.qself.is_none() => item_impl.self_ty.clone(),
+			ty => {
+				let msg = "Invalid pallet::call, expected the pallet struct, like `Pallet<T>`";
+				return Err(syn::Error::new(ty.span(), msg))
+			},
 		};
 
-		// Here is where we will store all the callable functions.
+		// The calls refer to the runtime as `T`, so it must be the first type parameter.
+		let generics = item_impl.generics.clone();
+		if generics.type_params().next().is_none_or(|param| param.ident != "T") {
+			let span = match generics.params.first() {
+				Some(param) => param.span(),
+				None => item_impl.impl_token.span,
+			};
+			let msg = "Invalid pallet::call, expected the runtime `T` as the first type parameter";
+			return Err(syn::Error::new(span, msg))
+		}
+
+		// Here is where we will store all the callable functions, and the spans of their indices.
 		let mut methods = vec![];
+		let mut index_spans = vec![];
 		for item in item_impl.items {
-			if let syn::ImplItem::Fn(method) = item {
+			if let syn::ImplItem::Fn(mut method) = item {
+				// Every call must have an explicit index, so that reordering the functions does not
+				// change the encoding of calls.
+				let (index, index_span) =
+					match crate::index::take_index_attr(&mut method.attrs, "call_index")? {
+						Some(index) => index,
+						None => {
+							let msg = "missing `#[call_index(n)]`, every call needs an explicit \
+								index";
+							return Err(syn::Error::new(method.sig.ident.span(), msg))
+						},
+					};
+				index_spans.push(index_span);
+				let inherent = take_inherent_attr(&mut method.attrs)?;
+
 				// Here is where we will store all the args for each callable functions.
 				let mut args = vec![];
+				let mut lookup_args = vec![];
 
-				// First argument should be some variant of `self`.
-				match method.sig.inputs.first() {
-					Some(syn::FnArg::Receiver(_)) => {},
-					_ => {
-						let msg = "Invalid call, first argument must be a variant of self";
+				// First argument should be some variant of `self`, or the `runtime: &mut T`
+				// argument.
+				let receiver = match method.sig.inputs.first() {
+					Some(syn::FnArg::Receiver(_)) => CallReceiver::Pallet,
+					Some(syn::FnArg::Typed(arg)) => {
+						check_runtime_arg(arg)?;
+						CallReceiver::Runtime
+					},
+					None => {
+						let msg = "Invalid call, first argument must be a variant of self or \
+							`runtime: &mut T`";
 						return Err(syn::Error::new(method.sig.span(), msg))
 					},
-				}
+				};
 
-				// The second argument should be the `caller: T::AccountId` argument.
-				match method.sig.inputs.iter().skip(1).next() {
+				// The second argument should be the `caller: T::AccountId` or the
+				// `origin: Origin<T>` argument.
+				let caller = match method.sig.inputs.iter().skip(1).next() {
 					Some(syn::FnArg::Typed(arg)) => {
 						// Here we specifically check that this argument is as we expect for
-						// `caller: T::AccountId`.
-						check_caller_arg(arg)?;
+						// `caller: T::AccountId` or `origin: Origin<T>`.
+						check_caller_arg(arg)?
 					},
 					_ => {
-						let msg = "Invalid call, second argument should be `caller: T::AccountId`";
+						let msg = "Invalid call, second argument should be `caller: T::AccountId` \
+							or `origin: Origin<T>`";
 						return Err(syn::Error::new(method.sig.span(), msg))
 					},
-				}
+				};
 
 				let fn_name = method.sig.ident.clone();
 
+				// Inherents are unsigned, so they cannot take a caller.
+				if inherent && caller == CallerArg::Caller {
+					let msg = "Invalid call, an inherent is unsigned, so its second argument \
+						should be `origin: Origin<T>`";
+					return Err(syn::Error::new(fn_name.span(), msg))
+				}
+
 				// Parsing the rest of the args. Skipping 2 for `self` and `caller`.
 				for arg in method.sig.inputs.iter().skip(2) {
 					// All arguments should be typed.
@@ -92,46 +179,88 @@ impl CallDef {
 					};
 
 					// Store the argument name and the argument type for generating code.
+					if is_account_id(&arg.ty) {
+						lookup_args.push(arg_ident.clone());
+					}
 					args.push((arg_ident, arg.ty.clone()));
 				}
 
 				// Store all the function name and the arg data for the function.
-				methods.push(CallVariantDef { name: fn_name, args });
+				let docs = crate::pallet_enum::docs(&method.attrs);
+				methods.push(CallVariantDef {
+					name: fn_name,
+					receiver,
+					caller,
+					args,
+					lookup_args,
+					docs,
+					index,
+					inherent,
+				});
 			}
 		}
 
+		let indices = methods
+			.iter()
+			.zip(index_spans)
+			.map(|(method, span)| (&method.name, method.index, span))
+			.collect::<Vec<_>>();
+		crate::index::check_unique("call", &indices)?;
+
 		// Return all callable functions for this pallet.
-		Ok(Self { pallet_struct, methods })
+		Ok(Self { self_ty, generics, methods })
 	}
 }
 
-/// Check caller arg is exactly: `caller: T::AccountId`.
+/// Remove the `#[inherent]` attribute from `attrs`, and return whether there was one.
+fn take_inherent_attr(attrs: &mut Vec<syn::Attribute>) -> syn::Result<bool> {
+	let mut inherent = false;
+	let mut result = Ok(());
+	attrs.retain(|attr| {
+		if !attr.path().is_ident("inherent") {
+			return true
+		}
+		if let Err(e) = attr.meta.require_path_only() {
+			let msg = "expected `#[inherent]`, without arguments";
+			let mut error = syn::Error::new_spanned(attr, msg);
+			error.combine(e);
+			result = Err(error);
+		} else if inherent {
+			result = Err(syn::Error::new_spanned(attr, "duplicate `#[inherent]` attribute"));
+		}
+		inherent = true;
+		false
+	});
+	result.map(|()| inherent)
+}
+
+/// Check runtime arg is exactly: `runtime: &mut T`.
 ///
 /// This is kept strict to keep the code simple.
-pub fn check_caller_arg(arg: &syn::PatType) -> syn::Result<()> {
-	pub struct CheckDispatchableFirstArg;
-	impl syn::parse::Parse for CheckDispatchableFirstArg {
+pub fn check_runtime_arg(arg: &syn::PatType) -> syn::Result<()> {
+	pub struct CheckRuntimeArg;
+	impl syn::parse::Parse for CheckRuntimeArg {
 		fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+			input.parse::<syn::Token![&]>()?;
+			input.parse::<syn::Token![mut]>()?;
 			input.parse::<keyword::T>()?;
-			input.parse::<syn::Token![::]>()?;
-			input.parse::<keyword::AccountId>()?;
 			Ok(Self)
 		}
 	}
 
-	// This checks the arg name is `caller` or `_caller`.
+	// This checks the arg name is `runtime` or `_runtime`.
 	if let syn::Pat::Ident(ident) = &*arg.pat {
-		// We also support the name as `_caller` for when the variable is unused.
-		if &ident.ident != "caller" && &ident.ident != "_caller" {
-			let msg = "Invalid name for second parameter: expected `caller: T::AccountId`";
+		if &ident.ident != "runtime" && &ident.ident != "_runtime" {
+			let msg = "Invalid name for first parameter: expected a variant of self or \
+				`runtime: &mut T`";
 			return Err(syn::Error::new(ident.span(), msg))
 		}
 	}
 
-	// This checks the type is `T::AccountId` with `CheckDispatchableFirstArg`
+	// This checks the type is `&mut T` with `CheckRuntimeArg`.
 	let ty = &arg.ty;
-	syn::parse2::<CheckDispatchableFirstArg>(ty.to_token_stream()).map_err(|e| {
-		let msg = "Invalid type for second parameter: expected `caller: T::AccountId`";
+	syn::parse2::<CheckRuntimeArg>(ty.to_token_stream()).map_err(|e| {
+		let msg = "Invalid type for first parameter: expected `runtime: &mut T`";
 		let mut err = syn::Error::new(ty.span(), msg);
 		err.combine(e);
 		err
@@ -139,3 +268,69 @@ pub fn check_caller_arg(arg: &syn::PatType) -> syn::Result<()> {
 
 	Ok(())
 }
+
+/// Parses exactly the type `T::AccountId`.
+pub struct CheckAccountIdType;
+impl syn::parse::Parse for CheckAccountIdType {
+	fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+		input.parse::<keyword::T>()?;
+		input.parse::<syn::Token![::]>()?;
+		input.parse::<keyword::AccountId>()?;
+		Ok(Self)
+	}
+}
+
+/// Check whether a type is exactly `T::AccountId`. Other spellings of the same type, and types
+/// which contain it like `Vec<T::AccountId>`, are not resolved with `support::Lookup`.
+pub fn is_account_id(ty: &syn::Type) -> bool {
+	syn::parse2::<CheckAccountIdType>(ty.to_token_stream()).is_ok()
+}
+
+/// Check caller arg is exactly: `caller: T::AccountId` or `origin: Origin<T>`.
+///
+/// This is kept strict to keep the code simple.
+pub fn check_caller_arg(arg: &syn::PatType) -> syn::Result<CallerArg> {
+
+	pub struct CheckDispatchableOriginArg;
+	impl syn::parse::Parse for CheckDispatchableOriginArg {
+		fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+			input.parse::<keyword::Origin>()?;
+			input.parse::<syn::Token![<]>()?;
+			input.parse::<keyword::T>()?;
+			input.parse::<syn::Token![>]>()?;
+			Ok(Self)
+		}
+	}
+
+	// This checks the arg name is `caller` or `origin`, or `_caller` or `_origin`.
+	let mut caller = CallerArg::Caller;
+	if let syn::Pat::Ident(ident) = &*arg.pat {
+		// We also support the names with a leading `_` for when the variable is unused.
+		if &ident.ident == "origin" || &ident.ident == "_origin" {
+			caller = CallerArg::Origin;
+		} else if &ident.ident != "caller" && &ident.ident != "_caller" {
+			let msg = "Invalid name for second parameter: expected `caller: T::AccountId` or \
+				`origin: Origin<T>`";
+			return Err(syn::Error::new(ident.span(), msg))
+		}
+	}
+
+	// This checks the type is `T::AccountId` with `CheckAccountIdType`, or `Origin<T>` with
+	// `CheckDispatchableOriginArg`.
+	let ty = &arg.ty;
+	let result = match caller {
+		CallerArg::Caller => syn::parse2::<CheckAccountIdType>(ty.to_token_stream()).map(|_| ()),
+		CallerArg::Origin => syn::parse2::<CheckDispatchableOriginArg>(ty.to_token_stream()).map(|_| ()),
+	};
+	result.map_err(|e| {
+		let msg = match caller {
+			CallerArg::Caller => "Invalid type for second parameter: expected `caller: T::AccountId`",
+			CallerArg::Origin => "Invalid type for second parameter: expected `origin: Origin<T>`",
+		};
+		let mut err = syn::Error::new(ty.span(), msg);
+		err.combine(e);
+		err
+	})?;
+
+	Ok(caller)
+}
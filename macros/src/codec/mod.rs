@@ -0,0 +1,161 @@
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+
+/// See the `fn derive_encode` docs at the `lib.rs` of this crate for a high level definition.
+pub fn derive_encode(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = syn::parse_macro_input!(item as syn::DeriveInput);
+	match expand_encode(input) {
+		Ok(generated) => generated.into(),
+		Err(e) => e.to_compile_error().into(),
+	}
+}
+
+/// See the `fn derive_decode` docs at the `lib.rs` of this crate for a high level definition.
+pub fn derive_decode(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = syn::parse_macro_input!(item as syn::DeriveInput);
+	match expand_decode(input) {
+		Ok(generated) => generated.into(),
+		Err(e) => e.to_compile_error().into(),
+	}
+}
+
+fn expand_encode(input: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+	let name = &input.ident;
+	let generics = with_bound(&input.generics, quote! { crate::codec::Encode });
+	let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+	// Structs encode their fields in order. Enums encode the index of their variant first.
+	let body = match &input.data {
+		syn::Data::Struct(data) => {
+			let (names, pattern) = bindings(&data.fields);
+			quote! {
+				let Self #pattern = self;
+				#( crate::codec::Encode::encode_to(#names, dest); )*
+			}
+		},
+		syn::Data::Enum(data) => {
+			let index = variant_indices(data)?;
+			let (variant, (names, pattern)): (Vec<_>, (Vec<_>, Vec<_>)) = data
+				.variants
+				.iter()
+				.map(|variant| (&variant.ident, bindings(&variant.fields)))
+				.unzip();
+			quote! {
+				match self {
+					#(
+						Self::#variant #pattern => {
+							crate::codec::Encode::encode_to(&#index, dest);
+							#( crate::codec::Encode::encode_to(#names, dest); )*
+						},
+					)*
+				}
+			}
+		},
+		syn::Data::Union(_) => {
+			return Err(syn::Error::new(input.span(), "unions cannot be encoded"));
+		},
+	};
+
+	Ok(quote! {
+		impl #impl_generics crate::codec::Encode for #name #ty_generics #where_clause {
+			fn encode_to(&self, dest: &mut Vec<u8>) {
+				#body
+			}
+		}
+	})
+}
+
+fn expand_decode(input: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+	let name = &input.ident;
+	let generics = with_bound(&input.generics, quote! { crate::codec::Decode });
+	let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+	let body = match &input.data {
+		syn::Data::Struct(data) => {
+			let construct = construct(&data.fields);
+			quote! { Ok(Self #construct) }
+		},
+		syn::Data::Enum(data) => {
+			let index = variant_indices(data)?;
+			let variant = data.variants.iter().map(|variant| &variant.ident);
+			let construct = data.variants.iter().map(|variant| construct(&variant.fields));
+			let error = format!("invalid variant index of `{}`", name);
+			quote! {
+				match <u8 as crate::codec::Decode>::decode(input)? {
+					#( #index => Ok(Self::#variant #construct), )*
+					_ => Err(#error.to_string()),
+				}
+			}
+		},
+		syn::Data::Union(_) => {
+			return Err(syn::Error::new(input.span(), "unions cannot be decoded"));
+		},
+	};
+
+	Ok(quote! {
+		impl #impl_generics crate::codec::Decode for #name #ty_generics #where_clause {
+			fn decode(input: &mut &[u8]) -> Result<Self, String> {
+				#body
+			}
+		}
+	})
+}
+
+/// Bound every type parameter of `generics` by `bound`.
+fn with_bound(generics: &syn::Generics, bound: proc_macro2::TokenStream) -> syn::Generics {
+	let mut generics = generics.clone();
+	for param in generics.type_params_mut() {
+		param.bounds.push(syn::parse_quote!(#bound));
+	}
+	generics
+}
+
+/// The index of each variant of an enum, which is its position. There can be at most 256 variants,
+/// since the index is encoded as a `u8`.
+fn variant_indices(data: &syn::DataEnum) -> syn::Result<Vec<u8>> {
+	(0..data.variants.len())
+		.map(|index| {
+			u8::try_from(index).map_err(|_| {
+				let msg = "enums with more than 256 variants cannot be encoded";
+				syn::Error::new(data.variants[index].span(), msg)
+			})
+		})
+		.collect()
+}
+
+/// The names bound to each field, and the pattern which binds them: `{ a, b }` for named fields,
+/// and `(field_0, field_1)` for unnamed fields.
+fn bindings(fields: &syn::Fields) -> (Vec<syn::Ident>, proc_macro2::TokenStream) {
+	match fields {
+		syn::Fields::Named(fields) => {
+			let names =
+				fields.named.iter().filter_map(|field| field.ident.clone()).collect::<Vec<_>>();
+			let pattern = quote! { { #( #names ),* } };
+			(names, pattern)
+		},
+		syn::Fields::Unnamed(fields) => {
+			let names = (0..fields.unnamed.len())
+				.map(|index| format_ident!("field_{}", index))
+				.collect::<Vec<_>>();
+			let pattern = quote! { ( #( #names ),* ) };
+			(names, pattern)
+		},
+		syn::Fields::Unit => (vec![], quote! {}),
+	}
+}
+
+/// The expression which decodes each field in order, and constructs the value from them.
+fn construct(fields: &syn::Fields) -> proc_macro2::TokenStream {
+	match fields {
+		syn::Fields::Named(fields) => {
+			let names = fields.named.iter().map(|field| &field.ident);
+			quote! { { #( #names: crate::codec::Decode::decode(input)? ),* } }
+		},
+		syn::Fields::Unnamed(fields) => {
+			let decode =
+				fields.unnamed.iter().map(|_| quote! { crate::codec::Decode::decode(input)? });
+			quote! { ( #( #decode ),* ) }
+		},
+		syn::Fields::Unit => quote! {},
+	}
+}
@@ -0,0 +1,110 @@
+use proc_macro2::TokenTree;
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+
+/// See the `fn config` docs at the `lib.rs` of this crate for a high level definition.
+pub fn config(
+	_attr: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	let item = syn::parse_macro_input!(item as syn::Item);
+	expand(item).into()
+}
+
+/// Expand the config trait `item`, or give its errors. This is also used by `#[macros::pallet]`
+/// for its `#[pallet::config]` section.
+pub fn expand(item: syn::Item) -> proc_macro2::TokenStream {
+	match expand_config(item) {
+		Ok(generated) => generated,
+		Err(e) => e.to_compile_error(),
+	}
+}
+
+fn expand_config(item: syn::Item) -> syn::Result<proc_macro2::TokenStream> {
+	let item_trait = if let syn::Item::Trait(item) = item {
+		item
+	} else {
+		return Err(syn::Error::new(item.span(), "Invalid pallet::config, expected item trait"))
+	};
+	let trait_ident = &item_trait.ident;
+
+	// The type parameters of the config, like the instance `I` of `Config<I: Instance = ()>`, are
+	// also type parameters of the pallet, after `T`.
+	let (_, trait_generics, _) = item_trait.generics.split_for_impl();
+	let params = item_trait.generics.type_params().map(|param| {
+		let mut param = param.clone();
+		param.eq_token = None;
+		param.default = None;
+		param
+	});
+	let param_idents = item_trait.generics.type_params().map(|param| &param.ident);
+
+	// The constants of the config. Their values are only known for a given runtime, so they are
+	// read from `T`.
+	let constants = item_trait
+		.items
+		.iter()
+		.filter_map(|item| match item {
+			syn::TraitItem::Const(constant) => Some(constant),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+
+	// The types of the constants, named outside of the trait, like `T::Balance` for
+	// `Self::Balance`. The metadata describes them, so they must implement `TypeInfo`.
+	let constant_type = constants
+		.iter()
+		.map(|constant| replace_self(constant.ty.to_token_stream(), &quote! { T }))
+		.collect::<Vec<_>>();
+	let generics: syn::Generics =
+		syn::parse_quote!(<T: #trait_ident #trait_generics #( , #params )*>);
+	let generics = crate::type_info::with_bounds(&generics, &constant_type);
+	let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+	let metadata = constants.iter().zip(&constant_type).map(|(constant, ty)| {
+		let ident = &constant.ident;
+		let name = ident.to_string();
+		let docs = crate::pallet_enum::docs(&constant.attrs);
+		quote! {
+			crate::metadata::ConstantMetadata {
+				name: #name,
+				ty: registry.register::<#ty>(),
+				value: format!("{:?}", <T as #trait_ident #trait_generics>::#ident),
+				docs: #docs,
+			}
+		}
+	});
+
+	Ok(quote! {
+		#item_trait
+
+		impl #impl_generics Pallet<T #( , #param_idents )*> #where_clause {
+			/// The metadata of the constants of the config of the pallet, with their values in `T`.
+			pub fn constants_metadata(
+				registry: &mut crate::metadata::TypeRegistry,
+			) -> Vec<crate::metadata::ConstantMetadata> {
+				vec![ #( #metadata ),* ]
+			}
+		}
+	})
+}
+
+/// `tokens` with every `Self` replaced by `with`.
+fn replace_self(
+	tokens: proc_macro2::TokenStream,
+	with: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+	tokens
+		.into_iter()
+		.map(|token| match token {
+			TokenTree::Ident(ident) if ident == "Self" => with.clone(),
+			TokenTree::Group(group) => {
+				let mut replaced =
+					proc_macro2::Group::new(group.delimiter(), replace_self(group.stream(), with));
+				replaced.set_span(group.span());
+				TokenTree::Group(replaced).into()
+			},
+			token => token.into(),
+		})
+		.collect()
+}
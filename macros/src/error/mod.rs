@@ -0,0 +1,89 @@
+use crate::pallet_enum::{self, PalletEnumDef};
+use quote::quote;
+use syn::spanned::Spanned;
+
+/// See the `fn error` docs at the `lib.rs` of this crate for a high level definition.
+pub fn error(
+	_attr: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	let item = syn::parse_macro_input!(item as syn::Item);
+	expand(item, crate::pallet::module_name(false)).into()
+}
+
+/// Expand the `Error` enum `item`, or give its errors. This is also used by `#[macros::pallet]`
+/// for its `#[pallet::error]` section. `module_name` is the name of the pallet of the errors, see
+/// `pallet::module_name`.
+pub fn expand(item: syn::Item, module_name: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+	let def = PalletEnumDef::try_from(item, "Error");
+	match def.and_then(|def| expand_error(def, module_name)) {
+		Ok(generated) => generated,
+		Err(e) => e.to_compile_error(),
+	}
+}
+
+fn expand_error(
+	def: PalletEnumDef,
+	module_name: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+	// Errors are identified by their index alone, so they cannot hold any data.
+	for variant in &def.item_enum.variants {
+		if !matches!(variant.fields, syn::Fields::Unit) {
+			return Err(syn::Error::new(variant.fields.span(), "errors cannot have fields"))
+		}
+	}
+
+	let common = pallet_enum::expand_common(&def);
+	let (impl_generics, ty_generics, where_clause) = def.item_enum.generics.split_for_impl();
+
+	Ok(quote! {
+		#common
+
+		impl #impl_generics core::fmt::Debug for Error #ty_generics #where_clause {
+			fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+				f.write_str(self.name())
+			}
+		}
+
+		impl #impl_generics core::clone::Clone for Error #ty_generics #where_clause {
+			fn clone(&self) -> Self {
+				*self
+			}
+		}
+
+		impl #impl_generics core::marker::Copy for Error #ty_generics #where_clause {}
+
+		// Errors are equal when everything that identifies them is, not only their index.
+		impl #impl_generics core::cmp::PartialEq for Error #ty_generics #where_clause {
+			fn eq(&self, other: &Self) -> bool {
+				let module_error = crate::support::ModuleError::from;
+				module_error(*self) == module_error(*other)
+			}
+		}
+
+		impl #impl_generics core::cmp::Eq for Error #ty_generics #where_clause {}
+
+		// The pallet of an error is the name of the module it is declared in, like for the prefix
+		// of storage items.
+		impl #impl_generics From<Error #ty_generics> for crate::support::ModuleError
+		#where_clause
+		{
+			fn from(error: Error #ty_generics) -> Self {
+				crate::support::ModuleError {
+					pallet: #module_name,
+					index: error.index(),
+					name: error.name(),
+					description: error.description(),
+				}
+			}
+		}
+
+		impl #impl_generics From<Error #ty_generics> for crate::support::DispatchError
+		#where_clause
+		{
+			fn from(error: Error #ty_generics) -> Self {
+				crate::support::DispatchError::Module(error.into())
+			}
+		}
+	})
+}
@@ -0,0 +1,145 @@
+use crate::pallet_enum::{self, PalletEnumDef};
+use quote::quote;
+
+/// See the `fn event` docs at the `lib.rs` of this crate for a high level definition.
+pub fn event(
+	_attr: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	let item = syn::parse_macro_input!(item as syn::Item);
+	expand(item).into()
+}
+
+/// Expand the `Event` enum `item`, or give its errors. This is also used by `#[macros::pallet]`
+/// for its `#[pallet::event]` section.
+pub fn expand(item: syn::Item) -> proc_macro2::TokenStream {
+	match PalletEnumDef::try_from(item, "Event").and_then(expand_event) {
+		Ok(generated) => generated,
+		Err(e) => e.to_compile_error(),
+	}
+}
+
+fn expand_event(def: PalletEnumDef) -> syn::Result<proc_macro2::TokenStream> {
+	// Events are deposited into system through the runtime, which is the first type parameter.
+	let runtime = match def.type_params.first() {
+		Some(runtime) => runtime.clone(),
+		None => {
+			let msg = "events must be generic over the pallet config, like `Event<T: Config>`";
+			return Err(syn::Error::new(def.item_enum.ident.span(), msg))
+		},
+	};
+
+	let common = pallet_enum::expand_common(&def);
+	let generics = &def.item_enum.generics;
+	let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+	let ignore_arm = def.ignore_arm();
+
+	// `Debug`, `Clone` and `PartialEq` are implemented by hand, since deriving them would require
+	// the runtime to implement them too. Instead, they require the types of the fields to implement
+	// them.
+	let field_types = def
+		.variants
+		.iter()
+		.flat_map(|variant| variant.fields.iter().map(|field| &field.ty))
+		.collect::<Vec<_>>();
+	let bounded = |bound: proc_macro2::TokenStream| {
+		let mut generics = generics.clone();
+		let where_clause = generics.make_where_clause();
+		for ty in &field_types {
+			where_clause.predicates.push(syn::parse_quote!(#ty: #bound));
+		}
+		generics
+	};
+	let debug_generics = bounded(quote! { core::fmt::Debug });
+	let debug_where = &debug_generics.where_clause;
+	let clone_generics = bounded(quote! { core::clone::Clone });
+	let clone_where = &clone_generics.where_clause;
+	let eq_generics = bounded(quote! { core::cmp::Eq });
+	let eq_where = &eq_generics.where_clause;
+
+	let variant = def.variants.iter().map(|variant| &variant.ident).collect::<Vec<_>>();
+	let (names, pattern): (Vec<_>, Vec<_>) =
+		def.variants.iter().map(|variant| pallet_enum::bindings(&variant.fields, "field")).unzip();
+	let (other_names, other_pattern): (Vec<_>, Vec<_>) =
+		def.variants.iter().map(|variant| pallet_enum::bindings(&variant.fields, "other")).unzip();
+
+	let debug_arms = def.variants.iter().zip(&names).map(|(variant, names)| {
+		let ident = &variant.ident;
+		let name = ident.to_string();
+		match &variant.fields {
+			syn::Fields::Named(fields) => {
+				let field = fields.named.iter().filter_map(|field| field.ident.as_ref());
+				let field = field.map(|field| field.to_string());
+				quote! { f.debug_struct(#name) #( .field(#field, #names) )* .finish() }
+			},
+			syn::Fields::Unnamed(_) => {
+				quote! { f.debug_tuple(#name) #( .field(#names) )* .finish() }
+			},
+			syn::Fields::Unit => quote! { f.write_str(#name) },
+		}
+	});
+	let clone_construct = def.variants.iter().zip(&names).map(|(variant, names)| {
+		match &variant.fields {
+			syn::Fields::Named(fields) => {
+				let field = fields.named.iter().filter_map(|field| field.ident.as_ref());
+				quote! { { #( #field: #names.clone() ),* } }
+			},
+			syn::Fields::Unnamed(_) => quote! { ( #( #names.clone() ),* ) },
+			syn::Fields::Unit => quote! {},
+		}
+	});
+	let eq_exprs = names.iter().zip(&other_names).map(|(names, other_names)| {
+		if names.is_empty() {
+			quote! { true }
+		} else {
+			quote! { #( #names == #other_names )&&* }
+		}
+	});
+
+	Ok(quote! {
+		#common
+
+		impl #impl_generics core::fmt::Debug for Event #ty_generics #debug_where {
+			fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+				match self {
+					#( Self::#variant #pattern => #debug_arms, )*
+					#ignore_arm
+				}
+			}
+		}
+
+		impl #impl_generics core::clone::Clone for Event #ty_generics #clone_where {
+			fn clone(&self) -> Self {
+				match self {
+					#( Self::#variant #pattern => Self::#variant #clone_construct, )*
+					#ignore_arm
+				}
+			}
+		}
+
+		impl #impl_generics core::cmp::PartialEq for Event #ty_generics #eq_where {
+			fn eq(&self, other: &Self) -> bool {
+				match (self, other) {
+					#(
+						(Self::#variant #pattern, Self::#variant #other_pattern) => #eq_exprs,
+					)*
+					_ => false,
+				}
+			}
+		}
+
+		impl #impl_generics core::cmp::Eq for Event #ty_generics #eq_where {}
+
+		impl #impl_generics Pallet #ty_generics #where_clause {
+			/// Deposit an event of this pallet into system, for the current block.
+			pub fn deposit_event(runtime: &mut #runtime, event: Event #ty_generics)
+			where
+				#runtime: crate::support::GetPallet<crate::system::Pallet<#runtime>>,
+				<#runtime as crate::system::Config>::RuntimeEvent: From<Event #ty_generics>,
+			{
+				let system: &mut crate::system::Pallet<#runtime> = runtime.pallet_mut();
+				system.deposit_event(event.into());
+			}
+		}
+	})
+}
@@ -0,0 +1,67 @@
+//! Parsing of the explicit indices of calls and pallets, given with `#[call_index(n)]` and
+//! `#[pallet_index(n)]`, which identify them in the encoding of `Call` and `RuntimeCall`.
+
+use proc_macro2::Span;
+
+/// Remove the `#[name(n)]` attribute from `attrs`, and return its index and the span of the index,
+/// if there was one.
+pub fn take_index_attr(
+	attrs: &mut Vec<syn::Attribute>,
+	name: &str,
+) -> syn::Result<Option<(u8, Span)>> {
+	let mut index = None;
+	let mut errors: Option<syn::Error> = None;
+	attrs.retain(|attr| {
+		if !attr.path().is_ident(name) {
+			return true
+		}
+		let parsed = attr
+			.parse_args::<syn::LitInt>()
+			.and_then(|lit| Ok((lit.base10_parse::<u8>()?, lit.span())));
+		let error = match parsed {
+			Ok(_) if index.is_some() => {
+				let msg = format!("duplicate `#[{}]` attribute", name);
+				syn::Error::new_spanned(attr, msg)
+			},
+			Ok(parsed) => {
+				index = Some(parsed);
+				return false
+			},
+			Err(e) => {
+				let msg = format!("expected `#[{}(n)]`, where `n` is a `u8`", name);
+				let mut error = syn::Error::new_spanned(attr, msg);
+				error.combine(e);
+				error
+			},
+		};
+		match &mut errors {
+			Some(errors) => errors.combine(error),
+			None => errors = Some(error),
+		}
+		false
+	});
+	match errors {
+		Some(errors) => Err(errors),
+		None => Ok(index),
+	}
+}
+
+/// Check that no two items have the same index. `items` are the name of each item, its index, and
+/// the span of its index, which is where the error points to.
+pub fn check_unique(kind: &str, items: &[(&syn::Ident, u8, Span)]) -> syn::Result<()> {
+	let mut errors: Option<syn::Error> = None;
+	for (position, (_, index, span)) in items.iter().enumerate() {
+		if let Some((other, ..)) = items[..position].iter().find(|(_, other, _)| other == index) {
+			let msg = format!("{} index {} is already used by `{}`", kind, index, other);
+			let error = syn::Error::new(*span, msg);
+			match &mut errors {
+				Some(errors) => errors.combine(error),
+				None => errors = Some(error),
+			}
+		}
+	}
+	match errors {
+		Some(errors) => Err(errors),
+		None => Ok(()),
+	}
+}
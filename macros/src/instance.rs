@@ -0,0 +1,16 @@
+//! Support for instantiable pallets, which are generic over an instance `I: Instance = ()` after
+//! `T`. See `support::Instance`.
+
+/// The instance parameter of `generics`, which is the type parameter bounded by `Instance`.
+///
+/// Like the types of storage items, the bound is matched by the name of its last segment, so it can
+/// be imported or written as a path like `crate::support::Instance`.
+pub fn instance_param(generics: &syn::Generics) -> Option<&syn::TypeParam> {
+	generics.type_params().find(|param| {
+		param.bounds.iter().any(|bound| match bound {
+			syn::TypeParamBound::Trait(bound) =>
+				bound.path.segments.last().is_some_and(|segment| segment.ident == "Instance"),
+			_ => false,
+		})
+	})
+}
@@ -1,6 +1,52 @@
 mod call;
+mod codec;
+mod config;
+mod error;
+mod event;
+mod index;
+mod instance;
+mod pallet;
+mod pallet_enum;
 mod runtime;
+mod storage;
+mod type_info;
 
+/// Expand the callable functions of a pallet.
+///
+/// This generates:
+/// - `enum Call<T>` - an enum with one variant per function in the `impl` block, holding the
+///   function arguments except for the first two. It has the generics and the where clause of the
+///   `impl` block, and the instance parameter of instantiable pallets defaults to `()`, like in
+///   `Pallet<T, I = ()>`. A hidden variant uses the type parameters, for pallets whose calls do not
+///   otherwise mention them.
+/// - `fn Call::dispatch()` - which calls the right function with the origin and the arguments.
+/// - implementations of `Clone` and `Hash` for `Call<T>`, so calls can be stored and identified by
+///   their hash, of `Debug`, `PartialEq` and `Eq`, so calls can be printed and compared, of
+///   `codec::Encode` and `codec::Decode`, so calls can be stored as bytes, and of
+///   `support::GetWeight` and `support::Inherents`. They require the types of the arguments to
+///   implement them, but not the type parameters.
+/// - `fn Call::metadata()` - the name, the arguments and the doc comment of each call, by index,
+///   and an implementation of `metadata::TypeInfo` for `Call<T>`, which describes it by the same
+///   variants. The types of the arguments are registered in the registry of types, so they must
+///   implement `metadata::TypeInfo`.
+///
+/// The runtime must be the first type parameter of the `impl` block, `T`. The first argument of
+/// every function is either a variant of `self`, or `runtime: &mut T` for functions which need
+/// access to other pallets or need to dispatch calls themselves. The second
+/// argument is either `caller: T::AccountId`, for functions which can only be called by a signed
+/// origin, or `origin: Origin<T>` for functions which check the origin themselves. The origin is a
+/// `support::RawOrigin` of `T::AccountId`, so the pallet can use whichever system pallet its config
+/// extends.
+///
+/// Every function must be marked with `#[call_index(n)]`, where `n` is a `u8` which identifies the
+/// call in the encoding of `Call<T>`, and in the metadata. Indices must be unique within a pallet.
+///
+/// Functions marked with `#[inherent]` are inherents, which every block includes exactly once,
+/// unsigned, like the time of the block. They must take `origin: Origin<T>`.
+///
+/// Other arguments of type `T::AccountId` are held in `Call<T>` as a `support::Lookup` address of
+/// `T`, and resolved to accounts when the call is dispatched, so `T` must implement
+/// `support::Lookup`.
 #[proc_macro_attribute]
 pub fn call(
 	attr: proc_macro::TokenStream,
@@ -14,17 +60,63 @@ pub fn call(
 /// This generates function implementations on `Runtime`:
 /// - `fn new()` - which generates a new instance of the runtime, by instantiating all the pallets
 ///   included in the runtime.
+/// - `fn from_genesis()` - which creates the runtime like `fn new()`, and sets the initial state of
+///   every pallet marked with `#[genesis]` from its `GenesisConfig`, with `support::BuildGenesis`.
+///   The genesis configs are the fields of the generated `struct RuntimeGenesisConfig`, named like
+///   the pallets.
 /// - `fn execute_block()` - which handles basic logic for executing a block of extrinsics. It does
 ///   basic actions like incrementing the block number and checking the block to be executed has a
-///   valid block number.
+///   valid block number, and includes every inherent exactly once, unsigned, with no other
+///   unsigned extrinsics, before anything is changed. It also calls the `support::Hooks` of every
+///   pallet. Extrinsics without a caller are dispatched with no origin, and signed extrinsics
+///   first resolve their caller through `support::Lookup`, and pay their fee through
+///   `support::ChargeTransaction`, both of which the runtime must implement. Failing extrinsics
+///   and hooks do not make the block invalid, and are returned in a
+///   `support::BlockReport`, with the extrinsics which were applied and the weight used by the
+///   block.
 ///
 /// This also generates code needed for dispatching calls to the pallets:
 /// - Note: For simplicity, we assume that the system pallet is not callable.
 /// - `enum RuntimeCall` - an "outer"-enum representing the accumulation of all possible calls to
-///   all pallets. The system pallet is not included.
+///   all pallets. The system pallet is not included. It derives `Clone`, `Debug`, `PartialEq` and
+///   `Eq`, and implements `support::Inherents` with the inherents of every pallet.
 /// - implements the trait `support::Dispatch` to dispatch calls to the appropriate pallet. Basic
 ///   logic like incrementing the nonce of the user is included in the generated code. The system
 ///   pallet is not included.
+/// - implements `codec::Encode`, `codec::Decode` and `metadata::TypeInfo` for `RuntimeCall`.
+/// - `fn metadata()` - the metadata of every pallet, including system, from the metadata generated
+///   by the other macros of this crate. Pallets marked with `#[error]` must declare their errors
+///   with `#[macros::error]`, and their errors are included too. The types they refer to are
+///   collected in a registry of types, see `metadata`.
+/// - `enum RuntimeEvent` - the "outer"-enum of the events of the pallets marked with `#[event]`,
+///   which must declare their events with `#[macros::event]`, and `From` their events, and
+///   `metadata::TypeInfo` for it, which describes each variant with the index of its pallet. The
+///   events deposited in system are removed at the start of each block.
+///
+/// Finally, this implements `support::GetPallet` on `Runtime` for every pallet, including system,
+/// so that pallets can access each other through the runtime.
+///
+/// The markers of each pallet are checked against its `support::PalletInfo` when the runtime is
+/// compiled: a pallet marked with `#[event]`, `#[error]` or `#[genesis]` must have an `Event`, an
+/// `Error` or a `GenesisConfig`, and a pallet which has an `Error` must be marked with `#[error]`.
+///
+/// The `Call`, `Event` and `Error` of each pallet are found in the module of its type, with the
+/// same generic arguments, so a runtime can include several instances of a pallet, like
+/// `balances::Pallet<Self>` and `balances::Pallet<Self, Instance1>`.
+///
+/// Every pallet except system must be marked with `#[pallet_index(n)]`, where `n` is a `u8` which
+/// identifies the pallet in the encoding of `RuntimeCall`, and in the metadata. Indices must be
+/// unique within the runtime.
+///
+/// The system pallet is the field marked with `#[pallet(system)]`, or the field named `system`,
+/// anywhere in the struct. The macro takes optional arguments, like
+/// `#[macros::runtime(block = types::Block, system = system)]`:
+/// - `block` - the type of the blocks given to `execute_block()`, `types::Block` by default.
+/// - `system` - the module of the system pallet, which has its `Origin`. By default, it is the
+///   module in the type of the system pallet, so it is only needed when the type is imported, like
+///   `SystemPallet<Self>`. The `support`, `metadata` and `codec` modules which the generated code
+///   refers to are found next to it, like `frame::support` for `frame::system`, or at the root of
+///   the crate for a module without a parent, like `system`.
 #[proc_macro_attribute]
 pub fn runtime(
 	attr: proc_macro::TokenStream,
@@ -32,3 +124,145 @@ pub fn runtime(
 ) -> proc_macro::TokenStream {
 	runtime::runtime(attr, item)
 }
+
+/// Expand the storage of a pallet.
+///
+/// Every field of the pallet struct must be a storage item from the `storage` module: a
+/// `StorageValue`, a `StorageMap` or a `StorageDoubleMap`, with their hashers, query kind and
+/// default value as generic arguments. This generates:
+/// - `fn new()` - which creates the pallet with all of its items empty, giving each item a unique
+///   prefix from the name of the module of the pallet and the name of the field. For instantiable
+///   pallets, the `support::Instance::PREFIX` of the instance is added to the name of the module.
+/// - a getter for every field marked with `#[getter(name)]`, which reads the item with its query
+///   kind, taking the keys of maps by reference.
+/// - a setter for every field marked with `#[setter(name)]`, which inserts a value.
+/// - `fn storage_metadata()` - the kind, the hashers, the types and the doc comment of every item.
+///   The types of the keys and values must implement `metadata::TypeInfo`.
+///
+/// Iteration and other helpers are methods of the storage items themselves.
+#[proc_macro_attribute]
+pub fn storage(
+	attr: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	storage::storage(attr, item)
+}
+
+/// Expand the events of a pallet, declared as `enum Event<T: Config>`.
+///
+/// The index of each event is its position in the enum, so new events must be added at the end.
+/// This generates:
+/// - `const Event::VARIANTS` - the name and the description of each event, by index, where the
+///   description is the doc comment of the event. With `fn index()`, `fn name()` and
+///   `fn description()`, they describe an event.
+/// - `fn Event::metadata()` - the name, the fields and the doc comment of each event, by index,
+///   and an implementation of `metadata::TypeInfo` for `Event<T>`, which describes it by the same
+///   variants. The types of the fields must implement `metadata::TypeInfo`.
+/// - implementations of `Debug`, `Clone`, `PartialEq` and `Eq` for `Event<T>`, which require the
+///   types of the fields to implement them, but not `T`.
+/// - `fn Pallet::deposit_event()` - which deposits an event into the system pallet through the
+///   runtime. It requires the `RuntimeEvent` of the runtime to implement `From<Event<T>>`, which
+///   `#[macros::runtime]` does for every pallet marked with `#[event]`.
+///
+/// A hidden variant uses the type parameters, for events which do not otherwise mention them.
+#[proc_macro_attribute]
+pub fn event(
+	attr: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	event::event(attr, item)
+}
+
+/// Expand the errors of a pallet, declared as `enum Error<T>` with variants without fields.
+///
+/// Like events, the index of each error is its position in the enum, and its description is its
+/// doc comment. This generates the same `VARIANTS` and methods as `#[macros::event]`, and:
+/// - implementations of `Debug`, `Clone`, `Copy`, `PartialEq` and `Eq` for `Error<T>`. Two errors
+///   are equal when their whole `support::ModuleError` is.
+/// - `From<Error<T>>` for `support::ModuleError` and `support::DispatchError`, so that calls can
+///   return errors with `?` or `.into()`. The error is a `DispatchError::Module`, which identifies
+///   the pallet by the name of its module.
+///
+/// Declaring errors is optional: pallets without an `Error` enum return messages, which become a
+/// `DispatchError::Other`.
+#[proc_macro_attribute]
+pub fn error(
+	attr: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	error::error(attr, item)
+}
+
+/// Expand the config trait of a pallet.
+///
+/// This keeps the trait as it is, and generates `fn Pallet::constants_metadata()` - the name, the
+/// type and the doc comment of every constant of the trait, with its value in a given runtime
+/// formatted with `Debug`. Every constant must implement `Debug` and `metadata::TypeInfo`.
+#[proc_macro_attribute]
+pub fn config(
+	attr: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	config::config(attr, item)
+}
+
+/// Expand a whole pallet, declared as a module with one item per section, like
+/// `#[macros::pallet] pub mod pallet { .. }`, which the file of the pallet re-exports with
+/// `pub use pallet::*`.
+///
+/// Each section is an item of the module marked with `#[pallet::name]`, and is expanded like the
+/// macro of its own:
+/// - `#[pallet::config]` - the config trait, like `#[macros::config]`. If the pallet has events,
+///   the trait also requires the `RuntimeEvent` of system to convert from them.
+/// - `#[pallet::storage]` - the `Pallet` struct, like `#[macros::storage]`.
+/// - `#[pallet::call]` - the `impl Pallet` block of callable functions, like `#[macros::call]`.
+///   Pallets without one get a `Call` without variants, since the runtime needs a `Call` for every
+///   pallet.
+/// - `#[pallet::event]` and `#[pallet::error]` - the `Event` and `Error` enums, like
+///   `#[macros::event]` and `#[macros::error]`.
+/// - `#[pallet::hooks]` - the implementation of `support::Hooks` for `Pallet`. Pallets without one
+///   get hooks which do nothing.
+/// - `#[pallet::genesis_config]` and `#[pallet::genesis_build]` - the `GenesisConfig` struct, and
+///   its implementation of `support::BuildGenesis`, which sets the initial state of the pallet when
+///   the runtime is created with `Runtime::from_genesis()`. They go together.
+///
+/// The config and the storage are required, and every section can be given at most once. Other
+/// items of the module, like imports and helper functions, are kept as they are. The name of the
+/// pallet, in the prefixes of its storage items and in its errors, is the name of the module
+/// around the module of the sections, like `balances` for `balances::pallet`. The runtime
+/// includes the pallet like any other, marked with `#[event]`, `#[error]` and `#[genesis]` for the
+/// sections it has, which the macro records in its implementation of `support::PalletInfo`. The
+/// macro takes no arguments.
+#[proc_macro_attribute]
+pub fn pallet(
+	attr: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	pallet::pallet(attr, item)
+}
+
+/// Derive `codec::Encode` for a struct or an enum.
+///
+/// Struct fields are encoded in order. Enums encode the index of their variant as a `u8`, followed
+/// by the fields of the variant. Every type parameter must implement `codec::Encode`.
+#[proc_macro_derive(Encode)]
+pub fn derive_encode(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	codec::derive_encode(item)
+}
+
+/// Derive `codec::Decode` for a struct or an enum, matching `#[derive(Encode)]`.
+#[proc_macro_derive(Decode)]
+pub fn derive_decode(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	codec::derive_decode(item)
+}
+
+/// Derive `metadata::TypeInfo` for a struct or an enum, so that it can be described in the
+/// metadata.
+///
+/// Structs are described as composites of their fields, and enums as variants, indexed by their
+/// position like in `#[derive(Encode)]`. The types of every field must implement
+/// `metadata::TypeInfo`.
+#[proc_macro_derive(TypeInfo)]
+pub fn derive_type_info(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	type_info::derive_type_info(item)
+}
@@ -0,0 +1,80 @@
+use super::parse::PalletDef;
+use quote::quote;
+
+/// See the `fn pallet` docs at the `lib.rs` of this crate for a high level definition.
+pub fn expand_pallet(def: PalletDef) -> proc_macro2::TokenStream {
+	let PalletDef { mut item_mod, mut config, storage, call, event, error, hooks, genesis } = def;
+
+	// Pallets with events deposit them into system through the runtime, so the config requires
+	// the `RuntimeEvent` of the runtime to convert from them, like `Event<Self>`, which needs the
+	// runtime to be `Sized`.
+	if let Some(event) = &event {
+		let mut params = event.generics.type_params().map(|param| &param.ident);
+		if params.next().is_some() {
+			config.supertraits.push(syn::parse_quote!(Sized));
+			config.supertraits.push(syn::parse_quote! {
+				crate::system::Config<RuntimeEvent: From<Event<Self #( , #params )*>>>
+			});
+		}
+	}
+
+	// The runtime calls the hooks of every pallet, and dispatches the calls of every pallet, so
+	// pallets without them get empty ones, with the generics of the pallet struct.
+	let (impl_generics, ty_generics, where_clause) = storage.generics.split_for_impl();
+	let hooks = match (hooks, storage.generics.type_params().next()) {
+		(Some(hooks), _) => quote! { #hooks },
+		(None, Some(runtime)) => {
+			let runtime = &runtime.ident;
+			quote! {
+				impl #impl_generics crate::support::Hooks<#runtime> for Pallet #ty_generics
+					#where_clause {}
+			}
+		},
+		// The storage macro gives an error for pallet structs without a runtime.
+		(None, None) => quote! {},
+	};
+
+	// The runtime checks its markers of the pallet against the sections it has. See
+	// `support::PalletInfo`.
+	let (has_event, has_error, has_genesis) = (event.is_some(), error.is_some(), genesis.is_some());
+	let pallet_info = quote! {
+		impl #impl_generics crate::support::PalletInfo for Pallet #ty_generics #where_clause {
+			const HAS_EVENT: bool = #has_event;
+			const HAS_ERROR: bool = #has_error;
+			const HAS_GENESIS: bool = #has_genesis;
+		}
+	};
+
+	let call = call.unwrap_or_else(|| {
+		syn::parse_quote! {
+			impl #impl_generics Pallet #ty_generics #where_clause {}
+		}
+	});
+
+	// Each section is expanded by the macro of its own, like `#[macros::call]` for the calls.
+	let mut sections = vec![
+		crate::config::expand(syn::Item::Trait(config)),
+		crate::storage::expand(syn::Item::Struct(storage), super::module_name(true)),
+		crate::call::expand(syn::Item::Impl(call)),
+		hooks,
+		pallet_info,
+	];
+	if let Some(event) = event {
+		sections.push(crate::event::expand(syn::Item::Enum(event)));
+	}
+	if let Some(error) = error {
+		sections.push(crate::error::expand(syn::Item::Enum(error), super::module_name(true)));
+	}
+	if let Some((genesis_config, genesis_build)) = genesis {
+		sections.push(quote! {
+			#genesis_config
+			#genesis_build
+		});
+	}
+
+	// The expanded sections are added to the end of the module, after its other items.
+	if let Some((_, items)) = &mut item_mod.content {
+		items.extend(sections.into_iter().map(syn::Item::Verbatim));
+	}
+	quote! { #item_mod }
+}
@@ -0,0 +1,47 @@
+pub mod expand;
+pub mod parse;
+
+/// The name of the pallet, as an expression of the generated code. It is the name of the module
+/// the code is in, like `balances`, except for the sections of `#[macros::pallet]`, which are
+/// `nested` in a module of the pallet, like `balances::pallet`, and use the name of the module
+/// around it.
+pub fn module_name(nested: bool) -> proc_macro2::TokenStream {
+	let skip = usize::from(nested);
+	quote::quote! { module_path!().rsplit("::").nth(#skip).unwrap_or_default() }
+}
+
+/// See the `fn pallet` docs at the `lib.rs` of this crate for a high level definition.
+pub fn pallet(
+	attr: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	let item_mod = syn::parse_macro_input!(item as syn::Item);
+
+	// On errors, we return the module without its items, so that the errors are not hidden by
+	// errors about the module going missing, or about the `#[pallet::name]` attributes of its
+	// sections, which are not real attributes.
+	let mut original = item_mod.clone();
+	if let syn::Item::Mod(syn::ItemMod { content: Some((_, items)), .. }) = &mut original {
+		items.clear();
+	}
+
+	// First we parse the sections of the pallet, which are all the macro takes...
+	let parsed = if attr.is_empty() {
+		parse::PalletDef::try_from(item_mod)
+	} else {
+		let attr = proc_macro2::TokenStream::from(attr);
+		Err(syn::Error::new_spanned(attr, "expected `#[macros::pallet]`, without arguments"))
+	};
+	match parsed {
+		// ..then we generate our new code.
+		Ok(def) => expand::expand_pallet(def).into(),
+		Err(e) => {
+			let error = e.to_compile_error();
+			quote::quote! {
+				#original
+				#error
+			}
+			.into()
+		},
+	}
+}
@@ -0,0 +1,252 @@
+use syn::spanned::Spanned;
+
+/// The sections of a pallet, which are the items of its module marked with `#[pallet::name]`.
+const SECTIONS: &[&str] =
+	&["config", "storage", "call", "event", "error", "hooks", "genesis_config", "genesis_build"];
+
+/// This object will collect all the information we need to keep while parsing the module of a
+/// pallet.
+#[derive(Debug)]
+pub struct PalletDef {
+	/// The module of the pallet, with the items which are not sections, like imports and helper
+	/// functions, which are kept as they are.
+	pub item_mod: syn::ItemMod,
+	/// The config trait, from `#[pallet::config]`.
+	pub config: syn::ItemTrait,
+	/// The pallet struct and its storage items, from `#[pallet::storage]`.
+	pub storage: syn::ItemStruct,
+	/// The callable functions, from `#[pallet::call]`. If there are none, the pallet gets a `Call`
+	/// without variants, since the runtime needs one for every pallet.
+	pub call: Option<syn::ItemImpl>,
+	/// The `Event` enum, from `#[pallet::event]`.
+	pub event: Option<syn::ItemEnum>,
+	/// The `Error` enum, from `#[pallet::error]`.
+	pub error: Option<syn::ItemEnum>,
+	/// The implementation of `support::Hooks`, from `#[pallet::hooks]`. If there is none, the
+	/// pallet gets one which does nothing.
+	pub hooks: Option<syn::ItemImpl>,
+	/// The `GenesisConfig` struct and its implementation of `support::BuildGenesis`, from
+	/// `#[pallet::genesis_config]` and `#[pallet::genesis_build]`, which come together.
+	pub genesis: Option<(syn::ItemStruct, syn::ItemImpl)>,
+}
+
+impl PalletDef {
+	pub fn try_from(item: syn::Item) -> syn::Result<Self> {
+		// First we check that we are parsing a module with its items inline.
+		let mut item_mod = if let syn::Item::Mod(item) = item {
+			item
+		} else {
+			return Err(syn::Error::new(item.span(), "Invalid pallet, expected item mod"))
+		};
+		let items = match &mut item_mod.content {
+			Some((_, items)) => std::mem::take(items),
+			None => {
+				let msg = "Invalid pallet, expected a module with its items inline, like \
+					`pub mod pallet { .. }`";
+				return Err(syn::Error::new(item_mod.span(), msg))
+			},
+		};
+
+		// Then we sort the items into their sections. Items which are not sections are kept in
+		// the module.
+		let mut sections = Sections::default();
+		let mut others = vec![];
+		for mut item in items {
+			match take_section_attr(&mut item)? {
+				Some((name, attr)) => sections.insert(&name, attr, item)?,
+				None => others.push(item),
+			}
+		}
+		if let Some((_, items)) = &mut item_mod.content {
+			*items = others;
+		}
+
+		// Every pallet needs a config and a pallet struct, which the runtime refers to.
+		let missing = |name: &str, what: &str| {
+			let msg = format!("missing `#[pallet::{}]` section, every pallet needs {}", name, what);
+			syn::Error::new(item_mod.ident.span(), msg)
+		};
+		let config = sections.config.ok_or_else(|| missing("config", "a config trait"))?;
+		let storage = sections.storage.ok_or_else(|| missing("storage", "a `Pallet` struct"))?;
+
+		// The generated code of the other macros and of the runtime refers to the items of the
+		// pallet by name.
+		if storage.ident != "Pallet" {
+			let msg = "expected the pallet struct to be named `Pallet`";
+			return Err(syn::Error::new(storage.ident.span(), msg))
+		}
+		if let Some(call) = &sections.call {
+			check_impl(call, None, "Pallet", "call")?;
+		}
+		if let Some(hooks) = &sections.hooks {
+			check_impl(hooks, Some("Hooks"), "Pallet", "hooks")?;
+		}
+
+		// The genesis config is only useful with a way to build it, and the other way around.
+		let genesis = match (sections.genesis_config, sections.genesis_build) {
+			(Some(genesis_config), Some(genesis_build)) => {
+				if genesis_config.ident != "GenesisConfig" {
+					let msg = "expected the genesis config to be named `GenesisConfig`";
+					return Err(syn::Error::new(genesis_config.ident.span(), msg))
+				}
+				check_impl(&genesis_build, Some("BuildGenesis"), "GenesisConfig", "genesis_build")?;
+				Some((genesis_config, genesis_build))
+			},
+			(Some(genesis_config), None) => {
+				let msg = "missing `#[pallet::genesis_build]` section, which implements \
+					`BuildGenesis` for the `GenesisConfig` of the pallet";
+				return Err(syn::Error::new(genesis_config.ident.span(), msg))
+			},
+			(None, Some(genesis_build)) => {
+				let msg = "missing `#[pallet::genesis_config]` section, which declares the \
+					`GenesisConfig` built by this implementation";
+				return Err(syn::Error::new(genesis_build.self_ty.span(), msg))
+			},
+			(None, None) => None,
+		};
+
+		Ok(Self {
+			item_mod,
+			config,
+			storage,
+			call: sections.call,
+			event: sections.event,
+			error: sections.error,
+			hooks: sections.hooks,
+			genesis,
+		})
+	}
+}
+
+/// The sections found in the module of a pallet, in any order.
+#[derive(Default)]
+struct Sections {
+	config: Option<syn::ItemTrait>,
+	storage: Option<syn::ItemStruct>,
+	call: Option<syn::ItemImpl>,
+	event: Option<syn::ItemEnum>,
+	error: Option<syn::ItemEnum>,
+	hooks: Option<syn::ItemImpl>,
+	genesis_config: Option<syn::ItemStruct>,
+	genesis_build: Option<syn::ItemImpl>,
+}
+
+impl Sections {
+	/// Add `item` as the section `name`, checking that it is the right kind of item, and that the
+	/// section is not already there. `attr` is the `#[pallet::name]` attribute, for errors.
+	fn insert(&mut self, name: &str, attr: syn::Attribute, item: syn::Item) -> syn::Result<()> {
+		let wrong_item = |kind: &str| {
+			let msg = format!("expected {} for `#[pallet::{}]`", kind, name);
+			syn::Error::new_spanned(&attr, msg)
+		};
+		let duplicate = match (name, item) {
+			("config", syn::Item::Trait(item)) => self.config.replace(item).is_some(),
+			("config", _) => return Err(wrong_item("a trait")),
+			("storage", syn::Item::Struct(item)) => self.storage.replace(item).is_some(),
+			("storage", _) => return Err(wrong_item("a struct")),
+			("call", syn::Item::Impl(item)) => self.call.replace(item).is_some(),
+			("call", _) => return Err(wrong_item("an impl block")),
+			("event", syn::Item::Enum(item)) => self.event.replace(item).is_some(),
+			("event", _) => return Err(wrong_item("an enum")),
+			("error", syn::Item::Enum(item)) => self.error.replace(item).is_some(),
+			("error", _) => return Err(wrong_item("an enum")),
+			("hooks", syn::Item::Impl(item)) => self.hooks.replace(item).is_some(),
+			("hooks", _) => return Err(wrong_item("an impl block")),
+			("genesis_config", syn::Item::Struct(item)) =>
+				self.genesis_config.replace(item).is_some(),
+			("genesis_config", _) => return Err(wrong_item("a struct")),
+			("genesis_build", syn::Item::Impl(item)) => self.genesis_build.replace(item).is_some(),
+			("genesis_build", _) => return Err(wrong_item("an impl block")),
+			_ => unreachable!("the name of the section is checked by `take_section_attr`; qed"),
+		};
+		if duplicate {
+			let msg = format!("duplicate `#[pallet::{}]` section", name);
+			return Err(syn::Error::new_spanned(attr, msg))
+		}
+		Ok(())
+	}
+}
+
+/// Remove the `#[pallet::name]` attribute from `item`, and return the name of the section and the
+/// attribute if there was one.
+fn take_section_attr(item: &mut syn::Item) -> syn::Result<Option<(String, syn::Attribute)>> {
+	let attrs = match item {
+		syn::Item::Const(item) => &mut item.attrs,
+		syn::Item::Enum(item) => &mut item.attrs,
+		syn::Item::Fn(item) => &mut item.attrs,
+		syn::Item::Impl(item) => &mut item.attrs,
+		syn::Item::Mod(item) => &mut item.attrs,
+		syn::Item::Static(item) => &mut item.attrs,
+		syn::Item::Struct(item) => &mut item.attrs,
+		syn::Item::Trait(item) => &mut item.attrs,
+		syn::Item::Type(item) => &mut item.attrs,
+		syn::Item::Use(item) => &mut item.attrs,
+		_ => return Ok(None),
+	};
+
+	let mut section = None;
+	let mut result = Ok(());
+	attrs.retain(|attr| {
+		let segments = &attr.path().segments;
+		if segments.len() != 2 || segments[0].ident != "pallet" {
+			return true
+		}
+		let name = segments[1].ident.to_string();
+		if !SECTIONS.contains(&name.as_str()) {
+			let expected = SECTIONS.iter().map(|name| format!("`{}`", name)).collect::<Vec<_>>();
+			let msg = format!(
+				"unknown pallet section `{}`, expected one of {}",
+				name,
+				expected.join(", ")
+			);
+			result = Err(syn::Error::new(segments[1].ident.span(), msg));
+		} else if let Err(e) = attr.meta.require_path_only() {
+			let msg = format!("expected `#[pallet::{}]`, without arguments", name);
+			let mut error = syn::Error::new_spanned(attr, msg);
+			error.combine(e);
+			result = Err(error);
+		} else if section.is_some() {
+			let msg = "an item can only be one section of a pallet";
+			result = Err(syn::Error::new_spanned(attr, msg));
+		}
+		section = Some((name, attr.clone()));
+		false
+	});
+	result.map(|()| section)
+}
+
+/// Check that the `impl` block of the section `name` is for the type `self_name`, and implements
+/// the trait `trait_name`, or is an inherent `impl` block if it is `None`.
+fn check_impl(
+	item_impl: &syn::ItemImpl,
+	trait_name: Option<&str>,
+	self_name: &str,
+	name: &str,
+) -> syn::Result<()> {
+	let last_ident = |path: &syn::Path| path.segments.last().map(|segment| segment.ident.clone());
+	let found_trait = item_impl.trait_.as_ref().and_then(|(_, path, _)| last_ident(path));
+	let found_self = match &*item_impl.self_ty {
+		syn::Type::Path(tp) if tp.qself.is_none() => last_ident(&tp.path),
+		_ => None,
+	};
+	let expected = match trait_name {
+		Some(trait_name) => format!("`impl {}<..> for {}<..>`", trait_name, self_name),
+		None => format!("`impl {}<..>`", self_name),
+	};
+	let msg = format!("expected {} for `#[pallet::{}]`", expected, name);
+	let trait_matches = match (trait_name, &found_trait) {
+		(Some(trait_name), Some(found)) => found == trait_name,
+		(None, None) => true,
+		_ => false,
+	};
+	if !trait_matches {
+		return Err(match &item_impl.trait_ {
+			Some((_, path, _)) => syn::Error::new_spanned(path, msg),
+			None => syn::Error::new_spanned(&item_impl.self_ty, msg),
+		})
+	}
+	if found_self.is_none_or(|found| found != self_name) {
+		return Err(syn::Error::new_spanned(&item_impl.self_ty, msg))
+	}
+	Ok(())
+}
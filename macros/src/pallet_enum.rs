@@ -0,0 +1,223 @@
+//! Parsing and expansion shared by `#[macros::event]` and `#[macros::error]`, which both declare an
+//! enum of a pallet whose variants have stable indices and descriptions.
+
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+
+/// This object will collect all the information we need to keep while parsing the enum.
+pub struct PalletEnumDef {
+	pub item_enum: syn::ItemEnum,
+	/// The type parameters of the enum, like `T` in `Event<T: Config>`.
+	pub type_params: Vec<syn::Ident>,
+	pub variants: Vec<VariantDef>,
+}
+
+pub struct VariantDef {
+	pub ident: syn::Ident,
+	pub fields: syn::Fields,
+	/// The index of the variant, which is its position in the enum.
+	pub index: u8,
+	/// The doc comment of the variant, one line per `///` line.
+	pub description: String,
+}
+
+impl PalletEnumDef {
+	/// Parse an enum which must be named `name`, since the generated code of other macros refers
+	/// to it by name.
+	pub fn try_from(item: syn::Item, name: &str) -> syn::Result<Self> {
+		let item_enum = if let syn::Item::Enum(item) = item {
+			item
+		} else {
+			let msg = format!("expected `enum {}`", name);
+			return Err(syn::Error::new(item.span(), msg))
+		};
+		if item_enum.ident != name {
+			let msg = format!("expected the enum to be named `{}`", name);
+			return Err(syn::Error::new(item_enum.ident.span(), msg))
+		}
+
+		let type_params =
+			item_enum.generics.type_params().map(|param| param.ident.clone()).collect();
+
+		let mut variants = vec![];
+		for (index, variant) in item_enum.variants.iter().enumerate() {
+			let index = u8::try_from(index).map_err(|_| {
+				let msg = "enums with more than 256 variants are not supported";
+				syn::Error::new(variant.span(), msg)
+			})?;
+			if let Some((_, discriminant)) = &variant.discriminant {
+				let msg = "the index of a variant is its position, it cannot have a discriminant";
+				return Err(syn::Error::new(discriminant.span(), msg))
+			}
+			variants.push(VariantDef {
+				ident: variant.ident.clone(),
+				fields: variant.fields.clone(),
+				index,
+				description: docs(&variant.attrs),
+			});
+		}
+
+		Ok(Self { item_enum, type_params, variants })
+	}
+
+	/// The match arm for the hidden variant, if there is one. See `expand_common`.
+	pub fn ignore_arm(&self) -> proc_macro2::TokenStream {
+		if self.type_params.is_empty() {
+			quote! {}
+		} else {
+			quote! { Self::__Ignore(_, never) => match *never {}, }
+		}
+	}
+}
+
+/// The doc comment in `attrs`, one line per `///` line.
+pub fn docs(attrs: &[syn::Attribute]) -> String {
+	let lines = attrs.iter().filter_map(|attr| match &attr.meta {
+		syn::Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+			syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(doc), .. }) => Some(doc.value()),
+			_ => None,
+		},
+		_ => None,
+	});
+	// `/// Text` is `#[doc = " Text"]`, so we remove the space after the slashes.
+	lines
+		.map(|line| line.strip_prefix(' ').map(str::to_string).unwrap_or(line))
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+/// Generate the enum, with a hidden variant which uses its type parameters, and the methods which
+/// describe its variants.
+pub fn expand_common(def: &PalletEnumDef) -> proc_macro2::TokenStream {
+	let mut item_enum = def.item_enum.clone();
+	let type_params = &def.type_params;
+	if !type_params.is_empty() {
+		// Uses the type parameters for enums whose variants do not otherwise mention them, like
+		// errors. This variant can never be constructed.
+		item_enum.variants.push(syn::parse_quote! {
+			#[doc(hidden)]
+			__Ignore(core::marker::PhantomData<(#( #type_params ),*)>, core::convert::Infallible)
+		});
+	}
+
+	let name = &item_enum.ident;
+	let (impl_generics, ty_generics, where_clause) = item_enum.generics.split_for_impl();
+	let variant = def.variants.iter().map(|variant| &variant.ident).collect::<Vec<_>>();
+	let variant_name = variant.iter().map(|ident| ident.to_string()).collect::<Vec<_>>();
+	let index = def.variants.iter().map(|variant| variant.index).collect::<Vec<_>>();
+	let description = def.variants.iter().map(|variant| &variant.description).collect::<Vec<_>>();
+	let ignore_arm = def.ignore_arm();
+	let fields = def.variants.iter().map(|variant| fields_metadata(&variant.fields));
+	let name_str = name.to_string();
+
+	// The metadata describes the fields of the variants, so their types must implement `TypeInfo`.
+	let field_types =
+		def.variants.iter().flat_map(|variant| variant.fields.iter().map(|field| &field.ty));
+	let type_info_generics = crate::type_info::with_bounds(&item_enum.generics, field_types);
+	let type_info_where = &type_info_generics.where_clause;
+
+	quote! {
+		#item_enum
+
+		// Not every pallet uses every one of these, but they describe the enum, for example in
+		// logs and metadata.
+		#[allow(dead_code)]
+		impl #impl_generics #name #ty_generics #where_clause {
+			/// The name and the description of each variant, by index.
+			pub const VARIANTS: &'static [(&'static str, &'static str)] = &[
+				#( (#variant_name, #description) ),*
+			];
+
+			/// The index of the variant, which is its position in the enum. New variants are added
+			/// at the end, so that the indices of the others do not change.
+			pub fn index(&self) -> u8 {
+				match self {
+					#( Self::#variant { .. } => #index, )*
+					#ignore_arm
+				}
+			}
+
+			pub fn name(&self) -> &'static str {
+				Self::VARIANTS[usize::from(self.index())].0
+			}
+
+			/// The doc comment of the variant.
+			pub fn description(&self) -> &'static str {
+				Self::VARIANTS[usize::from(self.index())].1
+			}
+		}
+
+		#[allow(dead_code)]
+		impl #impl_generics #name #ty_generics #type_info_where {
+			/// The metadata of each variant, by index.
+			pub fn metadata(
+				registry: &mut crate::metadata::TypeRegistry,
+			) -> Vec<crate::metadata::VariantMetadata> {
+				vec![
+					#(
+						crate::metadata::VariantMetadata {
+							name: #variant_name,
+							index: #index,
+							fields: #fields,
+							docs: #description,
+						}
+					),*
+				]
+			}
+		}
+
+		impl #impl_generics crate::metadata::TypeInfo for #name #ty_generics #type_info_where {
+			fn type_info(
+				registry: &mut crate::metadata::TypeRegistry,
+			) -> crate::metadata::TypeMetadata {
+				crate::metadata::TypeMetadata {
+					path: concat!(module_path!(), "::", #name_str),
+					def: crate::metadata::TypeDef::Variant(Self::metadata(registry)),
+				}
+			}
+		}
+	}
+}
+
+/// The metadata of the fields of a variant or a struct, as a `Vec`. The types of the fields are
+/// registered in `registry`.
+pub fn fields_metadata<'a>(
+	fields: impl IntoIterator<Item = &'a syn::Field>,
+) -> proc_macro2::TokenStream {
+	let fields = fields.into_iter().map(|field| {
+		let name = match &field.ident {
+			Some(ident) => {
+				let name = ident.to_string();
+				quote! { Some(#name) }
+			},
+			None => quote! { None },
+		};
+		let ty = &field.ty;
+		quote! {
+			crate::metadata::FieldMetadata { name: #name, ty: registry.register::<#ty>() }
+		}
+	});
+	quote! { vec![ #( #fields ),* ] }
+}
+
+/// The names bound to the fields of a variant, prefixed with `prefix`, and the pattern which binds
+/// them: `{ a: prefix_a }` for named fields, and `(prefix_0)` for unnamed fields.
+pub fn bindings(fields: &syn::Fields, prefix: &str) -> (Vec<syn::Ident>, proc_macro2::TokenStream) {
+	match fields {
+		syn::Fields::Named(fields) => {
+			let field = fields.named.iter().filter_map(|field| field.ident.as_ref());
+			let names = field.clone().map(|field| format_ident!("{}_{}", prefix, field));
+			let names = names.collect::<Vec<_>>();
+			let pattern = quote! { { #( #field: #names ),* } };
+			(names, pattern)
+		},
+		syn::Fields::Unnamed(fields) => {
+			let names = (0..fields.unnamed.len())
+				.map(|index| format_ident!("{}_{}", prefix, index))
+				.collect::<Vec<_>>();
+			let pattern = quote! { ( #( #names ),* ) };
+			(names, pattern)
+		},
+		syn::Fields::Unit => (vec![], quote! {}),
+	}
+}
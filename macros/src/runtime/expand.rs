@@ -1,47 +1,331 @@
 use super::parse::RuntimeDef;
-use quote::quote;
+use quote::{format_ident, quote};
 
 /// See the `fn runtime` docs at the `lib.rs` of this crate for a high level definition.
 pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
-	let RuntimeDef { runtime_struct, pallets } = def;
+	let RuntimeDef { item_struct, runtime_struct, system, block, pallets } = def;
+	let system_name = &system.name;
+	let system_name_str = system_name.to_string();
+	let system_type = &system.ty;
+	let system_module = &system.module;
+	let frame = system.frame();
 
 	// This is a vector of all the pallet names, not including system.
-	let pallet_names = pallets.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+	let pallet_names = pallets.iter().map(|pallet| pallet.name.clone()).collect::<Vec<_>>();
 	// This is a vector of all the pallet types, not including system.
-	let pallet_types = pallets.iter().map(|(_, type_)| type_.clone()).collect::<Vec<_>>();
+	let pallet_types = pallets.iter().map(|pallet| pallet.ty.clone()).collect::<Vec<_>>();
+
+	// This is a vector of the index of each pallet, from `#[pallet_index(n)]`, which identifies it
+	// in the encoding of a `RuntimeCall`.
+	let pallet_index = pallets.iter().map(|pallet| pallet.index).collect::<Vec<_>>();
+
+	// This is a vector of the `Call` types of the pallets, not including system. Like the other
+	// items of a pallet, they are in the module of the pallet, with the same generic arguments.
+	let call_types = pallets
+		.iter()
+		.map(|pallet| pallet.item_type("Call", &runtime_struct))
+		.collect::<Vec<_>>();
+
+	// This is a vector of the position of the first inherent of each pallet among the inherents of
+	// the runtime, which come in the order of the pallets. See `support::Inherents`.
+	let inherent_offsets = (0..call_types.len())
+		.map(|position| {
+			let previous = &call_types[..position];
+			quote! { 0 #( + <#previous as #frame::support::Inherents>::COUNT )* }
+		})
+		.collect::<Vec<_>>();
+
+	// These are vectors of the names and the `Event` types of the pallets marked with `#[event]`.
+	let event_pallets = pallets.iter().filter(|pallet| pallet.event).collect::<Vec<_>>();
+	let event_names = event_pallets.iter().map(|pallet| &pallet.name).collect::<Vec<_>>();
+	let event_types = event_pallets
+		.iter()
+		.map(|pallet| pallet.item_type("Event", &runtime_struct))
+		.collect::<Vec<_>>();
+
+	// These are vectors of the names of the pallets marked with `#[event]` as strings, and of the
+	// index of their variant in `RuntimeEvent`, which is the index of the pallet, like in
+	// `RuntimeCall`, so that adding a pallet does not renumber the others.
+	let event_name_str = event_names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+	let event_index = event_pallets.iter().map(|pallet| pallet.index).collect::<Vec<_>>();
+
+	// These are vectors of the names and the `GenesisConfig` types of the pallets marked with
+	// `#[genesis]`, and of the expressions which create each pallet from the genesis config of the
+	// runtime. The other pallets start empty.
+	let genesis_pallets = pallets.iter().filter(|pallet| pallet.genesis).collect::<Vec<_>>();
+	let genesis_names = genesis_pallets.iter().map(|pallet| &pallet.name).collect::<Vec<_>>();
+	// The genesis config of each pallet is bound to `<name>_genesis`, so that it does not shadow
+	// the variables of the generated code.
+	let genesis_bindings = genesis_names
+		.iter()
+		.map(|name| format_ident!("{}_genesis", name))
+		.collect::<Vec<_>>();
+	let genesis_types = genesis_pallets
+		.iter()
+		.map(|pallet| pallet.item_type("GenesisConfig", &runtime_struct))
+		.collect::<Vec<_>>();
+	let genesis_pallet_inits = pallets
+		.iter()
+		.map(|pallet| {
+			let ty = &pallet.ty;
+			if pallet.genesis {
+				let binding = format_ident!("{}_genesis", pallet.name);
+				quote! {{
+					let mut pallet = <#ty>::new();
+					#frame::support::BuildGenesis::build(&#binding, &mut pallet);
+					pallet
+				}}
+			} else {
+				quote! { <#ty>::new() }
+			}
+		})
+		.collect::<Vec<_>>();
+
+	// These are vectors of the names of the pallets as strings, and of the metadata of their events
+	// and errors, which are empty for pallets not marked with `#[event]` or `#[error]`.
+	let pallet_name_str = pallet_names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+	let pallet_events = pallets
+		.iter()
+		.map(|pallet| {
+			if pallet.event {
+				let ty = pallet.item_type("Event", &runtime_struct);
+				quote! { <#ty>::metadata(&mut registry) }
+			} else {
+				quote! { vec![] }
+			}
+		})
+		.collect::<Vec<_>>();
+	let pallet_errors = pallets
+		.iter()
+		.map(|pallet| {
+			if pallet.error {
+				let ty = pallet.item_type("Error", &runtime_struct);
+				quote! { <#ty>::metadata(&mut registry) }
+			} else {
+				quote! { vec![] }
+			}
+		})
+		.collect::<Vec<_>>();
+
+	// This is a vector of the checks that the markers of each pallet match the parts it has, like
+	// its events for `#[event]`, which are evaluated when the runtime is compiled. A pallet with
+	// errors but no `#[error]` would otherwise leave its errors out of the metadata silently. See
+	// `support::PalletInfo`.
+	let marker_checks = pallets
+		.iter()
+		.map(|pallet| {
+			let ty = pallet.pallet_type(&runtime_struct);
+			let markers = [
+				("event", "HAS_EVENT", "an `Event`", pallet.event),
+				("error", "HAS_ERROR", "an `Error`", pallet.error),
+				("genesis", "HAS_GENESIS", "a `GenesisConfig`", pallet.genesis),
+			];
+			let checks = markers.map(|(marker, constant, part, marked)| {
+				let constant = format_ident!("{}", constant);
+				let msg = if marked {
+					format!("`{}` is marked with `#[{}]`, but has no {}", pallet.name, marker, part)
+				} else {
+					let name = &pallet.name;
+					format!("`{}` has {}, so it must be marked with `#[{}]`", name, part, marker)
+				};
+				quote! {
+					assert!(<#ty as #frame::support::PalletInfo>::#constant == #marked, #msg);
+				}
+			});
+			quote! { #( #checks )* }
+		})
+		.collect::<Vec<_>>();
 
 	// This quote block implements functions on the `Runtime` struct.
 	let runtime_impl = quote! {
+		const _: () = {
+			#( #marker_checks )*
+		};
+
 		impl #runtime_struct {
 			// Create a new instance of the main Runtime, by creating a new instance of each pallet.
-			fn new() -> Self {
+			pub fn new() -> Self {
 				Self {
 					// Since system is not included in the list of pallets, we manually add it here.
-					system: <system::Pallet::<Self>>::new(),
+					#system_name: <#system_type>::new(),
 					#(
 						#pallet_names: <#pallet_types>::new()
 					),*
 				}
 			}
 
-			// Execute a block of extrinsics. Increments the block number.
-			fn execute_block(&mut self, block: types::Block) -> crate::support::DispatchResult {
-				self.system.inc_block_number();
-				if block.header.block_number != self.system.block_number() {
-					return Err(&"block number does not match what is expected")
+			// Create a new instance of the main Runtime, where the pallets marked with `#[genesis]`
+			// start with the state in their genesis config, built by `support::BuildGenesis`.
+			pub fn from_genesis(genesis: RuntimeGenesisConfig) -> Self {
+				let RuntimeGenesisConfig { #( #genesis_names: #genesis_bindings ),* } = genesis;
+				Self {
+					#system_name: <#system_type>::new(),
+					#(
+						#pallet_names: #genesis_pallet_inits
+					),*
 				}
-				for (i, support::Extrinsic { caller, call }) in block.extrinsics.into_iter().enumerate() {
-					self.system.inc_nonce(&caller);
-					let _res = self.dispatch(caller, call).map_err(|e| {
-						eprintln!(
-							"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tError: {}",
-							block.header.block_number, i, e
-						)
-					});
+			}
+
+			// Execute a block of extrinsics. Increments the block number, and returns what
+			// happened to each extrinsic and hook. Extrinsics and hooks which fail do not make the
+			// block invalid, but an invalid block number or invalid inherents do, and then nothing
+			// is changed.
+			pub fn execute_block(
+				&mut self,
+				block: #block,
+			) -> Result<#frame::support::BlockReport, #frame::support::DispatchError> {
+				// The block number is checked before anything is changed, so that an invalid block
+				// has no effect.
+				let expected = self.#system_name.next_block_number()?;
+				if block.header.block_number != expected {
+					let msg = format!(
+						"block number {} does not match the expected block number {}",
+						block.header.block_number, expected
+					);
+					return Err(msg.into())
 				}
-				Ok(())
+				// Only inherents can be unsigned, and the block must include each of them exactly
+				// once. See `support::Inherents`.
+				let mut included = vec![false; <RuntimeCall as #frame::support::Inherents>::COUNT];
+				for (i, extrinsic) in block.extrinsics.iter().enumerate() {
+					let inherent = #frame::support::Inherents::inherent(&extrinsic.call);
+					let msg = match (&extrinsic.caller, inherent) {
+						(Some(_), None) => continue,
+						(None, Some(position)) if !included[position] => {
+							included[position] = true;
+							continue
+						},
+						(None, Some(_)) => "is an inherent which is already included",
+						(None, None) => "is unsigned, but is not an inherent",
+						(Some(_), Some(_)) => "is an inherent, which must be unsigned",
+					};
+					return Err(format!("extrinsic {} {}", i, msg).into())
+				}
+				if included.contains(&false) {
+					return Err("the block does not include every inherent".into())
+				}
+				self.#system_name.inc_block_number()?;
+				// The events of the previous block are removed, so that only the events of this
+				// block are kept.
+				self.#system_name.reset_events();
+				let mut report = #frame::support::BlockReport::default();
+				// Give each pallet the chance to do some work before the extrinsics are executed.
+				// Their errors are reported, like those of extrinsics.
+				#(
+					let initialized =
+						<#pallet_types as #frame::support::Hooks<Self>>::on_initialize(
+							self,
+							block.header.block_number,
+						);
+					report.weight = report.weight.saturating_add(initialized.weight);
+					for e in initialized.failed {
+						report.initialize_failed.push((stringify!(#pallet_names), e));
+					}
+				)*
+				for (i, #frame::support::Extrinsic { caller, call }) in block.extrinsics.into_iter().enumerate() {
+					let i = i as u32;
+					self.#system_name.set_extrinsic_index(i);
+					// Extrinsics without a caller are inherents, checked above, and dispatched with
+					// no origin.
+					let origin = match caller {
+						Some(caller) => {
+							// The caller is resolved from its address, pays the fee, and has its
+							// nonce incremented. Extrinsics for which any of these fail are not
+							// dispatched.
+							let signed = #frame::support::Lookup::lookup(self, caller)
+								.and_then(|caller| {
+									#frame::support::ChargeTransaction::charge_transaction(
+										self, &caller, &call,
+									)?;
+									self.#system_name.inc_nonce(caller.clone())?;
+									Ok(caller)
+								});
+							match signed {
+								Ok(caller) => #frame::support::RawOrigin::Signed(caller),
+								Err(e) => {
+									report.failed.push((i, e));
+									continue
+								},
+							}
+						},
+						None => #frame::support::RawOrigin::None,
+					};
+					let weight = #frame::support::GetWeight::weight(&call);
+					report.weight = report.weight.saturating_add(weight);
+					match #frame::support::Dispatch::dispatch(self, origin, call) {
+						Ok(()) => report.applied.push(i),
+						Err(e) => report.failed.push((i, e)),
+					}
+				}
+				// Give each pallet the chance to do some work after the extrinsics are executed.
+				// The block is already applied at this point, so errors are only reported.
+				#(
+					let finalized = <#pallet_types as #frame::support::Hooks<Self>>::on_finalize(
+						self,
+						block.header.block_number,
+					);
+					if let Err(e) = finalized {
+						report.finalize_failed.push((stringify!(#pallet_names), e));
+					}
+				)*
+				Ok(report)
+			}
+		}
+
+		impl #runtime_struct {
+			/// The metadata of the runtime, which describes every pallet. See `metadata`.
+			pub fn metadata() -> #frame::metadata::RuntimeMetadata {
+				// The types are registered as the pallets describe them.
+				let mut registry = #frame::metadata::TypeRegistry::new();
+				let pallets = vec![
+					// System is not callable, and has no events or errors of its own.
+					#frame::metadata::PalletMetadata {
+						name: #system_name_str,
+						index: None,
+						calls: vec![],
+						events: vec![],
+						errors: vec![],
+						storage: <#system_type>::storage_metadata(&mut registry),
+						constants: <#system_type>::constants_metadata(&mut registry),
+					},
+					#(
+						#frame::metadata::PalletMetadata {
+							name: #pallet_name_str,
+							index: Some(#pallet_index),
+							calls: <#call_types>::metadata(&mut registry),
+							events: #pallet_events,
+							errors: #pallet_errors,
+							storage: <#pallet_types>::storage_metadata(&mut registry),
+							constants: <#pallet_types>::constants_metadata(&mut registry),
+						}
+					),*
+				];
+				#frame::metadata::RuntimeMetadata { types: registry.into_types(), pallets }
+			}
+		}
+
+		// Give pallets access to each other through the runtime. See `support::GetPallet`.
+		impl #frame::support::GetPallet<#system_type> for #runtime_struct {
+			fn pallet(&self) -> &#system_type {
+				&self.#system_name
+			}
+
+			fn pallet_mut(&mut self) -> &mut #system_type {
+				&mut self.#system_name
 			}
 		}
+
+		#(
+			impl #frame::support::GetPallet<#pallet_types> for #runtime_struct {
+				fn pallet(&self) -> &#pallet_types {
+					&self.#pallet_names
+				}
+
+				fn pallet_mut(&mut self) -> &mut #pallet_types {
+					&mut self.#pallet_names
+				}
+			}
+		)*
 	};
 
 	// This quote block implements the `RuntimeCall` enum and implements the `Dispatch` trait.
@@ -50,40 +334,187 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 		// Note that it is just an accumulation of the calls exposed by each pallet.
 		//
 		// The parsed function names will be `snake_case`, and that will show up in the enum.
+		//
+		// Unlike `Call`, `RuntimeCall` is not generic, so its traits are derived.
 		#[allow(non_camel_case_types)]
+		#[derive(Clone, Debug, PartialEq, Eq)]
 		pub enum RuntimeCall {
-			#( #pallet_names(#pallet_names::Call<#runtime_struct>) ),*
+			#( #pallet_names(#call_types) ),*
 		}
 
-		impl crate::support::Dispatch for #runtime_struct {
-			type Caller = <Runtime as system::Config>::AccountId;
+		impl #frame::support::Dispatch for #runtime_struct {
+			type Caller = #system_module::Origin<#runtime_struct>;
 			type Call = RuntimeCall;
-			// Dispatch a call on behalf of a caller. Increments the caller's nonce.
+			// Dispatch a call on behalf of a caller.
 			//
 			// Dispatch allows us to identify which underlying pallet call we want to execute.
 			// Note that we extract the `caller` from the extrinsic, and use that information
 			// to determine who we are executing the call on behalf of.
 			fn dispatch(
 				&mut self,
-				caller: Self::Caller,
+				origin: Self::Caller,
 				runtime_call: Self::Call,
-			) -> crate::support::DispatchResult {
+			) -> #frame::support::DispatchResult {
 				// This match statement will allow us to correctly route `RuntimeCall`s
 				// to the appropriate pallet level call.
 				match runtime_call {
 					#(
 						RuntimeCall::#pallet_names(call) => {
-							self.#pallet_names.dispatch(caller, call)?;
+							call.dispatch(self, origin)?;
 						}
 					),*
 				}
 				Ok(())
 			}
 		}
+
+		// The inherents of the runtime are those of each pallet, in the order of the pallets.
+		impl #frame::support::Inherents for RuntimeCall {
+			const COUNT: usize = 0 #( + <#call_types as #frame::support::Inherents>::COUNT )*;
+
+			fn inherent(&self) -> Option<usize> {
+				match self {
+					#(
+						RuntimeCall::#pallet_names(call) => {
+							let position = #frame::support::Inherents::inherent(call)?;
+							Some(#inherent_offsets + position)
+						},
+					)*
+				}
+			}
+		}
+
+		impl #frame::support::GetWeight for RuntimeCall {
+			fn weight(&self) -> #frame::support::Weight {
+				match self {
+					#(
+						RuntimeCall::#pallet_names(call) => #frame::support::GetWeight::weight(call),
+					)*
+				}
+			}
+		}
+
+		// Hashing a `RuntimeCall` hashes the index of the pallet it belongs to, and then the pallet
+		// level call.
+		impl core::hash::Hash for RuntimeCall {
+			fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+				match self {
+					#(
+						RuntimeCall::#pallet_names(call) => {
+							state.write_u8(#pallet_index);
+							core::hash::Hash::hash(call, state)
+						},
+					)*
+				}
+			}
+		}
+
+		// Encoding a `RuntimeCall` encodes the index of the pallet it belongs to, and then the
+		// pallet level call. See `codec`.
+		impl #frame::codec::Encode for RuntimeCall {
+			fn encode_to(&self, dest: &mut Vec<u8>) {
+				match self {
+					#(
+						RuntimeCall::#pallet_names(call) => {
+							#frame::codec::Encode::encode_to(&#pallet_index, dest);
+							#frame::codec::Encode::encode_to(call, dest);
+						},
+					)*
+				}
+			}
+		}
+
+		// Like its encoding, `RuntimeCall` is described by the index of each pallet, followed by
+		// the pallet level call. See `metadata`.
+		impl #frame::metadata::TypeInfo for RuntimeCall {
+			fn type_info(
+				registry: &mut #frame::metadata::TypeRegistry,
+			) -> #frame::metadata::TypeMetadata {
+				#frame::metadata::TypeMetadata {
+					path: concat!(module_path!(), "::RuntimeCall"),
+					def: #frame::metadata::TypeDef::Variant(vec![
+						#(
+							#frame::metadata::VariantMetadata {
+								name: #pallet_name_str,
+								index: #pallet_index,
+								fields: vec![#frame::metadata::FieldMetadata {
+									name: None,
+									ty: registry.register::<#call_types>(),
+								}],
+								docs: "",
+							}
+						),*
+					]),
+				}
+			}
+		}
+
+		impl #frame::codec::Decode for RuntimeCall {
+			fn decode(input: &mut &[u8]) -> Result<Self, String> {
+				match <u8 as #frame::codec::Decode>::decode(input)? {
+					#(
+						#pallet_index => Ok(RuntimeCall::#pallet_names(#frame::codec::Decode::decode(input)?)),
+					)*
+					_ => Err("invalid pallet index".to_string()),
+				}
+			}
+		}
+	};
+
+	// This quote block implements the `RuntimeEvent` enum, and its conversion from the events of
+	// each pallet marked with `#[event]`. See `#[macros::event]`.
+	let event_impl = quote! {
+		#[allow(non_camel_case_types)]
+		#[derive(Debug, Clone, PartialEq, Eq)]
+		pub enum RuntimeEvent {
+			#( #event_names(#event_types) ),*
+		}
+
+		#(
+			impl From<#event_types> for RuntimeEvent {
+				fn from(event: #event_types) -> Self {
+					RuntimeEvent::#event_names(event)
+				}
+			}
+		)*
+
+		impl #frame::metadata::TypeInfo for RuntimeEvent {
+			fn type_info(
+				registry: &mut #frame::metadata::TypeRegistry,
+			) -> #frame::metadata::TypeMetadata {
+				#frame::metadata::TypeMetadata {
+					path: concat!(module_path!(), "::RuntimeEvent"),
+					def: #frame::metadata::TypeDef::Variant(vec![
+						#(
+							#frame::metadata::VariantMetadata {
+								name: #event_name_str,
+								index: #event_index,
+								fields: vec![#frame::metadata::FieldMetadata {
+									name: None,
+									ty: registry.register::<#event_types>(),
+								}],
+								docs: "",
+							}
+						),*
+					]),
+				}
+			}
+		}
+	};
+
+	// This quote block implements the `RuntimeGenesisConfig` struct, with the genesis config of
+	// each pallet marked with `#[genesis]`. See `Runtime::from_genesis`.
+	let genesis_impl = quote! {
+		pub struct RuntimeGenesisConfig {
+			#( pub #genesis_names: #genesis_types ),*
+		}
 	};
 
 	// We combine and return all the generated code.
 	quote! {
+		#item_struct
+		#event_impl
+		#genesis_impl
 		#dispatch_impl
 		#runtime_impl
 	}
@@ -1,25 +1,38 @@
 pub mod expand;
 pub mod parse;
 
+/// The attributes of the fields of the `Runtime` struct which are read by this macro, and are not
+/// real attributes.
+const FIELD_ATTRS: &[&str] = &["event", "error", "genesis", "pallet_index", "pallet"];
+
 /// See the `fn runtime` docs at the `lib.rs` of this crate for a high level definition.
 pub fn runtime(
-	_attr: proc_macro::TokenStream,
+	attr: proc_macro::TokenStream,
 	item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-	// The final expanded code will be placed here.
-	// Since our macro only adds new code, our final product will contain all of our old code too,
-	// hence we clone `item`.
-	let mut finished = item.clone();
 	let item_mod = syn::parse_macro_input!(item as syn::Item);
 
+	// The `Runtime` struct is replaced, since the attributes in `FIELD_ATTRS` are removed from its
+	// fields. On errors, we return the struct without them, like `#[macros::call]`, so that the
+	// errors are not hidden by the struct going missing or by errors about unknown attributes.
+	let mut original = item_mod.clone();
+	if let syn::Item::Struct(item_struct) = &mut original {
+		for field in item_struct.fields.iter_mut() {
+			field.attrs.retain(|attr| !FIELD_ATTRS.iter().any(|name| attr.path().is_ident(name)));
+		}
+	}
+
 	// First we parse the `Runtime` struct...
-	let generated: proc_macro::TokenStream = match parse::RuntimeDef::try_from(item_mod.clone()) {
+	match parse::RuntimeDef::try_from(attr.into(), item_mod) {
 		// ..then we generate our new code.
 		Ok(def) => expand::expand_runtime(def).into(),
-		Err(e) => e.to_compile_error().into(),
-	};
-
-	// Add our generated code to the end, and return the final result.
-	finished.extend(generated);
-	return finished;
+		Err(e) => {
+			let error = e.to_compile_error();
+			quote::quote! {
+				#original
+				#error
+			}
+			.into()
+		},
+	}
 }
@@ -3,65 +3,323 @@ use syn::spanned::Spanned;
 /// This object will collect all the information we need to keep while parsing the `Runtime` struct.
 #[derive(Debug)]
 pub struct RuntimeDef {
+	/// The `Runtime` struct, without the `#[event]`, `#[error]`, `#[genesis]`, `#[pallet_index]`
+	/// and `#[pallet]` attributes of its fields.
+	pub item_struct: syn::ItemStruct,
 	/// This is the name of the struct used by the user. We mostly assume it is `Runtime`.
 	pub runtime_struct: syn::Ident,
+	/// The system pallet. See `SystemDef`.
+	pub system: SystemDef,
+	/// The type of the blocks executed by the runtime, from `#[macros::runtime(block = ..)]`. It
+	/// is `types::Block` by default.
+	pub block: syn::Type,
 	/// This is the list of pallets included in the `Runtime` struct. We omit `system` from this
 	/// list, but during parsing we check that system exists.
-	pub pallets: Vec<(syn::Ident, syn::Type)>,
+	pub pallets: Vec<PalletDef>,
+}
+
+/// This is the metadata we keep about the system pallet, which is not callable and is used by the
+/// runtime itself to execute blocks.
+#[derive(Debug)]
+pub struct SystemDef {
+	/// The name of the field of the system pallet. It is the field marked with `#[pallet(system)]`,
+	/// or the field named `system`.
+	pub name: syn::Ident,
+	/// The type of the system pallet, like `system::Pallet<Self>`.
+	pub ty: syn::Type,
+	/// The module of the system pallet, where its `Origin` is, from
+	/// `#[macros::runtime(system = ..)]`. It is the module in `ty` by default.
+	pub module: syn::Path,
+}
+
+impl SystemDef {
+	/// The module which has the `support`, `metadata` and `codec` modules next to the system
+	/// pallet, which is the parent of `module`, like `crate` for `crate::system`. A module without
+	/// a parent, like `system`, is at the root of the crate.
+	pub fn frame(&self) -> syn::Path {
+		let mut frame = self.module.clone();
+		frame.segments.pop();
+		frame.segments.pop_punct();
+		if frame.segments.is_empty() {
+			syn::parse_quote!(crate)
+		} else {
+			frame
+		}
+	}
+}
+
+/// The arguments of `#[macros::runtime(block = .., system = ..)]`, which are all optional.
+#[derive(Default)]
+struct RuntimeArgs {
+	block: Option<syn::Type>,
+	system: Option<syn::Path>,
+}
+
+impl syn::parse::Parse for RuntimeArgs {
+	fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+		let mut args = Self::default();
+		while !input.is_empty() {
+			let key = input.parse::<syn::Ident>()?;
+			input.parse::<syn::Token![=]>()?;
+			let duplicate = if key == "block" {
+				args.block.replace(input.parse()?).is_some()
+			} else if key == "system" {
+				args.system.replace(input.parse()?).is_some()
+			} else {
+				let msg = "unknown runtime argument, expected `block` or `system`";
+				return Err(syn::Error::new(key.span(), msg))
+			};
+			if duplicate {
+				let msg = format!("duplicate runtime argument `{}`", key);
+				return Err(syn::Error::new(key.span(), msg))
+			}
+			if !input.is_empty() {
+				input.parse::<syn::Token![,]>()?;
+			}
+		}
+		Ok(args)
+	}
+}
+
+/// A field of the `Runtime` struct, with the attributes read by this macro.
+struct FieldDef {
+	name: syn::Ident,
+	ty: syn::Type,
+	event: Option<syn::Attribute>,
+	error: Option<syn::Attribute>,
+	genesis: Option<syn::Attribute>,
+	index: Option<(u8, proc_macro2::Span)>,
+	/// The `#[pallet(system)]` attribute, if the field has one.
+	system: Option<syn::Attribute>,
+}
+
+/// This is the metadata we keep about each pallet in the `Runtime` struct.
+#[derive(Debug)]
+pub struct PalletDef {
+	/// The name of the field of the pallet.
+	pub name: syn::Ident,
+	/// The type of the pallet, like `balances::Pallet<Self>`.
+	pub ty: syn::Type,
+	/// The module of the pallet and the generic arguments of its type, like `balances` and
+	/// `<Self>`. See `PalletDef::item_type`.
+	pub module: syn::Path,
+	pub args: syn::PathArguments,
+	/// The index of the pallet, from `#[pallet_index(n)]`, which identifies it in the encoding of a
+	/// `RuntimeCall`.
+	pub index: u8,
+	/// Whether the pallet is marked with `#[event]`, and has an `Event` enum.
+	pub event: bool,
+	/// Whether the pallet is marked with `#[error]`, and has an `Error` enum.
+	pub error: bool,
+	/// Whether the pallet is marked with `#[genesis]`, and has a `GenesisConfig`.
+	pub genesis: bool,
+}
+
+impl PalletDef {
+	/// The type of the item `name` of the module of the pallet, like `balances::Call<Runtime>`,
+	/// with the same generic arguments as the pallet, where `Self` is replaced with the runtime,
+	/// since it is used outside of the `Runtime` struct.
+	pub fn item_type(&self, name: &str, runtime: &syn::Ident) -> syn::Type {
+		let mut args = self.args.clone();
+		if let syn::PathArguments::AngleBracketed(args) = &mut args {
+			for arg in args.args.iter_mut() {
+				if let syn::GenericArgument::Type(ty) = arg {
+					if matches!(ty, syn::Type::Path(tp) if tp.path.is_ident("Self")) {
+						*ty = syn::parse_quote!(#runtime);
+					}
+				}
+			}
+		}
+		let module = &self.module;
+		let name = syn::Ident::new(name, proc_macro2::Span::call_site());
+		syn::parse_quote!(#module :: #name #args)
+	}
+
+	/// The type of the pallet itself, like `balances::Pallet<Runtime>`. See `item_type`.
+	pub fn pallet_type(&self, runtime: &syn::Ident) -> syn::Type {
+		let name = match &self.ty {
+			syn::Type::Path(tp) => tp.path.segments.last().map(|segment| segment.ident.to_string()),
+			_ => None,
+		};
+		self.item_type(&name.expect("checked by `split_pallet_type`; qed"), runtime)
+	}
 }
 
 impl RuntimeDef {
-	pub fn try_from(item: syn::Item) -> syn::Result<Self> {
+	pub fn try_from(attr: proc_macro2::TokenStream, item: syn::Item) -> syn::Result<Self> {
+		let args = syn::parse2::<RuntimeArgs>(attr)?;
+
 		// First we check that we are parsing a `struct`.
-		let item_struct = if let syn::Item::Struct(item) = item {
+		let mut item_struct = if let syn::Item::Struct(item) = item {
 			item
 		} else {
 			return Err(syn::Error::new(item.span(), "Invalid runtime, expected item struct"))
 		};
+		let runtime_struct = item_struct.ident.clone();
 
-		// We check that the `Runtime` includes the `system` pallet as the first item.
-		check_system(&item_struct)?;
+		// Then we read the attributes of every field, which are removed from the struct.
+		let fields = match &mut item_struct.fields {
+			syn::Fields::Named(fields) => &mut fields.named,
+			syn::Fields::Unnamed(_) | syn::Fields::Unit => {
+				let msg = "Invalid runtime, expected a struct with named fields";
+				return Err(syn::Error::new(runtime_struct.span(), msg))
+			},
+		};
+		let mut field_defs = vec![];
+		for field in fields.iter_mut() {
+			field_defs.push(FieldDef {
+				name: field.ident.clone().expect("the fields are named; qed"),
+				ty: field.ty.clone(),
+				event: take_marker_attr(field, "event")?,
+				error: take_marker_attr(field, "error")?,
+				genesis: take_marker_attr(field, "genesis")?,
+				index: crate::index::take_index_attr(&mut field.attrs, "pallet_index")?,
+				system: take_pallet_attr(field)?,
+			});
+		}
 
-		let runtime_struct = item_struct.ident;
+		// We check that the `Runtime` includes the `system` pallet, and take it out of the fields.
+		let position = find_system(&runtime_struct, &field_defs)?;
+		let system_field = field_defs.remove(position);
+		if let Some(attr) = system_field.event {
+			return Err(syn::Error::new_spanned(attr, "system does not have events"))
+		}
+		if let Some(attr) = system_field.error {
+			return Err(syn::Error::new_spanned(attr, "system does not have errors"))
+		}
+		if let Some(attr) = system_field.genesis {
+			return Err(syn::Error::new_spanned(attr, "system does not have a genesis config"))
+		}
+		if let Some((_, span)) = system_field.index {
+			let msg = "system is not callable, so it does not have a pallet index";
+			return Err(syn::Error::new(span, msg))
+		}
+		let module = match args.system {
+			Some(module) => module,
+			None => split_pallet_type(&system_field.ty).map(|(module, _)| module).map_err(|_| {
+				let msg = "expected the type of the system pallet, like `system::Pallet<Self>`, \
+					or its module in `#[macros::runtime(system = ..)]`";
+				syn::Error::new(system_field.ty.span(), msg)
+			})?,
+		};
+		let system = SystemDef { name: system_field.name, ty: system_field.ty, module };
+		let block = args.block.unwrap_or_else(|| syn::parse_quote!(types::Block));
 
 		// Here is where we will store a list of all the pallets.
 		let mut pallets = vec![];
-		// We skip `system`, which we ensure is the first field in `check_system`.
-		for field in item_struct.fields.into_iter().skip(1) {
-			if let Some(ident) = field.ident {
-				pallets.push((ident, field.ty))
-			}
+		let mut index_spans = vec![];
+		for field in field_defs {
+			// Every pallet must have an explicit index, so that reordering the pallets does not
+			// change the encoding of calls.
+			let (index, span) = field.index.ok_or_else(|| {
+				let msg = "missing `#[pallet_index(n)]`, every pallet needs an explicit index";
+				syn::Error::new(field.name.span(), msg)
+			})?;
+			index_spans.push(span);
+			let (module, args) = split_pallet_type(&field.ty)?;
+			pallets.push(PalletDef {
+				name: field.name,
+				ty: field.ty,
+				module,
+				args,
+				index,
+				event: field.event.is_some(),
+				error: field.error.is_some(),
+				genesis: field.genesis.is_some(),
+			})
 		}
 
-		Ok(Self { runtime_struct, pallets })
+		let indices = pallets
+			.iter()
+			.zip(index_spans)
+			.map(|(pallet, span)| (&pallet.name, pallet.index, span))
+			.collect::<Vec<_>>();
+		crate::index::check_unique("pallet", &indices)?;
+
+		Ok(Self { item_struct, runtime_struct, system, block, pallets })
 	}
 }
 
-/// This function checks that the `system` pallet is the first pallet included in the `Runtime`
-/// struct. We make many assumptions about the `system` pallet in order to keep these macros simple.
-/// For example, we assume that the system pallet has no callable functions, and that it contains
-/// specific functions like incrementing the block number and a user's nonce.
-///
-/// You can consider these macros to be tightly coupled to the logic of the `system` pallet.
-fn check_system(item_struct: &syn::ItemStruct) -> syn::Result<()> {
-	// Extract the name of the first field in the `Runtime` struct.
-	let first_field_name = if let Some(first_field) = item_struct.fields.iter().next() {
-		if let Some(field_name) = &first_field.ident {
-			field_name.to_string()
-		} else {
-			let msg = "first field is expected to have the name system";
-			return Err(syn::Error::new(item_struct.span(), msg))
-		}
-	} else {
-		let msg = "runtime struct is expected to have fields";
-		return Err(syn::Error::new(item_struct.span(), msg))
+/// Split the type of a pallet, like `balances::Pallet<Self, Instance1>`, into the path of its
+/// module and its generic arguments. The `Call`, `Event`, `Error` and `GenesisConfig` of the
+/// pallet are in the same module, with the same generic arguments.
+fn split_pallet_type(ty: &syn::Type) -> syn::Result<(syn::Path, syn::PathArguments)> {
+	let msg = "expected the type of a pallet, like `balances::Pallet<Self>`";
+	let path = match ty {
+		syn::Type::Path(tp) if tp.qself.is_none() && tp.path.segments.len() > 1 => &tp.path,
+		_ => return Err(syn::Error::new(ty.span(), msg)),
 	};
+	let mut module = path.clone();
+	let last = module.segments.pop().expect("the path has more than one segment; qed");
+	// Remove the trailing `::` of the module.
+	module.segments.pop_punct();
+	Ok((module, last.into_value().arguments))
+}
 
-	// Check if the first field is named "system"
-	if first_field_name != "system" {
-		let msg = "first field is expected to be named system";
-		return Err(syn::Error::new(item_struct.span(), msg))
-	}
+/// Remove the `#[name]` attribute from `field`, and return it if there was one.
+fn take_marker_attr(field: &mut syn::Field, name: &str) -> syn::Result<Option<syn::Attribute>> {
+	let mut marker = None;
+	let mut result = Ok(());
+	field.attrs.retain(|attr| {
+		if !attr.path().is_ident(name) {
+			return true
+		}
+		if let Err(e) = attr.meta.require_path_only() {
+			let msg = format!("expected `#[{}]`, without arguments", name);
+			let mut error = syn::Error::new_spanned(attr, msg);
+			error.combine(e);
+			result = Err(error);
+		} else if marker.is_some() {
+			let msg = format!("duplicate `#[{}]` attribute", name);
+			result = Err(syn::Error::new_spanned(attr, msg));
+		}
+		marker = Some(attr.clone());
+		false
+	});
+	result.map(|()| marker)
+}
 
-	Ok(())
+/// Remove the `#[pallet(system)]` attribute from `field`, and return it if there was one.
+fn take_pallet_attr(field: &mut syn::Field) -> syn::Result<Option<syn::Attribute>> {
+	let mut marker = None;
+	let mut result = Ok(());
+	field.attrs.retain(|attr| {
+		if !attr.path().is_ident("pallet") {
+			return true
+		}
+		let is_system = attr.parse_args::<syn::Ident>().is_ok_and(|ident| ident == "system");
+		if !is_system {
+			let msg = "expected `#[pallet(system)]`";
+			result = Err(syn::Error::new_spanned(attr, msg));
+		} else if marker.is_some() {
+			let msg = "duplicate `#[pallet(system)]` attribute";
+			result = Err(syn::Error::new_spanned(attr, msg));
+		}
+		marker = Some(attr.clone());
+		false
+	});
+	result.map(|()| marker)
+}
+
+/// This function finds the position of the `system` pallet in the fields of the `Runtime` struct.
+/// It is the field marked with `#[pallet(system)]`, or the field named `system` if no field is
+/// marked. We make many assumptions about the `system` pallet in order to keep these macros
+/// simple. For example, we assume that the system pallet has no callable functions, and that it
+/// contains specific functions like incrementing the block number and a user's nonce.
+///
+/// You can consider these macros to be tightly coupled to the logic of the `system` pallet.
+fn find_system(runtime_struct: &syn::Ident, fields: &[FieldDef]) -> syn::Result<usize> {
+	let mut marked = fields.iter().enumerate().filter(|(_, field)| field.system.is_some());
+	match (marked.next(), marked.next()) {
+		(Some((position, _)), None) => Ok(position),
+		(Some((_, first)), Some((_, second))) => {
+			let msg = format!("the system pallet is already `{}`", first.name);
+			Err(syn::Error::new_spanned(&second.system, msg))
+		},
+		(None, _) => fields.iter().position(|field| field.name == "system").ok_or_else(|| {
+			let msg = "runtime struct is expected to have a system pallet, in a field named \
+				`system` or marked with `#[pallet(system)]`";
+			syn::Error::new(runtime_struct.span(), msg)
+		}),
+	}
 }
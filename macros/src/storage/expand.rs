@@ -0,0 +1,153 @@
+use super::parse::{StorageDef, StorageKind};
+use quote::quote;
+
+/// See the `fn storage` docs at the `lib.rs` of this crate for a high level definition.
+pub fn expand_storage(
+	def: StorageDef,
+	module_name: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+	let StorageDef { pallet_struct, items } = def;
+	let pallet_ident = &pallet_struct.ident;
+	let (impl_generics, ty_generics, where_clause) = pallet_struct.generics.split_for_impl();
+
+	// This is a vector of the names of all the storage items, and a vector of their types.
+	let item_name = items.iter().map(|item| &item.name).collect::<Vec<_>>();
+	let item_type = items.iter().map(|item| &item.ty).collect::<Vec<_>>();
+
+	// This is a vector of the names of the items as strings, used in their prefixes.
+	let item_str = items.iter().map(|item| item.name.to_string()).collect::<Vec<_>>();
+
+	// This is a vector of the getters requested with `#[getter(name)]`. They read the item with
+	// its query kind, taking the keys by reference.
+	let getters = items
+		.iter()
+		.filter_map(|item| {
+			let getter = item.getter.as_ref()?;
+			let name = &item.name;
+			let (value, query, on_empty) = (&item.value, &item.query, &item.on_empty);
+			let returns = quote! {
+				<#query as crate::storage::QueryKind<#value, #on_empty>>::Query
+			};
+			Some(match (item.kind, item.keys.as_slice()) {
+				(StorageKind::Value, _) => quote! {
+					pub fn #getter(&self) -> #returns {
+						self.#name.get()
+					}
+				},
+				(StorageKind::Map, [key]) => quote! {
+					pub fn #getter(&self, key: &#key) -> #returns {
+						self.#name.get(key)
+					}
+				},
+				(StorageKind::DoubleMap, [k1, k2]) => quote! {
+					pub fn #getter(&self, k1: &#k1, k2: &#k2) -> #returns {
+						self.#name.get(k1, k2)
+					}
+				},
+				_ => unreachable!("the number of keys matches the kind; qed"),
+			})
+		})
+		.collect::<Vec<_>>();
+
+	// This is a vector of the setters requested with `#[setter(name)]`. They insert a value.
+	let setters = items
+		.iter()
+		.filter_map(|item| {
+			let setter = item.setter.as_ref()?;
+			let name = &item.name;
+			let value = &item.value;
+			Some(match (item.kind, item.keys.as_slice()) {
+				(StorageKind::Value, _) => quote! {
+					pub fn #setter(&mut self, value: #value) {
+						self.#name.put(value)
+					}
+				},
+				(StorageKind::Map, [key]) => quote! {
+					pub fn #setter(&mut self, key: #key, value: #value) {
+						self.#name.insert(key, value)
+					}
+				},
+				(StorageKind::DoubleMap, [k1, k2]) => quote! {
+					pub fn #setter(&mut self, k1: #k1, k2: #k2, value: #value) {
+						self.#name.insert(k1, k2, value)
+					}
+				},
+				_ => unreachable!("the number of keys matches the kind; qed"),
+			})
+		})
+		.collect::<Vec<_>>();
+
+	// This is a vector of the metadata of each storage item. The types of the keys and values are
+	// registered in the registry of types, see `metadata`.
+	let metadata = items.iter().map(|item| {
+		let name = item.name.to_string();
+		let kind = match item.kind {
+			StorageKind::Value => quote! { Value },
+			StorageKind::Map => quote! { Map },
+			StorageKind::DoubleMap => quote! { DoubleMap },
+		};
+		let (hashers, keys) = (&item.hashers, &item.keys);
+		let (value, query, on_empty) = (&item.value, &item.query, &item.on_empty);
+		let docs = &item.docs;
+		quote! {
+			crate::metadata::StorageMetadata {
+				name: #name,
+				kind: crate::metadata::StorageKind::#kind,
+				hashers: vec![ #( <#hashers as crate::storage::StorageHasher>::NAME ),* ],
+				keys: vec![ #( registry.register::<#keys>() ),* ],
+				value: registry.register::<#value>(),
+				optional: <#query as crate::storage::QueryKind<#value, #on_empty>>::OPTIONAL,
+				docs: #docs,
+			}
+		}
+	});
+
+	// The metadata describes the keys and the values, so their types must implement `TypeInfo`.
+	let described = items.iter().flat_map(|item| item.keys.iter().chain([&item.value]));
+	let type_info_generics = crate::type_info::with_bounds(&pallet_struct.generics, described);
+	let type_info_where = &type_info_generics.where_clause;
+
+	// The name of the pallet in the prefixes is the name of its module, followed by the prefix of
+	// its instance for instantiable pallets, so that each instance has its own storage.
+	let pallet_name = match crate::instance::instance_param(&pallet_struct.generics) {
+		Some(instance) => {
+			let instance = &instance.ident;
+			quote! {
+				&format!("{}{}", module, <#instance as crate::support::Instance>::PREFIX)
+			}
+		},
+		None => quote! { module },
+	};
+
+	quote! {
+		#pallet_struct
+
+		impl #impl_generics #pallet_ident #ty_generics #where_clause {
+			// Create the pallet with all of its storage items empty. Each item gets its own prefix,
+			// from the name of the pallet, which is the name of its module like `balances`, and the
+			// name of the item.
+			pub fn new() -> Self {
+				let module = #module_name;
+				let pallet = #pallet_name;
+				Self {
+					#(
+						#item_name: <#item_type>::new(crate::storage::storage_prefix(pallet, #item_str)),
+					)*
+				}
+			}
+
+			#( #getters )*
+
+			#( #setters )*
+		}
+
+		impl #impl_generics #pallet_ident #ty_generics #type_info_where {
+			/// The metadata of the storage items of the pallet.
+			pub fn storage_metadata(
+				registry: &mut crate::metadata::TypeRegistry,
+			) -> Vec<crate::metadata::StorageMetadata> {
+				vec![ #( #metadata ),* ]
+			}
+		}
+	}
+}
@@ -0,0 +1,37 @@
+pub mod expand;
+pub mod parse;
+
+/// See the `fn storage` docs at the `lib.rs` of this crate for a high level definition.
+pub fn storage(
+	_attr: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	let item_mod = syn::parse_macro_input!(item as syn::Item);
+	expand(item_mod, crate::pallet::module_name(false)).into()
+}
+
+/// Expand the storage of the pallet struct `item_mod`, with any errors in the generated code. This
+/// is also used by `#[macros::pallet]` for its `#[pallet::storage]` section. `module_name` is the
+/// name of the pallet in the prefixes, see `pallet::module_name`.
+pub fn expand(
+	item_mod: syn::Item,
+	module_name: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+	// Unlike the other macros, this one replaces the struct, since it removes the `#[getter]` and
+	// `#[setter]` attributes from its fields. We keep the original code to return it with any
+	// errors, so that the errors are not hidden by the struct going missing.
+	let original = item_mod.clone();
+
+	// First we parse the storage items of the pallet struct...
+	match parse::StorageDef::try_from(item_mod) {
+		// ..then we generate our new code.
+		Ok(def) => expand::expand_storage(def, module_name),
+		Err(e) => {
+			let error = e.to_compile_error();
+			quote::quote! {
+				#original
+				#error
+			}
+		},
+	}
+}
@@ -0,0 +1,175 @@
+use syn::spanned::Spanned;
+
+/// This object will collect all the information we need to keep while parsing the storage of a
+/// pallet.
+#[derive(Debug)]
+pub struct StorageDef {
+	/// The pallet struct, without the `#[getter]` and `#[setter]` attributes of its fields.
+	pub pallet_struct: syn::ItemStruct,
+	/// The storage items, one per field of the pallet struct. See `StorageItemDef`.
+	pub items: Vec<StorageItemDef>,
+}
+
+/// This is the metadata we keep about each storage item.
+#[derive(Debug)]
+pub struct StorageItemDef {
+	/// The name of the field, which is also the name of the item in its prefix.
+	pub name: syn::Ident,
+	/// The type of the field.
+	pub ty: syn::Type,
+	pub kind: StorageKind,
+	/// The hashers of the keys, in the same order.
+	pub hashers: Vec<syn::Type>,
+	/// The types of the keys of the item: none for a value, one for a map and two for a double map.
+	pub keys: Vec<syn::Type>,
+	pub value: syn::Type,
+	/// The query kind of the item, `OptionQuery` if it is not given.
+	pub query: syn::Type,
+	/// The default value of the item, `GetDefault` if it is not given.
+	pub on_empty: syn::Type,
+	/// The name of the getter to generate, from `#[getter(name)]`.
+	pub getter: Option<syn::Ident>,
+	/// The name of the setter to generate, from `#[setter(name)]`.
+	pub setter: Option<syn::Ident>,
+	/// The doc comment of the field.
+	pub docs: String,
+}
+
+/// The kinds of storage items, named after their types in the `storage` module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+	/// `StorageValue<V, Q, OnEmpty>`.
+	Value,
+	/// `StorageMap<H, K, V, Q, OnEmpty>`.
+	Map,
+	/// `StorageDoubleMap<H1, K1, H2, K2, V, Q, OnEmpty>`.
+	DoubleMap,
+}
+
+impl StorageDef {
+	pub fn try_from(item: syn::Item) -> syn::Result<Self> {
+		// First we check that we are parsing a `struct` with named fields.
+		let mut pallet_struct = if let syn::Item::Struct(item) = item {
+			item
+		} else {
+			return Err(syn::Error::new(item.span(), "Invalid pallet::storage, expected item struct"))
+		};
+		let fields = if let syn::Fields::Named(fields) = &mut pallet_struct.fields {
+			fields
+		} else {
+			let msg = "Invalid pallet::storage, expected a struct with named fields";
+			return Err(syn::Error::new(pallet_struct.span(), msg))
+		};
+
+		// Here is where we will store all the storage items.
+		let mut items = vec![];
+		for field in fields.named.iter_mut() {
+			let name = field.ident.clone().expect("fields are named; qed");
+
+			// Take the `#[getter]` and `#[setter]` attributes out of the field, since they are
+			// not real attributes.
+			let mut getter = None;
+			let mut setter = None;
+			let mut attrs = vec![];
+			for attr in field.attrs.drain(..) {
+				if attr.path().is_ident("getter") {
+					getter = Some(attr.parse_args::<syn::Ident>()?);
+				} else if attr.path().is_ident("setter") {
+					setter = Some(attr.parse_args::<syn::Ident>()?);
+				} else {
+					attrs.push(attr);
+				}
+			}
+			let docs = crate::pallet_enum::docs(&attrs);
+			field.attrs = attrs;
+
+			let StorageType { kind, hashers, keys, value, query, on_empty } =
+				parse_storage_type(&field.ty)?;
+			items.push(StorageItemDef {
+				name,
+				ty: field.ty.clone(),
+				kind,
+				hashers,
+				keys,
+				value,
+				query,
+				on_empty,
+				getter,
+				setter,
+				docs,
+			});
+		}
+
+		Ok(Self { pallet_struct, items })
+	}
+}
+
+/// The generic arguments of the type of a storage item. See `StorageItemDef`.
+struct StorageType {
+	kind: StorageKind,
+	hashers: Vec<syn::Type>,
+	keys: Vec<syn::Type>,
+	value: syn::Type,
+	query: syn::Type,
+	on_empty: syn::Type,
+}
+
+/// Parse the type of a storage item, which must be one of the types in `StorageKind`.
+///
+/// The type is matched by the name of its last segment, so it can be imported or written as a
+/// path like `crate::storage::StorageMap`.
+fn parse_storage_type(ty: &syn::Type) -> syn::Result<StorageType> {
+	let msg = "Invalid pallet::storage, expected `StorageValue`, `StorageMap` or `StorageDoubleMap`";
+	let segment = match ty {
+		syn::Type::Path(tp) if tp.qself.is_none() => tp.path.segments.last(),
+		_ => None,
+	};
+	let segment = segment.ok_or_else(|| syn::Error::new(ty.span(), msg))?;
+
+	// The number of keys of each kind, and the number of hashers which come with them.
+	let (kind, key_count) = match segment.ident.to_string().as_str() {
+		"StorageValue" => (StorageKind::Value, 0),
+		"StorageMap" => (StorageKind::Map, 1),
+		"StorageDoubleMap" => (StorageKind::DoubleMap, 2),
+		_ => return Err(syn::Error::new(segment.ident.span(), msg)),
+	};
+
+	// All the generic arguments should be types.
+	let args = match &segment.arguments {
+		syn::PathArguments::AngleBracketed(args) => args
+			.args
+			.iter()
+			.map(|arg| match arg {
+				syn::GenericArgument::Type(ty) => Ok(ty.clone()),
+				_ => Err(syn::Error::new(arg.span(), "Invalid pallet::storage, expected a type")),
+			})
+			.collect::<syn::Result<Vec<_>>>()?,
+		_ => vec![],
+	};
+
+	// Each key comes after its hasher, then comes the value, and optionally the query kind and the
+	// default value.
+	let required = key_count * 2 + 1;
+	if args.len() < required || args.len() > required + 2 {
+		let msg = match kind {
+			StorageKind::Value => "Invalid pallet::storage, expected `StorageValue<V, Q, OnEmpty>`",
+			StorageKind::Map => "Invalid pallet::storage, expected `StorageMap<H, K, V, Q, OnEmpty>`",
+			StorageKind::DoubleMap =>
+				"Invalid pallet::storage, expected `StorageDoubleMap<H1, K1, H2, K2, V, Q, OnEmpty>`",
+		};
+		return Err(syn::Error::new(segment.span(), msg))
+	}
+	let hashers = (0..key_count).map(|index| args[index * 2].clone()).collect();
+	let keys = (0..key_count).map(|index| args[index * 2 + 1].clone()).collect();
+	let value = args[required - 1].clone();
+	let query = args
+		.get(required)
+		.cloned()
+		.unwrap_or_else(|| syn::parse_quote!(crate::storage::OptionQuery));
+	let on_empty = args
+		.get(required + 1)
+		.cloned()
+		.unwrap_or_else(|| syn::parse_quote!(crate::storage::GetDefault));
+
+	Ok(StorageType { kind, hashers, keys, value, query, on_empty })
+}
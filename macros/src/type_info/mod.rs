@@ -0,0 +1,94 @@
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+
+/// See the `fn derive_type_info` docs at the `lib.rs` of this crate for a high level definition.
+pub fn derive_type_info(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = syn::parse_macro_input!(item as syn::DeriveInput);
+	match expand_type_info(input) {
+		Ok(generated) => generated.into(),
+		Err(e) => e.to_compile_error().into(),
+	}
+}
+
+fn expand_type_info(input: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+	let name = &input.ident;
+	let name_str = name.to_string();
+
+	// Structs are composites of their fields. Enums are variants, indexed by their position like in
+	// their encoding.
+	let (def, field_types) = match &input.data {
+		syn::Data::Struct(data) => {
+			let fields = crate::pallet_enum::fields_metadata(&data.fields);
+			let def = quote! { crate::metadata::TypeDef::Composite(#fields) };
+			(def, data.fields.iter().map(|field| &field.ty).collect::<Vec<_>>())
+		},
+		syn::Data::Enum(data) => {
+			let index = (0..data.variants.len())
+				.map(|index| {
+					u8::try_from(index).map_err(|_| {
+						let msg = "enums with more than 256 variants cannot be described";
+						syn::Error::new(data.variants[index].span(), msg)
+					})
+				})
+				.collect::<syn::Result<Vec<_>>>()?;
+			let variant_name = data.variants.iter().map(|variant| variant.ident.to_string());
+			let fields = data
+				.variants
+				.iter()
+				.map(|variant| crate::pallet_enum::fields_metadata(&variant.fields));
+			let docs = data.variants.iter().map(|variant| crate::pallet_enum::docs(&variant.attrs));
+			let def = quote! {
+				crate::metadata::TypeDef::Variant(vec![
+					#(
+						crate::metadata::VariantMetadata {
+							name: #variant_name,
+							index: #index,
+							fields: #fields,
+							docs: #docs,
+						}
+					),*
+				])
+			};
+			let field_types = data.variants.iter().flat_map(|variant| &variant.fields);
+			(def, field_types.map(|field| &field.ty).collect())
+		},
+		syn::Data::Union(_) => {
+			return Err(syn::Error::new(input.span(), "unions cannot be described"));
+		},
+	};
+
+	let generics = with_bounds(&input.generics, field_types);
+	let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+	Ok(quote! {
+		impl #impl_generics crate::metadata::TypeInfo for #name #ty_generics #where_clause {
+			fn type_info(
+				registry: &mut crate::metadata::TypeRegistry,
+			) -> crate::metadata::TypeMetadata {
+				crate::metadata::TypeMetadata {
+					path: concat!(module_path!(), "::", #name_str),
+					def: #def,
+				}
+			}
+		}
+	})
+}
+
+/// Bound `generics` so that `types` can be described: each of them must implement
+/// `metadata::TypeInfo`, and the type parameters must be `'static`, like every described type.
+/// This is also used for the `Call`, `Event` and `Error` enums, and the metadata of storage items.
+pub fn with_bounds<T: ToTokens>(
+	generics: &syn::Generics,
+	types: impl IntoIterator<Item = T>,
+) -> syn::Generics {
+	let mut generics = generics.clone();
+	let params = generics.type_params().map(|param| param.ident.clone()).collect::<Vec<_>>();
+	let where_clause = generics.make_where_clause();
+	for param in params {
+		where_clause.predicates.push(syn::parse_quote!(#param: 'static));
+	}
+	for ty in types {
+		where_clause.predicates.push(syn::parse_quote!(#ty: crate::metadata::TypeInfo));
+	}
+	generics
+}
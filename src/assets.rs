@@ -0,0 +1,197 @@
+use crate::{
+	storage::{Hash64Concat, StorageDoubleMap, StorageMap, ValueQuery},
+	support::DispatchResult,
+};
+use core::fmt::Debug;
+use num::{CheckedAdd, CheckedSub, Zero};
+
+#[macros::config]
+pub trait Config: crate::balances::Config {
+	/// The identifier of an asset.
+	type AssetId: Ord + Copy + Debug + std::hash::Hash;
+}
+
+/// An asset, and who manages it.
+#[derive(Debug, Clone, PartialEq, Eq, macros::TypeInfo)]
+pub struct AssetDetails<AccountId, Balance> {
+	/// The account which created the asset, and may mint it.
+	pub owner: AccountId,
+	pub supply: Balance,
+}
+
+/// Fungible assets other than the native token of `balances::Pallet`. Asset balances use the same
+/// `Balance` type as the native token.
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	assets: StorageMap<Hash64Concat, T::AssetId, AssetDetails<T::AccountId, T::Balance>>,
+	accounts: StorageDoubleMap<
+		Hash64Concat,
+		T::AssetId,
+		Hash64Concat,
+		T::AccountId,
+		T::Balance,
+		ValueQuery,
+	>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn asset(&self, id: T::AssetId) -> Option<&AssetDetails<T::AccountId, T::Balance>> {
+		self.assets.get_ref(&id)
+	}
+
+	pub fn balance(&self, id: T::AssetId, who: &T::AccountId) -> T::Balance {
+		self.accounts.get(&id, who)
+	}
+
+	/// Check that `amount` of asset `id` can be moved from `from` to `to`, without moving it.
+	pub fn ensure_can_transfer(
+		&self,
+		id: T::AssetId,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		if !self.assets.contains_key(&id) {
+			return Err("asset does not exist".into());
+		}
+		self.balance(id, from).checked_sub(&amount).ok_or("Not enough funds.")?;
+		if from != to {
+			self.balance(id, to).checked_add(&amount).ok_or("Overflow")?;
+		}
+		Ok(())
+	}
+
+	/// Move `amount` of asset `id` from `from` to `to`.
+	pub fn do_transfer(
+		&mut self,
+		id: T::AssetId,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		self.ensure_can_transfer(id, from, to, amount)?;
+		if from == to {
+			return Ok(());
+		}
+		let new_from_balance =
+			self.balance(id, from).checked_sub(&amount).expect("checked above; qed");
+		let new_to_balance = self.balance(id, to).checked_add(&amount).expect("checked above; qed");
+		self.accounts.insert(id, from.clone(), new_from_balance);
+		self.accounts.insert(id, to.clone(), new_to_balance);
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Create asset `id`, owned by the caller.
+	#[call_index(0)]
+	pub fn create(&mut self, caller: T::AccountId, id: T::AssetId) -> DispatchResult {
+		if self.assets.contains_key(&id) {
+			return Err("asset already exists".into());
+		}
+		self.assets.insert(id, AssetDetails { owner: caller, supply: Zero::zero() });
+		Ok(())
+	}
+
+	/// Create `amount` of asset `id` for `beneficiary`. Only the owner of the asset may mint.
+	#[call_index(1)]
+	pub fn mint(
+		&mut self,
+		caller: T::AccountId,
+		id: T::AssetId,
+		beneficiary: T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		let details = self.assets.get_ref(&id).ok_or("asset does not exist")?;
+		if details.owner != caller {
+			return Err("not the owner of the asset".into());
+		}
+		let new_supply = details.supply.checked_add(&amount).ok_or("Overflow")?;
+		let new_balance = self.balance(id, &beneficiary).checked_add(&amount).ok_or("Overflow")?;
+
+		self.assets.get_mut(&id).expect("checked above; qed").supply = new_supply;
+		self.accounts.insert(id, beneficiary, new_balance);
+		Ok(())
+	}
+
+	#[call_index(2)]
+	pub fn transfer(
+		&mut self,
+		caller: T::AccountId,
+		id: T::AssetId,
+		to: T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		self.do_transfer(id, &caller, &to, amount)
+	}
+}
+
+impl<T: Config> crate::support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> crate::support::Hooks<T> for Pallet<T> {}
+
+#[cfg(test)]
+mod tests {
+	use super::AssetDetails;
+	use crate::Runtime;
+
+	#[test]
+	fn create_mint_and_transfer() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(
+			runtime.assets.mint(alice.clone(), 1, bob.clone(), 100),
+			Err("asset does not exist".into())
+		);
+		assert_eq!(runtime.assets.create(alice.clone(), 1), Ok(()));
+		assert_eq!(runtime.assets.create(bob.clone(), 1), Err("asset already exists".into()));
+		assert_eq!(
+			runtime.assets.mint(bob.clone(), 1, bob.clone(), 100),
+			Err("not the owner of the asset".into())
+		);
+		assert_eq!(runtime.assets.mint(alice.clone(), 1, bob.clone(), 100), Ok(()));
+		assert_eq!(
+			runtime.assets.asset(1),
+			Some(&AssetDetails { owner: alice.clone(), supply: 100 })
+		);
+
+		assert_eq!(
+			runtime.assets.transfer(bob.clone(), 1, alice.clone(), 101),
+			Err("Not enough funds.".into())
+		);
+		assert_eq!(runtime.assets.transfer(bob.clone(), 1, alice.clone(), 30), Ok(()));
+		assert_eq!(runtime.assets.balance(1, &alice), 30);
+		assert_eq!(runtime.assets.balance(1, &bob), 70);
+	}
+
+	#[test]
+	fn failed_transfer_changes_nothing() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.assets.create(alice.clone(), 1).unwrap();
+		runtime.assets.mint(alice.clone(), 1, alice.clone(), u128::MAX).unwrap();
+		assert_eq!(runtime.assets.transfer(alice.clone(), 1, bob.clone(), 10), Ok(()));
+
+		assert_eq!(runtime.assets.mint(alice.clone(), 1, bob.clone(), 1), Err("Overflow".into()));
+		assert_eq!(runtime.assets.asset(1).unwrap().supply, u128::MAX);
+		assert_eq!(
+			runtime.assets.transfer(alice.clone(), 1, bob.clone(), u128::MAX - 9),
+			Err("Not enough funds.".into())
+		);
+		assert_eq!(runtime.assets.balance(1, &alice), u128::MAX - 10);
+		assert_eq!(runtime.assets.balance(1, &bob), 10);
+
+		// Moving funds to oneself leaves the balance as it is.
+		assert_eq!(runtime.assets.transfer(bob.clone(), 1, bob.clone(), 10), Ok(()));
+		assert_eq!(runtime.assets.balance(1, &bob), 10);
+		assert_eq!(
+			runtime.assets.transfer(bob.clone(), 2, alice, 1),
+			Err("asset does not exist".into())
+		);
+	}
+}
@@ -1,91 +1,397 @@
-use num::traits::{CheckedAdd, CheckedSub, Zero};
-use std::collections::BTreeMap;
+pub use pallet::*;
 
-pub trait Config: crate::system::Config {
-	type Balance: Zero + CheckedSub + CheckedAdd + Copy;
-}
+#[macros::pallet]
+pub mod pallet {
+	use crate::{
+		storage::{Hash64Concat, Identity, StorageDoubleMap, StorageMap, ValueQuery},
+		support::{BuildGenesis, DispatchResult, Instance},
+	};
+	use num::traits::{CheckedAdd, CheckedSub, Zero};
 
-#[derive(Debug)]
-pub struct Pallet<T: Config> {
-	balances: BTreeMap<T::AccountId, T::Balance>,
-}
+	/// The config of an instance `I` of the pallet. Runtimes which hold more than one token include
+	/// the pallet once per token, each with its own instance. See `support::Instance`.
+	#[pallet::config]
+	pub trait Config<I: Instance = ()>: crate::system::Config {
+		type Balance: Zero + CheckedSub + CheckedAdd + Copy + Ord + Default + std::fmt::Debug;
+	}
 
-impl<T: Config> Pallet<T> {
-	pub fn new() -> Self {
-		Self { balances: BTreeMap::new() }
+	/// The name of a lock, so that each pallet can manage its own locks on an account.
+	pub type LockIdentifier = [u8; 8];
+
+	#[pallet::storage]
+	#[derive(Debug)]
+	pub struct Pallet<T: Config<I>, I: Instance = ()> {
+		#[getter(balance)]
+		#[setter(set_balance)]
+		balances: StorageMap<Hash64Concat, T::AccountId, T::Balance, ValueQuery>,
+		#[getter(reserved_balance)]
+		reserved: StorageMap<Hash64Concat, T::AccountId, T::Balance, ValueQuery>,
+		/// The locks on the free balance of each account. Locks overlap, so the locked amount is
+		/// the largest of them.
+		locks: StorageDoubleMap<Hash64Concat, T::AccountId, Identity, LockIdentifier, T::Balance>,
 	}
 
-	pub fn set_balance(&mut self, who: T::AccountId, amount: T::Balance) {
-		self.balances.insert(who, amount);
+	/// The free balances of the first accounts of the chain.
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config<I>, I: Instance = ()> {
+		pub balances: Vec<(T::AccountId, T::Balance)>,
 	}
 
-	pub fn balance(&self, who: &T::AccountId) -> T::Balance {
-		*self.balances.get(who).unwrap_or(&T::Balance::zero())
+	#[pallet::genesis_build]
+	impl<T: Config<I>, I: Instance> BuildGenesis<Pallet<T, I>> for GenesisConfig<T, I> {
+		fn build(&self, pallet: &mut Pallet<T, I>) {
+			for (who, amount) in &self.balances {
+				pallet.set_balance(who.clone(), *amount);
+			}
+		}
 	}
-}
 
-#[macros::call]
-impl<T: Config> Pallet<T> {
-	pub fn transfer(
-		&mut self,
-		caller: T::AccountId,
-		to: T::AccountId,
-		amount: T::Balance,
-	) -> crate::support::DispatchResult {
-		let caller_balance = self.balance(&caller);
-		let to_balance = self.balance(&to);
+	impl<T: Config<I>, I: Instance> Pallet<T, I> {
+		/// The part of the free balance of `who` which cannot be moved out of their account.
+		pub fn locked_balance(&self, who: &T::AccountId) -> T::Balance {
+			let locks = self.locks.iter_prefix(who).map(|(_, amount)| amount);
+			locks.copied().max().unwrap_or(T::Balance::zero())
+		}
 
-		let new_caller_balance = caller_balance.checked_sub(&amount).ok_or("Not enough funds.")?;
-		let new_to_balance = to_balance.checked_add(&amount).ok_or("Overflow")?;
+		/// Lock `amount` of the free balance of `who`, replacing the lock `id` if it exists. Locked
+		/// funds stay in the free balance, and can be used by several locks at the same time, for
+		/// example to vote and to stake.
+		pub fn set_lock(&mut self, id: LockIdentifier, who: &T::AccountId, amount: T::Balance) {
+			if amount.is_zero() {
+				return self.remove_lock(id, who);
+			}
+			self.locks.insert(who.clone(), id, amount);
+		}
 
-		self.balances.insert(caller, new_caller_balance);
-		self.balances.insert(to, new_to_balance);
+		pub fn remove_lock(&mut self, id: LockIdentifier, who: &T::AccountId) {
+			self.locks.remove(who, &id);
+		}
 
-		Ok(())
-	}
-}
+		/// Check that the free balance of `who` can be reduced to `new_balance`, given their locks.
+		fn ensure_can_withdraw(
+			&self,
+			who: &T::AccountId,
+			new_balance: T::Balance,
+		) -> DispatchResult {
+			if new_balance < self.locked_balance(who) {
+				return Err("Liquidity restrictions prevent withdrawal.".into());
+			}
+			Ok(())
+		}
 
-#[cfg(test)]
-mod tests {
-	struct TestConfig;
+		/// Check that `amount` can be withdrawn from the free balance of `who` once their lock `id`
+		/// is reduced by the same amount, for example to slash locked funds. The other locks of
+		/// `who` still apply.
+		pub fn ensure_can_withdraw_locked(
+			&self,
+			id: LockIdentifier,
+			who: &T::AccountId,
+			amount: T::Balance,
+		) -> DispatchResult {
+			let new_balance = self.balance(who).checked_sub(&amount).ok_or("Not enough funds.")?;
+			let locks = self.locks.iter_prefix(who).map(|(lock_id, locked)| {
+				if *lock_id == id {
+					locked.checked_sub(&amount).unwrap_or_default()
+				} else {
+					*locked
+				}
+			});
+			if new_balance < locks.max().unwrap_or_default() {
+				return Err("Liquidity restrictions prevent withdrawal.".into());
+			}
+			Ok(())
+		}
 
-	impl crate::system::Config for TestConfig {
-		type AccountId = String;
-		type BlockNumber = u32;
-		type Nonce = u32;
-	}
+		/// Check that `amount` can be transferred from `from` to `to`, without transferring it.
+		pub fn ensure_can_transfer(
+			&self,
+			from: &T::AccountId,
+			to: &T::AccountId,
+			amount: T::Balance,
+		) -> DispatchResult {
+			let new_from_balance =
+				self.balance(from).checked_sub(&amount).ok_or("Not enough funds.")?;
+			self.ensure_can_withdraw(from, new_from_balance)?;
+			self.balance(to).checked_add(&amount).ok_or("Overflow")?;
+			Ok(())
+		}
+
+		/// Add `amount` to the free balance of `who`, creating new funds.
+		pub fn deposit(&mut self, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+			let new_balance = self.balance(who).checked_add(&amount).ok_or("Overflow")?;
+			self.balances.insert(who.clone(), new_balance);
+			Ok(())
+		}
+
+		/// Remove `amount` from the free balance of `who`. The funds are destroyed, unless the
+		/// caller deposits them somewhere else.
+		pub fn withdraw(&mut self, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+			let new_balance = self.balance(who).checked_sub(&amount).ok_or("Not enough funds.")?;
+			self.ensure_can_withdraw(who, new_balance)?;
+			self.balances.insert(who.clone(), new_balance);
+			Ok(())
+		}
+
+		/// Move `amount` from the free balance of `who` into their reserved balance. Reserved funds
+		/// cannot be transferred, and are used by other pallets to hold deposits.
+		pub fn reserve(&mut self, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+			let new_balance = self.balance(who).checked_sub(&amount).ok_or("Not enough funds.")?;
+			self.ensure_can_withdraw(who, new_balance)?;
+			let new_reserved = self.reserved_balance(who).checked_add(&amount).ok_or("Overflow")?;
+
+			self.balances.insert(who.clone(), new_balance);
+			self.reserved.insert(who.clone(), new_reserved);
 
-	impl super::Config for TestConfig {
-		type Balance = u128;
+			Ok(())
+		}
+
+		/// Move `amount` from the reserved balance of `who` back into their free balance.
+		pub fn unreserve(&mut self, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+			let new_reserved = self
+				.reserved_balance(who)
+				.checked_sub(&amount)
+				.ok_or("Not enough reserved funds.")?;
+			let new_balance = self.balance(who).checked_add(&amount).ok_or("Overflow")?;
+
+			self.reserved.insert(who.clone(), new_reserved);
+			self.balances.insert(who.clone(), new_balance);
+
+			Ok(())
+		}
+
+		/// Remove `amount` from the reserved balance of `who`, as a punishment. Like `withdraw`,
+		/// the funds are destroyed, unless the caller deposits them somewhere else.
+		pub fn slash_reserved(&mut self, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+			let new_reserved = self
+				.reserved_balance(who)
+				.checked_sub(&amount)
+				.ok_or("Not enough reserved funds.")?;
+			self.reserved.insert(who.clone(), new_reserved);
+			Ok(())
+		}
+
+		/// Move `amount` from the reserved balance of `from` into the reserved balance of `to`, for
+		/// example to hand over a deposit along with what it is held for.
+		pub fn repatriate_reserved(
+			&mut self,
+			from: &T::AccountId,
+			to: &T::AccountId,
+			amount: T::Balance,
+		) -> DispatchResult {
+			let new_from_reserved = self
+				.reserved_balance(from)
+				.checked_sub(&amount)
+				.ok_or("Not enough reserved funds.")?;
+			if from == to {
+				return Ok(());
+			}
+			let new_to_reserved =
+				self.reserved_balance(to).checked_add(&amount).ok_or("Overflow")?;
+
+			self.reserved.insert(from.clone(), new_from_reserved);
+			self.reserved.insert(to.clone(), new_to_reserved);
+
+			Ok(())
+		}
 	}
 
-	#[test]
-	fn init_balances() {
-		let mut balances = super::Pallet::<TestConfig>::new();
+	#[pallet::call]
+	impl<T: Config<I>, I: Instance> Pallet<T, I> {
+		#[call_index(0)]
+		pub fn transfer(
+			&mut self,
+			caller: T::AccountId,
+			to: T::AccountId,
+			amount: T::Balance,
+		) -> DispatchResult {
+			let caller_balance = self.balance(&caller);
+			let to_balance = self.balance(&to);
+
+			let new_caller_balance =
+				caller_balance.checked_sub(&amount).ok_or("Not enough funds.")?;
+			self.ensure_can_withdraw(&caller, new_caller_balance)?;
+			let new_to_balance = to_balance.checked_add(&amount).ok_or("Overflow")?;
+
+			self.balances.insert(caller, new_caller_balance);
+			self.balances.insert(to, new_to_balance);
 
-		assert_eq!(balances.balance(&"alice".to_string()), 0);
-		balances.set_balance("alice".to_string(), 100);
-		assert_eq!(balances.balance(&"alice".to_string()), 100);
-		assert_eq!(balances.balance(&"bob".to_string()), 0);
+			Ok(())
+		}
 	}
 
-	#[test]
-	fn transfer_balance() {
-		let mut balances = super::Pallet::<TestConfig>::new();
+	#[cfg(test)]
+	mod tests {
+		use crate::support::{BuildGenesis, Instance1};
+
+		struct TestConfig;
+
+		impl crate::system::Config for TestConfig {
+			type AccountId = String;
+			type BlockNumber = u32;
+			type Nonce = u32;
+			type RuntimeEvent = ();
+		}
+
+		impl super::Config for TestConfig {
+			type Balance = u128;
+		}
+
+		impl super::Config<Instance1> for TestConfig {
+			type Balance = u64;
+		}
+
+		#[test]
+		fn init_balances() {
+			let mut balances = super::Pallet::<TestConfig>::new();
+
+			assert_eq!(balances.balance(&"alice".to_string()), 0);
+			balances.set_balance("alice".to_string(), 100);
+			assert_eq!(balances.balance(&"alice".to_string()), 100);
+			assert_eq!(balances.balance(&"bob".to_string()), 0);
+		}
+
+		#[test]
+		fn transfer_balance() {
+			let mut balances = super::Pallet::<TestConfig>::new();
+
+			assert_eq!(
+				balances.transfer("alice".to_string(), "bob".to_string(), 51),
+				Err("Not enough funds.".into())
+			);
+
+			balances.set_balance("alice".to_string(), 100);
+			assert_eq!(balances.transfer("alice".to_string(), "bob".to_string(), 51), Ok(()));
+			assert_eq!(balances.balance(&"alice".to_string()), 49);
+			assert_eq!(balances.balance(&"bob".to_string()), 51);
+
+			assert_eq!(
+				balances.transfer("alice".to_string(), "bob".to_string(), 51),
+				Err("Not enough funds.".into())
+			);
+		}
+
+		#[test]
+		fn reserve_balance() {
+			let mut balances = super::Pallet::<TestConfig>::new();
+			let alice = "alice".to_string();
+
+			balances.set_balance(alice.clone(), 100);
+			assert_eq!(balances.reserve(&alice, 101), Err("Not enough funds.".into()));
+			assert_eq!(balances.reserve(&alice, 60), Ok(()));
+			assert_eq!(balances.balance(&alice), 40);
+			assert_eq!(balances.reserved_balance(&alice), 60);
+
+			// Reserved funds cannot be transferred.
+			assert_eq!(
+				balances.transfer(alice.clone(), "bob".to_string(), 50),
+				Err("Not enough funds.".into())
+			);
+
+			assert_eq!(balances.unreserve(&alice, 61), Err("Not enough reserved funds.".into()));
+			assert_eq!(balances.unreserve(&alice, 60), Ok(()));
+			assert_eq!(balances.balance(&alice), 100);
+			assert_eq!(balances.reserved_balance(&alice), 0);
+		}
+
+		#[test]
+		fn withdraw_and_slash() {
+			let mut balances = super::Pallet::<TestConfig>::new();
+			let alice = "alice".to_string();
+
+			assert_eq!(balances.deposit(&alice, 100), Ok(()));
+			assert_eq!(balances.withdraw(&alice, 101), Err("Not enough funds.".into()));
+			assert_eq!(balances.withdraw(&alice, 10), Ok(()));
+			assert_eq!(balances.balance(&alice), 90);
+
+			assert_eq!(balances.reserve(&alice, 50), Ok(()));
+			assert_eq!(
+				balances.slash_reserved(&alice, 51),
+				Err("Not enough reserved funds.".into())
+			);
+			assert_eq!(balances.slash_reserved(&alice, 20), Ok(()));
+			assert_eq!(balances.balance(&alice), 40);
+			assert_eq!(balances.reserved_balance(&alice), 30);
+		}
+
+		#[test]
+		fn lock_balance() {
+			let mut balances = super::Pallet::<TestConfig>::new();
+			let alice = "alice".to_string();
+
+			balances.set_balance(alice.clone(), 100);
+			balances.set_lock(*b"staking ", &alice, 60);
+			balances.set_lock(*b"democrac", &alice, 30);
+			assert_eq!(balances.locked_balance(&alice), 60);
+
+			// Locked funds stay in the free balance, but cannot leave it.
+			assert_eq!(
+				balances.transfer(alice.clone(), "bob".to_string(), 41),
+				Err("Liquidity restrictions prevent withdrawal.".into())
+			);
+			assert_eq!(
+				balances.reserve(&alice, 41),
+				Err("Liquidity restrictions prevent withdrawal.".into())
+			);
+			assert_eq!(balances.withdraw(&alice, 40), Ok(()));
+
+			balances.remove_lock(*b"staking ", &alice);
+			assert_eq!(balances.locked_balance(&alice), 30);
+			balances.set_lock(*b"democrac", &alice, 0);
+			assert_eq!(balances.locked_balance(&alice), 0);
+			assert_eq!(balances.transfer(alice, "bob".to_string(), 60), Ok(()));
+		}
+
+		#[test]
+		fn withdraw_locked_funds() {
+			let mut balances = super::Pallet::<TestConfig>::new();
+			let alice = "alice".to_string();
+
+			balances.set_balance(alice.clone(), 100);
+			balances.set_lock(*b"staking ", &alice, 100);
+			balances.set_lock(*b"democrac", &alice, 70);
+
+			// The staking lock shrinks with the withdrawn funds, but the other lock still applies.
+			assert_eq!(balances.ensure_can_withdraw_locked(*b"staking ", &alice, 30), Ok(()));
+			assert_eq!(
+				balances.ensure_can_withdraw_locked(*b"staking ", &alice, 31),
+				Err("Liquidity restrictions prevent withdrawal.".into())
+			);
+			assert_eq!(
+				balances.ensure_can_withdraw_locked(*b"staking ", &alice, 101),
+				Err("Not enough funds.".into())
+			);
+		}
+
+		#[test]
+		fn build_genesis() {
+			let mut balances = super::Pallet::<TestConfig>::new();
+			let genesis = super::GenesisConfig::<TestConfig> {
+				balances: vec![("alice".to_string(), 100), ("bob".to_string(), 50)],
+			};
+
+			genesis.build(&mut balances);
+			assert_eq!(balances.balance(&"alice".to_string()), 100);
+			assert_eq!(balances.balance(&"bob".to_string()), 50);
+			assert_eq!(balances.balance(&"charlie".to_string()), 0);
+		}
 
-		assert_eq!(
-			balances.transfer("alice".to_string(), "bob".to_string(), 51),
-			Err("Not enough funds.".to_string())
-		);
+		#[test]
+		fn instances_have_their_own_storage() {
+			let mut balances = super::Pallet::<TestConfig>::new();
+			let mut tokens = super::Pallet::<TestConfig, Instance1>::new();
+			let alice = "alice".to_string();
 
-		balances.set_balance("alice".to_string(), 100);
-		assert_eq!(balances.transfer("alice".to_string(), "bob".to_string(), 51), Ok(()));
-		assert_eq!(balances.balance(&"alice".to_string()), 49);
-		assert_eq!(balances.balance(&"bob".to_string()), 51);
+			balances.set_balance(alice.clone(), 100);
+			tokens.set_balance(alice.clone(), 7);
+			assert_eq!(tokens.transfer(alice.clone(), "bob".to_string(), 2), Ok(()));
+			assert_eq!(balances.balance(&alice), 100);
+			assert_eq!(tokens.balance(&alice), 5);
 
-		assert_eq!(
-			balances.transfer("alice".to_string(), "bob".to_string(), 51),
-			Err("Not enough funds.".to_string())
-		);
+			// The default instance has the prefixes of a pallet which is not instantiable.
+			let prefix = crate::storage::storage_prefix("balances", "balances");
+			assert!(balances.balances.hashed_key_for(&alice).starts_with(&prefix));
+			let prefix = crate::storage::storage_prefix("balancesInstance1", "balances");
+			assert!(tokens.balances.hashed_key_for(&alice).starts_with(&prefix));
+		}
 	}
 }
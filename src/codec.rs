@@ -0,0 +1,202 @@
+//! A simple binary codec, used to store values like calls as bytes.
+//!
+//! Integers are encoded in little endian, and sequences are prefixed with their length as a
+//! `u32`. Enums are encoded as the index of their variant as a `u8`, followed by its fields.
+//!
+//! `#[macros::call]` and `#[macros::runtime]` implement this codec for `Call` and `RuntimeCall`,
+//! where the index of a call is given with `#[call_index(n)]` on its function, and the index of a
+//! pallet with `#[pallet_index(n)]` on its field in the `Runtime` struct. Indices are explicit, so
+//! that reordering calls or pallets does not change the encoding.
+
+pub trait Encode {
+	/// Append the encoding of `self` to `dest`.
+	fn encode_to(&self, dest: &mut Vec<u8>);
+
+	fn encode(&self) -> Vec<u8> {
+		let mut dest = Vec::new();
+		self.encode_to(&mut dest);
+		dest
+	}
+}
+
+pub trait Decode: Sized {
+	/// Decode a value from the start of `input`, and advance `input` past it.
+	fn decode(input: &mut &[u8]) -> Result<Self, String>;
+
+	/// Decode a value from all of `bytes`.
+	fn decode_all(mut bytes: &[u8]) -> Result<Self, String> {
+		let value = Self::decode(&mut bytes)?;
+		if !bytes.is_empty() {
+			return Err("input has trailing bytes".to_string());
+		}
+		Ok(value)
+	}
+}
+
+/// Take the first `len` bytes of `input`.
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], String> {
+	if input.len() < len {
+		return Err("input is too short".to_string());
+	}
+	let (bytes, rest) = input.split_at(len);
+	*input = rest;
+	Ok(bytes)
+}
+
+macro_rules! impl_integer {
+	($($int:ty),*) => {$(
+		impl Encode for $int {
+			fn encode_to(&self, dest: &mut Vec<u8>) {
+				dest.extend_from_slice(&self.to_le_bytes());
+			}
+		}
+
+		impl Decode for $int {
+			fn decode(input: &mut &[u8]) -> Result<Self, String> {
+				let bytes = take(input, core::mem::size_of::<$int>())?;
+				Ok(<$int>::from_le_bytes(bytes.try_into().expect("took the size of the integer; qed")))
+			}
+		}
+	)*};
+}
+
+impl_integer!(u8, u16, u32, u64, u128);
+
+impl Encode for bool {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		u8::from(*self).encode_to(dest);
+	}
+}
+
+impl Decode for bool {
+	fn decode(input: &mut &[u8]) -> Result<Self, String> {
+		match u8::decode(input)? {
+			0 => Ok(false),
+			1 => Ok(true),
+			_ => Err("invalid bool".to_string()),
+		}
+	}
+}
+
+/// Encode the length of a sequence.
+fn encode_len(len: usize, dest: &mut Vec<u8>) {
+	u32::try_from(len)
+		.expect("sequences are shorter than `u32::MAX`; qed")
+		.encode_to(dest);
+}
+
+impl Encode for str {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		encode_len(self.len(), dest);
+		dest.extend_from_slice(self.as_bytes());
+	}
+}
+
+impl Encode for String {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		self.as_str().encode_to(dest);
+	}
+}
+
+impl Decode for String {
+	fn decode(input: &mut &[u8]) -> Result<Self, String> {
+		let len = u32::decode(input)? as usize;
+		let bytes = take(input, len)?;
+		String::from_utf8(bytes.to_vec()).map_err(|_| "invalid utf8".to_string())
+	}
+}
+
+impl Encode for &'static str {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		(**self).encode_to(dest);
+	}
+}
+
+/// Decoded strings are leaked, since they must live for the rest of the program. This is fine for
+/// our simple state machine, which only uses them for small values like claims.
+impl Decode for &'static str {
+	fn decode(input: &mut &[u8]) -> Result<Self, String> {
+		Ok(String::decode(input)?.leak())
+	}
+}
+
+impl<T: Encode> Encode for Vec<T> {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		encode_len(self.len(), dest);
+		for item in self {
+			item.encode_to(dest);
+		}
+	}
+}
+
+impl<T: Decode> Decode for Vec<T> {
+	fn decode(input: &mut &[u8]) -> Result<Self, String> {
+		let len = u32::decode(input)?;
+		// The length is not trusted to preallocate, since the input may be malicious.
+		(0..len).map(|_| T::decode(input)).collect()
+	}
+}
+
+impl<T: Encode> Encode for Option<T> {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		match self {
+			None => 0u8.encode_to(dest),
+			Some(value) => {
+				1u8.encode_to(dest);
+				value.encode_to(dest);
+			},
+		}
+	}
+}
+
+impl<T: Decode> Decode for Option<T> {
+	fn decode(input: &mut &[u8]) -> Result<Self, String> {
+		match u8::decode(input)? {
+			0 => Ok(None),
+			1 => Ok(Some(T::decode(input)?)),
+			_ => Err("invalid option".to_string()),
+		}
+	}
+}
+
+impl<T: Encode> Encode for Box<T> {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		(**self).encode_to(dest);
+	}
+}
+
+impl<T: Decode> Decode for Box<T> {
+	fn decode(input: &mut &[u8]) -> Result<Self, String> {
+		Ok(Box::new(T::decode(input)?))
+	}
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		self.0.encode_to(dest);
+		self.1.encode_to(dest);
+	}
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+	fn decode(input: &mut &[u8]) -> Result<Self, String> {
+		Ok((A::decode(input)?, B::decode(input)?))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Decode, Encode};
+
+	#[test]
+	fn encode_and_decode() {
+		let value = (vec![Some("alice".to_string()), None], 42u128);
+		let bytes = value.encode();
+		assert_eq!(bytes.len(), 4 + (1 + 4 + 5) + 1 + 16);
+		assert_eq!(Decode::decode_all(&bytes), Ok(value));
+
+		assert_eq!(u32::decode_all(&[1, 0, 0]), Err("input is too short".to_string()));
+		assert_eq!(u8::decode_all(&[1, 0]), Err("input has trailing bytes".to_string()));
+		assert_eq!(bool::decode_all(&[2]), Err("invalid bool".to_string()));
+	}
+}
@@ -0,0 +1,388 @@
+use crate::{
+	storage::{Hash64Concat, StorageMap, StorageValue, ValueQuery},
+	support::{self, Dispatch, DispatchResult, GetPallet, Hash, MemberCount, RawOrigin},
+	system::Origin,
+};
+use num::CheckedAdd;
+
+#[macros::config]
+pub trait Config:
+	Sized
+	+ crate::system::Config
+	+ GetPallet<crate::system::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+	+ Dispatch<Caller = Origin<Self>, Call = <Self as Config>::RuntimeCall>
+{
+	/// The overarching call type, which approved motions dispatch.
+	type RuntimeCall: std::hash::Hash + Clone + std::fmt::Debug;
+	/// How long a motion is open for votes, in blocks. After that, members who did not vote are
+	/// counted as voting like the prime member.
+	const MOTION_DURATION: Self::BlockNumber;
+	/// The maximum number of members.
+	const MAX_MEMBERS: MemberCount;
+}
+
+/// The index of a motion.
+pub type ProposalIndex = u32;
+
+/// A motion of a member, open for votes.
+#[derive(Debug, macros::TypeInfo)]
+pub struct Motion<T: Config> {
+	/// The index of the motion, which tells apart motions with the same call over time.
+	pub index: ProposalIndex,
+	/// The call dispatched with a collective origin if the motion is approved.
+	pub call: <T as Config>::RuntimeCall,
+	/// The number of aye votes needed to approve the motion.
+	pub threshold: MemberCount,
+	pub ayes: Vec<T::AccountId>,
+	pub nays: Vec<T::AccountId>,
+	/// The block after which the motion can be closed with the prime member's defaults.
+	pub end: T::BlockNumber,
+}
+
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	/// The sorted list of members.
+	members: StorageValue<Vec<T::AccountId>, ValueQuery>,
+	/// The member whose vote is the default for members who did not vote.
+	prime: StorageValue<T::AccountId>,
+	/// The number of motions made so far, used as the index of the next one.
+	proposal_count: StorageValue<ProposalIndex, ValueQuery>,
+	/// Open motions, indexed by the hash of their call.
+	proposals: StorageMap<Hash64Concat, Hash, Motion<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn members(&self) -> &[T::AccountId] {
+		self.members.get_ref().map(Vec::as_slice).unwrap_or_default()
+	}
+
+	pub fn prime(&self) -> Option<&T::AccountId> {
+		self.prime.get_ref()
+	}
+
+	pub fn is_member(&self, who: &T::AccountId) -> bool {
+		self.members().binary_search(who).is_ok()
+	}
+
+	pub fn motion(&self, proposal: Hash) -> Option<&Motion<T>> {
+		self.proposals.get_ref(&proposal)
+	}
+
+	fn ensure_member(&self, who: &T::AccountId) -> DispatchResult {
+		if !self.is_member(who) {
+			return Err("not a member".into());
+		}
+		Ok(())
+	}
+}
+
+// The calls are boxed, since `Call` would otherwise contain itself through `RuntimeCall`.
+#[allow(clippy::boxed_local)]
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Replace the members, and the prime member. Only root may set the members.
+	///
+	/// Votes of outgoing members are removed from open motions.
+	#[call_index(0)]
+	pub fn set_members(
+		&mut self,
+		origin: Origin<T>,
+		new_members: Vec<T::AccountId>,
+		prime: Option<T::AccountId>,
+	) -> DispatchResult {
+		support::ensure_root(origin)?;
+		if new_members.len() > T::MAX_MEMBERS as usize {
+			return Err("too many members".into());
+		}
+		let mut new_members = new_members;
+		new_members.sort();
+		if new_members.windows(2).any(|pair| pair[0] == pair[1]) {
+			return Err("duplicate members".into());
+		}
+		if prime.as_ref().is_some_and(|prime| new_members.binary_search(prime).is_err()) {
+			return Err("prime is not a member".into());
+		}
+
+		for motion in self.proposals.iter_values_mut() {
+			motion.ayes.retain(|who| new_members.binary_search(who).is_ok());
+			motion.nays.retain(|who| new_members.binary_search(who).is_ok());
+		}
+		self.members.put(new_members);
+		self.prime.set(prime);
+		Ok(())
+	}
+
+	/// Propose a motion to dispatch `proposal`, which needs `threshold` aye votes. The proposer
+	/// votes aye.
+	///
+	/// Motions with a threshold below two are dispatched right away.
+	#[call_index(1)]
+	pub fn propose(
+		runtime: &mut T,
+		caller: T::AccountId,
+		threshold: MemberCount,
+		proposal: Box<<T as Config>::RuntimeCall>,
+	) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.ensure_member(&caller)?;
+		let total = pallet.members().len() as MemberCount;
+
+		if threshold < 2 {
+			return runtime.dispatch(RawOrigin::Collective(1, total), *proposal);
+		}
+
+		let proposal_hash = support::hash_of(&proposal);
+		if pallet.proposals.contains_key(&proposal_hash) {
+			return Err("duplicate proposal".into());
+		}
+
+		let index = pallet.proposal_count.get();
+		pallet.proposal_count.put(index.checked_add(1).ok_or("too many proposals")?);
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		let end = system
+			.block_number()
+			.checked_add(&T::MOTION_DURATION)
+			.ok_or("block number overflow")?;
+
+		let motion =
+			Motion { index, call: *proposal, threshold, ayes: vec![caller], nays: Vec::new(), end };
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.proposals.insert(proposal_hash, motion);
+		Ok(())
+	}
+
+	/// Vote on an open motion, replacing any previous vote on it.
+	#[call_index(2)]
+	pub fn vote(
+		&mut self,
+		caller: T::AccountId,
+		proposal: Hash,
+		index: ProposalIndex,
+		approve: bool,
+	) -> DispatchResult {
+		self.ensure_member(&caller)?;
+		let motion = self.proposals.get_mut(&proposal).ok_or("proposal does not exist")?;
+		if motion.index != index {
+			return Err("mismatched index".into());
+		}
+
+		let (votes, other_votes) = if approve {
+			(&mut motion.ayes, &mut motion.nays)
+		} else {
+			(&mut motion.nays, &mut motion.ayes)
+		};
+		if votes.contains(&caller) {
+			return Err("duplicate vote".into());
+		}
+		other_votes.retain(|who| *who != caller);
+		votes.push(caller);
+		Ok(())
+	}
+
+	/// Close a motion, and dispatch its call with a `Collective` origin if it is approved. If the
+	/// call fails, the motion stays open, so that it can be closed again later.
+	///
+	/// A motion can be closed once enough members voted to decide it, or once its duration is
+	/// over. In that case, members who did not vote are counted as voting like the prime member,
+	/// or against the motion if there is no prime member.
+	#[call_index(3)]
+	pub fn close(
+		runtime: &mut T,
+		_caller: T::AccountId,
+		proposal: Hash,
+		index: ProposalIndex,
+	) -> DispatchResult {
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		let now = system.block_number();
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let total = pallet.members().len() as MemberCount;
+		let motion = pallet.proposals.get_ref(&proposal).ok_or("proposal does not exist")?;
+		if motion.index != index {
+			return Err("mismatched index".into());
+		}
+
+		let mut ayes = motion.ayes.len() as MemberCount;
+		let nays = motion.nays.len() as MemberCount;
+		let approved = if ayes >= motion.threshold {
+			true
+		} else if nays > total.saturating_sub(motion.threshold) {
+			false
+		} else if now > motion.end {
+			let prime_aye = pallet.prime().is_some_and(|prime| motion.ayes.contains(prime));
+			if prime_aye {
+				ayes = total - nays;
+			}
+			ayes >= motion.threshold
+		} else {
+			return Err("motion is still open".into());
+		};
+
+		// The motion is only removed once its call succeeded, like multisig operations.
+		if approved {
+			let call = motion.call.clone();
+			runtime.dispatch(RawOrigin::Collective(ayes, total), call)?;
+		}
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.proposals.remove(&proposal);
+		Ok(())
+	}
+}
+
+impl<T: Config> support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> support::Hooks<T> for Pallet<T> {}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		support::{self, RawOrigin},
+		Runtime, RuntimeCall,
+	};
+
+	type Collective = super::Pallet<Runtime>;
+
+	fn approve_spend(proposal_id: u32) -> Box<RuntimeCall> {
+		Box::new(RuntimeCall::treasury(crate::treasury::Call::approve_proposal { proposal_id }))
+	}
+
+	/// A runtime with a council of alice, bob, charlie and eve, and a treasury spend proposal.
+	fn new_runtime(prime: Option<&str>) -> Runtime {
+		let mut runtime = Runtime::new();
+		let members = ["charlie", "alice", "eve", "bob"].map(|member| member.to_string()).to_vec();
+		let prime = prime.map(|prime| prime.to_string());
+		assert_eq!(runtime.collective.set_members(RawOrigin::Root, members, prime), Ok(()));
+
+		runtime.balances.set_balance("dave".to_string(), 100);
+		assert_eq!(
+			crate::treasury::Pallet::<Runtime>::propose_spend(
+				&mut runtime,
+				"dave".to_string(),
+				10,
+				"dave".to_string()
+			),
+			Ok(())
+		);
+		runtime
+	}
+
+	#[test]
+	fn set_members() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(
+			runtime.collective.set_members(RawOrigin::Signed(alice.clone()), vec![], None),
+			Err("bad origin: expected the root origin".into())
+		);
+		assert_eq!(
+			runtime.collective.set_members(
+				RawOrigin::Root,
+				vec![alice.clone(), alice.clone()],
+				None
+			),
+			Err("duplicate members".into())
+		);
+		assert_eq!(
+			runtime
+				.collective
+				.set_members(RawOrigin::Root, vec![alice.clone()], Some(bob.clone())),
+			Err("prime is not a member".into())
+		);
+		assert_eq!(
+			runtime.collective.set_members(
+				RawOrigin::Root,
+				vec![bob.clone(), alice.clone()],
+				Some(bob.clone())
+			),
+			Ok(())
+		);
+		assert_eq!(runtime.collective.members(), &[alice, bob.clone()]);
+		assert_eq!(runtime.collective.prime(), Some(&bob));
+	}
+
+	#[test]
+	fn approved_motion_is_dispatched() {
+		let mut runtime = new_runtime(None);
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		let hash = support::hash_of(&approve_spend(0));
+
+		assert_eq!(
+			Collective::propose(&mut runtime, "dave".to_string(), 2, approve_spend(0)),
+			Err("not a member".into())
+		);
+		assert_eq!(Collective::propose(&mut runtime, alice.clone(), 2, approve_spend(0)), Ok(()));
+		assert_eq!(
+			Collective::propose(&mut runtime, bob.clone(), 2, approve_spend(0)),
+			Err("duplicate proposal".into())
+		);
+		assert_eq!(
+			runtime.collective.vote(alice.clone(), hash, 0, true),
+			Err("duplicate vote".into())
+		);
+		assert_eq!(
+			runtime.collective.vote(bob.clone(), hash, 1, true),
+			Err("mismatched index".into())
+		);
+		assert_eq!(
+			Collective::close(&mut runtime, charlie.clone(), hash, 0),
+			Err("motion is still open".into())
+		);
+
+		// The threshold is reached, but the treasury requires two thirds of the council, so the
+		// call fails and the motion stays open.
+		assert_eq!(runtime.collective.vote(bob.clone(), hash, 0, true), Ok(()));
+		assert_eq!(
+			Collective::close(&mut runtime, charlie.clone(), hash, 0),
+			Err("bad origin: expected enough approvals of the council".into())
+		);
+		assert!(runtime.collective.motion(hash).is_some());
+		assert!(runtime.treasury.approvals().is_empty());
+
+		assert_eq!(runtime.collective.vote(charlie.clone(), hash, 0, true), Ok(()));
+		assert_eq!(Collective::close(&mut runtime, alice.clone(), hash, 0), Ok(()));
+		assert!(runtime.collective.motion(hash).is_none());
+		assert_eq!(runtime.treasury.approvals(), &[0]);
+
+		// The next motion for the same call gets a new index.
+		assert_eq!(Collective::propose(&mut runtime, alice, 3, approve_spend(0)), Ok(()));
+		assert_eq!(runtime.collective.motion(hash).map(|motion| motion.index), Some(1));
+	}
+
+	#[test]
+	fn prime_member_defaults() {
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let hash = support::hash_of(&approve_spend(0));
+
+		// Without a prime member, members who did not vote count as nays.
+		let mut runtime = new_runtime(None);
+		assert_eq!(Collective::propose(&mut runtime, alice.clone(), 2, approve_spend(0)), Ok(()));
+		for _ in 0..3 {
+			runtime.system.inc_block_number().unwrap();
+		}
+		assert_eq!(
+			Collective::close(&mut runtime, alice.clone(), hash, 0),
+			Err("motion is still open".into())
+		);
+		runtime.system.inc_block_number().unwrap();
+		assert_eq!(Collective::close(&mut runtime, alice.clone(), hash, 0), Ok(()));
+		assert!(runtime.collective.motion(hash).is_none());
+		assert!(runtime.treasury.approvals().is_empty());
+
+		// The prime member voted aye, so the members who did not vote count as ayes.
+		let mut runtime = new_runtime(Some("bob"));
+		assert_eq!(Collective::propose(&mut runtime, alice.clone(), 3, approve_spend(0)), Ok(()));
+		assert_eq!(runtime.collective.vote(bob, hash, 0, true), Ok(()));
+		for _ in 0..4 {
+			runtime.system.inc_block_number().unwrap();
+		}
+		assert_eq!(Collective::close(&mut runtime, alice, hash, 0), Ok(()));
+		assert_eq!(runtime.treasury.approvals(), &[0]);
+	}
+}
@@ -0,0 +1,759 @@
+use crate::{
+	storage::{Hash64Concat, StorageMap, StorageValue, ValueQuery},
+	support::{self, DispatchError, DispatchResult, GetPallet, InitializeReport, RawOrigin},
+};
+use num::{traits::NumOps, CheckedAdd, CheckedMul, CheckedSub, One, Zero};
+use std::collections::BTreeMap;
+
+/// The lock which holds the balance of voters.
+const DEMOCRACY_ID: crate::balances::LockIdentifier = *b"democrac";
+
+/// The priority of the task which enacts a passing referendum.
+const ENACTMENT_PRIORITY: crate::scheduler::Priority = 63;
+
+#[macros::config]
+pub trait Config:
+	crate::system::Config<BlockNumber: NumOps + CheckedMul + From<u32>>
+	+ crate::balances::Config<Balance: NumOps + CheckedMul + From<u32>>
+	+ crate::scheduler::Config
+	+ GetPallet<crate::balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The minimum amount which must be deposited to propose a call.
+	const MINIMUM_DEPOSIT: Self::Balance;
+	/// How often the top proposal becomes a referendum, in blocks.
+	const LAUNCH_PERIOD: Self::BlockNumber;
+	/// How long a referendum is open for votes, in blocks.
+	const VOTING_PERIOD: Self::BlockNumber;
+	/// How long after a referendum passes its call is dispatched, in blocks. A period of zero
+	/// dispatches it in the next block.
+	const ENACTMENT_PERIOD: Self::BlockNumber;
+	/// How long the balance of a voter stays locked after a referendum, for each period of
+	/// conviction, in blocks.
+	const VOTE_LOCKING_PERIOD: Self::BlockNumber;
+}
+
+/// The index of a public proposal.
+pub type PropIndex = u32;
+
+/// The index of a referendum.
+pub type ReferendumIndex = u32;
+
+/// How much a voter commits to their vote. Voters with more conviction get more votes, but their
+/// balance stays locked for longer after the referendum ends.
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, Hash, macros::Encode, macros::Decode, macros::TypeInfo,
+)]
+pub enum Conviction {
+	/// A tenth of a vote per unit of balance, which is not locked after the referendum.
+	None,
+	/// One vote per unit of balance, locked for one period.
+	Locked1x,
+	/// Two votes per unit of balance, locked for two periods.
+	Locked2x,
+	/// Three votes per unit of balance, locked for four periods.
+	Locked3x,
+	/// Four votes per unit of balance, locked for eight periods.
+	Locked4x,
+	/// Five votes per unit of balance, locked for sixteen periods.
+	Locked5x,
+	/// Six votes per unit of balance, locked for thirty-two periods.
+	Locked6x,
+}
+
+impl Conviction {
+	/// The number of votes `balance` is worth with this conviction, or `None` if they overflow.
+	pub fn votes<B: Copy + From<u32> + NumOps + CheckedMul>(self, balance: B) -> Option<B> {
+		let multiplier = match self {
+			Conviction::None => return Some(balance / B::from(10)),
+			Conviction::Locked1x => 1,
+			Conviction::Locked2x => 2,
+			Conviction::Locked3x => 3,
+			Conviction::Locked4x => 4,
+			Conviction::Locked5x => 5,
+			Conviction::Locked6x => 6,
+		};
+		balance.checked_mul(&B::from(multiplier))
+	}
+
+	/// The number of `VOTE_LOCKING_PERIOD`s the balance stays locked after the referendum.
+	pub fn lock_periods(self) -> u32 {
+		match self {
+			Conviction::None => 0,
+			Conviction::Locked1x => 1,
+			Conviction::Locked2x => 2,
+			Conviction::Locked3x => 4,
+			Conviction::Locked4x => 8,
+			Conviction::Locked5x => 16,
+			Conviction::Locked6x => 32,
+		}
+	}
+}
+
+/// A vote for or against a referendum, with a conviction.
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, Hash, macros::Encode, macros::Decode, macros::TypeInfo,
+)]
+pub struct Vote {
+	pub aye: bool,
+	pub conviction: Conviction,
+}
+
+/// A vote of an account, and the balance it is backed by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, macros::TypeInfo)]
+pub struct AccountVote<Balance> {
+	pub vote: Vote,
+	pub balance: Balance,
+}
+
+/// The votes counted for a referendum so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, macros::TypeInfo)]
+pub struct Tally<Balance> {
+	/// The votes in favor, with conviction.
+	pub ayes: Balance,
+	/// The votes against, with conviction.
+	pub nays: Balance,
+	/// The balance which voted, without conviction.
+	pub turnout: Balance,
+}
+
+impl<Balance: Copy + Zero + CheckedAdd + CheckedSub + CheckedMul + From<u32> + NumOps>
+	Tally<Balance>
+{
+	fn new() -> Self {
+		Self { ayes: Zero::zero(), nays: Zero::zero(), turnout: Zero::zero() }
+	}
+
+	/// Count a new vote. The tally is unchanged if this fails.
+	fn add(&mut self, vote: AccountVote<Balance>) -> DispatchResult {
+		let votes = vote.vote.conviction.votes(vote.balance).ok_or("votes overflow")?;
+		let turnout = self.turnout.checked_add(&vote.balance).ok_or("tally overflow")?;
+		let side = if vote.vote.aye { &mut self.ayes } else { &mut self.nays };
+		*side = side.checked_add(&votes).ok_or("tally overflow")?;
+		self.turnout = turnout;
+		Ok(())
+	}
+
+	/// Remove a vote which was counted before. The tally is unchanged if this fails.
+	fn remove(&mut self, vote: AccountVote<Balance>) -> DispatchResult {
+		let votes = vote.vote.conviction.votes(vote.balance).ok_or("votes overflow")?;
+		let turnout = self.turnout.checked_sub(&vote.balance).ok_or("tally underflow")?;
+		let side = if vote.vote.aye { &mut self.ayes } else { &mut self.nays };
+		*side = side.checked_sub(&votes).ok_or("tally underflow")?;
+		self.turnout = turnout;
+		Ok(())
+	}
+}
+
+/// A call proposed to become a referendum.
+#[derive(Debug, macros::TypeInfo)]
+pub struct Proposal<T: Config> {
+	pub proposer: T::AccountId,
+	pub call: <T as crate::scheduler::Config>::RuntimeCall,
+	/// The amount reserved from the proposer, and from each seconder.
+	pub deposit: T::Balance,
+	/// The accounts which seconded the proposal. An account may second a proposal more than once.
+	pub seconds: Vec<T::AccountId>,
+}
+
+/// A referendum which is open for votes.
+#[derive(Debug, macros::TypeInfo)]
+pub struct ReferendumStatus<T: Config> {
+	/// The block at which voting ends, and the referendum is decided.
+	pub end: T::BlockNumber,
+	/// The call dispatched as root if the referendum passes.
+	pub call: <T as crate::scheduler::Config>::RuntimeCall,
+	pub tally: Tally<T::Balance>,
+}
+
+#[derive(Debug, macros::TypeInfo)]
+pub enum ReferendumInfo<T: Config> {
+	Ongoing(ReferendumStatus<T>),
+	Finished { approved: bool, end: T::BlockNumber },
+}
+
+/// The votes of an account.
+#[derive(Debug, macros::TypeInfo)]
+pub struct Voting<T: Config> {
+	pub votes: BTreeMap<ReferendumIndex, AccountVote<T::Balance>>,
+	/// A lock kept after votes on finished referenda were removed: the block until which it
+	/// holds, and the locked balance.
+	pub prior: (T::BlockNumber, T::Balance),
+}
+
+impl<T: Config> Voting<T> {
+	fn new() -> Self {
+		Self { votes: BTreeMap::new(), prior: (Zero::zero(), Zero::zero()) }
+	}
+
+	/// The balance which must stay locked for these votes.
+	fn locked_balance(&self) -> T::Balance {
+		let votes = self.votes.values().map(|vote| vote.balance);
+		votes.fold(self.prior.1, |locked, balance| locked.max(balance))
+	}
+}
+
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	/// The number of public proposals made so far, used as the index of the next one.
+	public_prop_count: StorageValue<PropIndex, ValueQuery>,
+	/// Proposals which did not become a referendum yet.
+	public_props: StorageMap<Hash64Concat, PropIndex, Proposal<T>>,
+	/// The number of referenda started so far, used as the index of the next one.
+	referendum_count: StorageValue<ReferendumIndex, ValueQuery>,
+	referendums: StorageMap<Hash64Concat, ReferendumIndex, ReferendumInfo<T>>,
+	voting: StorageMap<Hash64Concat, T::AccountId, Voting<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn public_prop(&self, proposal: PropIndex) -> Option<&Proposal<T>> {
+		self.public_props.get_ref(&proposal)
+	}
+
+	pub fn referendum(&self, ref_index: ReferendumIndex) -> Option<&ReferendumInfo<T>> {
+		self.referendums.get_ref(&ref_index)
+	}
+
+	pub fn voting(&self, who: &T::AccountId) -> Option<&Voting<T>> {
+		self.voting.get_ref(who)
+	}
+
+	/// The total deposit backing a proposal with `deposit` and `seconds` seconds, or `None` if it
+	/// overflows. The proposal with the most backing becomes the next referendum.
+	fn backing(deposit: T::Balance, seconds: usize) -> Option<T::Balance> {
+		let depositors = u32::try_from(seconds).ok()?.checked_add(1)?;
+		deposit.checked_mul(&T::Balance::from(depositors))
+	}
+
+	/// Lock the balance needed by the votes of `who`, and forget their voting record once nothing
+	/// is locked anymore.
+	fn update_lock(runtime: &mut T, who: &T::AccountId) {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let locked = pallet
+			.voting
+			.get_ref(who)
+			.map_or(Zero::zero(), |voting| voting.locked_balance());
+		if locked.is_zero() {
+			pallet.voting.remove(who);
+		}
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.set_lock(DEMOCRACY_ID, who, locked);
+	}
+
+	/// Turn the proposal with the most backing into a referendum, and return its deposits.
+	fn launch_public(runtime: &mut T, now: T::BlockNumber) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		// On ties, the oldest proposal wins. Seconds which would overflow the backing are refused,
+		// so it is never `None`.
+		let top = pallet
+			.public_props
+			.iter()
+			.rev()
+			.max_by_key(|(_, proposal)| Self::backing(proposal.deposit, proposal.seconds.len()))
+			.map(|(index, _)| *index);
+		let Some(top) = top else { return Ok(()) };
+
+		// The proposal is only taken once the referendum can be started, so that it is not lost
+		// with its deposits.
+		let end = now.checked_add(&T::VOTING_PERIOD).ok_or("block number overflow")?;
+		let ref_index = pallet.referendum_count.get();
+		let next_index = ref_index.checked_add(1).ok_or("too many referenda")?;
+
+		let proposal = pallet.public_props.take(&top).expect("found above; qed");
+		pallet.referendum_count.put(next_index);
+		let status = ReferendumStatus { end, call: proposal.call, tally: Tally::new() };
+		pallet.referendums.insert(ref_index, ReferendumInfo::Ongoing(status));
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		for depositor in std::iter::once(&proposal.proposer).chain(&proposal.seconds) {
+			balances.unreserve(depositor, proposal.deposit)?;
+		}
+		Ok(())
+	}
+
+	/// Decide the referenda which ended by `now`, and schedule the calls of those which passed. A
+	/// referendum which passed stays ongoing until its call is scheduled, so it is decided again in
+	/// the next block if scheduling fails. Returns the errors of those which failed.
+	fn bake_referenda(runtime: &mut T, now: T::BlockNumber) -> Vec<DispatchError> {
+		let pallet: &Self = runtime.pallet();
+		let ending = pallet
+			.referendums
+			.iter()
+			.filter(
+				|(_, info)| matches!(info, ReferendumInfo::Ongoing(status) if status.end <= now),
+			)
+			.map(|(index, _)| *index)
+			.collect::<Vec<_>>();
+
+		let mut failed = Vec::new();
+		for ref_index in ending {
+			let pallet: &Self = runtime.pallet();
+			let Some(ReferendumInfo::Ongoing(status)) = pallet.referendums.get_ref(&ref_index)
+			else {
+				unreachable!("found above; qed")
+			};
+			let approved = status.tally.ayes > status.tally.nays;
+			if approved {
+				let call = status.call.clone();
+				// A period of zero enacts the call in the next block, since tasks cannot be
+				// scheduled for the current one.
+				let scheduled = now
+					.checked_add(&T::ENACTMENT_PERIOD.max(One::one()))
+					.ok_or_else(|| "block number overflow".into())
+					.and_then(|when| {
+						crate::scheduler::Pallet::<T>::do_schedule(
+							runtime,
+							None,
+							when,
+							None,
+							ENACTMENT_PRIORITY,
+							RawOrigin::Root,
+							call,
+						)
+						.map_err(DispatchError::from)
+					});
+				if let Err(e) = scheduled {
+					failed.push(e);
+					continue;
+				}
+			}
+
+			let pallet: &mut Self = runtime.pallet_mut();
+			pallet
+				.referendums
+				.insert(ref_index, ReferendumInfo::Finished { approved, end: now });
+		}
+		failed
+	}
+}
+
+// The calls are boxed, since `Call` would otherwise contain itself through `RuntimeCall`.
+#[allow(clippy::boxed_local)]
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Propose `proposal` to become a referendum, and reserve `value` as a deposit. The deposit
+	/// is returned when the proposal becomes a referendum.
+	#[call_index(0)]
+	pub fn propose(
+		runtime: &mut T,
+		caller: T::AccountId,
+		proposal: Box<<T as crate::scheduler::Config>::RuntimeCall>,
+		value: T::Balance,
+	) -> DispatchResult {
+		if value < T::MINIMUM_DEPOSIT {
+			return Err("deposit is below the minimum".into());
+		}
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.reserve(&caller, value)?;
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let index = pallet.public_prop_count.get();
+		pallet.public_prop_count.put(index.checked_add(1).ok_or("too many proposals")?);
+		let proposal =
+			Proposal { proposer: caller, call: *proposal, deposit: value, seconds: Vec::new() };
+		pallet.public_props.insert(index, proposal);
+
+		Ok(())
+	}
+
+	/// Back a proposal with the same deposit as the proposer, to make it the next referendum.
+	#[call_index(1)]
+	pub fn second(runtime: &mut T, caller: T::AccountId, proposal: PropIndex) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let prop = pallet.public_props.get_ref(&proposal).ok_or("proposal does not exist")?;
+		let deposit = prop.deposit;
+		Self::backing(deposit, prop.seconds.len() + 1).ok_or("proposal backing overflow")?;
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.reserve(&caller, deposit)?;
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet
+			.public_props
+			.get_mut(&proposal)
+			.expect("checked above; qed")
+			.seconds
+			.push(caller);
+		Ok(())
+	}
+
+	/// Vote on an ongoing referendum with `balance`, replacing any previous vote on it.
+	///
+	/// The balance is locked until the vote is removed, and for longer with conviction.
+	#[call_index(2)]
+	pub fn vote(
+		runtime: &mut T,
+		caller: T::AccountId,
+		ref_index: ReferendumIndex,
+		vote: Vote,
+		balance: T::Balance,
+	) -> DispatchResult {
+		let balances: &crate::balances::Pallet<T> = runtime.pallet();
+		if balance > balances.balance(&caller) {
+			return Err("not enough funds to vote".into());
+		}
+
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		let now = system.block_number();
+		let pallet: &mut Self = runtime.pallet_mut();
+		let Some(ReferendumInfo::Ongoing(status)) = pallet.referendums.get_mut(&ref_index) else {
+			return Err("referendum is not ongoing".into());
+		};
+		// A referendum which passed but could not be enacted yet is already decided.
+		if status.end <= now {
+			return Err("voting period is over".into());
+		}
+
+		// The tally is updated with the difference, so it never needs to count all the votes. It
+		// is updated on a copy, so that it is unchanged if this fails.
+		let new_vote = AccountVote { vote, balance };
+		let old_vote =
+			pallet.voting.get_ref(&caller).and_then(|voting| voting.votes.get(&ref_index));
+		let mut tally = status.tally;
+		if let Some(old_vote) = old_vote {
+			tally.remove(*old_vote)?;
+		}
+		tally.add(new_vote)?;
+		status.tally = tally;
+
+		if !pallet.voting.contains_key(&caller) {
+			pallet.voting.insert(caller.clone(), Voting::new());
+		}
+		let voting = pallet.voting.get_mut(&caller).expect("inserted above; qed");
+		voting.votes.insert(ref_index, new_vote);
+
+		Self::update_lock(runtime, &caller);
+		Ok(())
+	}
+
+	/// Remove a vote. Votes on ongoing referenda are removed from the tally.
+	///
+	/// The balance of votes on the winning side of a finished referendum stays locked until its
+	/// conviction expires. See `unlock`.
+	#[call_index(3)]
+	pub fn remove_vote(
+		runtime: &mut T,
+		caller: T::AccountId,
+		ref_index: ReferendumIndex,
+	) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let voting = pallet.voting.get_mut(&caller).ok_or("not voted")?;
+		let vote = *voting.votes.get(&ref_index).ok_or("not voted")?;
+
+		match pallet.referendums.get_mut(&ref_index) {
+			Some(ReferendumInfo::Ongoing(status)) => status.tally.remove(vote)?,
+			Some(ReferendumInfo::Finished { approved, end }) if *approved == vote.vote.aye => {
+				let periods = T::BlockNumber::from(vote.vote.conviction.lock_periods());
+				let unlock_at = T::VOTE_LOCKING_PERIOD
+					.checked_mul(&periods)
+					.and_then(|lock| end.checked_add(&lock))
+					.ok_or("block number overflow")?;
+				let (prior_until, prior_balance) = voting.prior;
+				voting.prior = (prior_until.max(unlock_at), prior_balance.max(vote.balance));
+			},
+			_ => {},
+		}
+		voting.votes.remove(&ref_index);
+
+		Self::update_lock(runtime, &caller);
+		Ok(())
+	}
+
+	/// Remove the lock kept on the balance of `target` after their conviction expired.
+	#[call_index(4)]
+	pub fn unlock(runtime: &mut T, _caller: T::AccountId, target: T::AccountId) -> DispatchResult {
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		let now = system.block_number();
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		if let Some(voting) = pallet.voting.get_mut(&target) {
+			if voting.prior.0 <= now {
+				voting.prior = (Zero::zero(), Zero::zero());
+			}
+		}
+
+		Self::update_lock(runtime, &target);
+		Ok(())
+	}
+}
+
+impl<T: Config> support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> support::Hooks<T> for Pallet<T> {
+	/// Decide the referenda which end in this block, and launch a new referendum at the start of
+	/// every launch period.
+	fn on_initialize(runtime: &mut T, block_number: T::BlockNumber) -> InitializeReport {
+		let mut report =
+			InitializeReport { weight: 0, failed: Self::bake_referenda(runtime, block_number) };
+		if (block_number % T::LAUNCH_PERIOD).is_zero() {
+			if let Err(e) = Self::launch_public(runtime, block_number) {
+				report.failed.push(e);
+			}
+		}
+		report
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Conviction, ReferendumInfo, Tally, Vote};
+	use crate::{
+		support::{self, Hooks},
+		types, Runtime, RuntimeCall,
+	};
+
+	type Democracy = super::Pallet<Runtime>;
+
+	fn approve_spend(proposal_id: u32) -> Box<RuntimeCall> {
+		Box::new(RuntimeCall::treasury(crate::treasury::Call::approve_proposal { proposal_id }))
+	}
+
+	fn aye(conviction: Conviction) -> Vote {
+		Vote { aye: true, conviction }
+	}
+
+	fn nay(conviction: Conviction) -> Vote {
+		Vote { aye: false, conviction }
+	}
+
+	/// Execute empty blocks, with only the timestamp inherent, until block `n`.
+	fn run_to_block(runtime: &mut Runtime, n: u32) {
+		while runtime.system.block_number() < n {
+			let block_number = runtime.system.block_number() + 1;
+			let set_timestamp = support::Extrinsic {
+				caller: None,
+				call: RuntimeCall::timestamp(crate::timestamp::Call::set {
+					now: block_number as u64 * 6_000,
+				}),
+			};
+			let block = types::Block {
+				header: support::Header { block_number },
+				extrinsics: vec![set_timestamp],
+			};
+			runtime.execute_block(block).expect("valid block");
+		}
+	}
+
+	#[test]
+	fn launch_top_proposal() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		runtime.balances.set_balance(bob.clone(), 100);
+		runtime.balances.set_balance(charlie.clone(), 100);
+
+		assert_eq!(
+			Democracy::propose(&mut runtime, alice.clone(), approve_spend(0), 9),
+			Err("deposit is below the minimum".into())
+		);
+		assert_eq!(Democracy::propose(&mut runtime, alice.clone(), approve_spend(0), 30), Ok(()));
+		assert_eq!(Democracy::propose(&mut runtime, bob.clone(), approve_spend(1), 20), Ok(()));
+		assert_eq!(
+			Democracy::second(&mut runtime, charlie.clone(), 2),
+			Err("proposal does not exist".into())
+		);
+		assert_eq!(Democracy::second(&mut runtime, charlie.clone(), 1), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&charlie), 20);
+
+		// Bob's proposal has more backing, so it becomes the first referendum.
+		run_to_block(&mut runtime, 5);
+		assert!(matches!(runtime.democracy.referendum(0), Some(ReferendumInfo::Ongoing(_))));
+		assert!(runtime.democracy.public_prop(1).is_none());
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+		assert_eq!(runtime.balances.reserved_balance(&charlie), 0);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 30);
+
+		run_to_block(&mut runtime, 10);
+		assert!(matches!(runtime.democracy.referendum(1), Some(ReferendumInfo::Ongoing(_))));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+	}
+
+	#[test]
+	fn passing_referendum_is_enacted() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		runtime.balances.set_balance(bob.clone(), 100);
+
+		assert_eq!(
+			crate::treasury::Pallet::<Runtime>::propose_spend(
+				&mut runtime,
+				alice.clone(),
+				10,
+				bob.clone()
+			),
+			Ok(())
+		);
+		assert_eq!(Democracy::propose(&mut runtime, alice.clone(), approve_spend(0), 10), Ok(()));
+		run_to_block(&mut runtime, 5);
+
+		assert_eq!(
+			Democracy::vote(&mut runtime, alice.clone(), 1, aye(Conviction::Locked1x), 10),
+			Err("referendum is not ongoing".into())
+		);
+		assert_eq!(
+			Democracy::vote(&mut runtime, alice.clone(), 0, aye(Conviction::Locked1x), 101),
+			Err("not enough funds to vote".into())
+		);
+		assert_eq!(
+			Democracy::vote(&mut runtime, alice.clone(), 0, aye(Conviction::Locked1x), 40),
+			Ok(())
+		);
+		assert_eq!(
+			Democracy::vote(&mut runtime, bob.clone(), 0, nay(Conviction::Locked1x), 100),
+			Ok(())
+		);
+		// Voting again replaces the previous vote in the tally.
+		assert_eq!(
+			Democracy::vote(&mut runtime, alice.clone(), 0, aye(Conviction::Locked3x), 40),
+			Ok(())
+		);
+		let Some(ReferendumInfo::Ongoing(status)) = runtime.democracy.referendum(0) else {
+			panic!("referendum is ongoing");
+		};
+		assert_eq!(status.tally, Tally { ayes: 120, nays: 100, turnout: 140 });
+
+		// The referendum passes at the end of the voting period, and is enacted after the
+		// enactment period.
+		run_to_block(&mut runtime, 9);
+		assert!(matches!(
+			runtime.democracy.referendum(0),
+			Some(ReferendumInfo::Finished { approved: true, end: 9 })
+		));
+		assert!(runtime.treasury.approvals().is_empty());
+		run_to_block(&mut runtime, 11);
+		assert_eq!(runtime.treasury.approvals(), &[0]);
+	}
+
+	#[test]
+	fn failed_enactment_is_retried() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		assert_eq!(Democracy::propose(&mut runtime, alice.clone(), approve_spend(0), 10), Ok(()));
+		run_to_block(&mut runtime, 5);
+		assert_eq!(
+			Democracy::vote(&mut runtime, alice.clone(), 0, aye(Conviction::Locked1x), 40),
+			Ok(())
+		);
+
+		// The hooks of block 9 run late, so the call cannot be scheduled for block 11. The
+		// referendum stays ongoing, but voting is over.
+		for _ in 5..12 {
+			runtime.system.inc_block_number().unwrap();
+		}
+		let report = Democracy::on_initialize(&mut runtime, 9);
+		assert_eq!(report.failed, ["scheduled block is in the past".into()]);
+		assert!(matches!(runtime.democracy.referendum(0), Some(ReferendumInfo::Ongoing(_))));
+		assert_eq!(
+			Democracy::vote(&mut runtime, alice.clone(), 0, aye(Conviction::Locked2x), 40),
+			Err("voting period is over".into())
+		);
+
+		// It is decided again in a later block, and its call is scheduled then.
+		assert_eq!(Democracy::on_initialize(&mut runtime, 12).failed, []);
+		assert!(matches!(
+			runtime.democracy.referendum(0),
+			Some(ReferendumInfo::Finished { approved: true, end: 12 })
+		));
+		assert!(runtime.scheduler.task((14, 0)).is_some());
+	}
+
+	#[test]
+	fn votes_overflow() {
+		assert_eq!(Conviction::None.votes(u64::MAX), Some(u64::MAX / 10));
+		assert_eq!(Conviction::Locked1x.votes(u64::MAX), Some(u64::MAX));
+		assert_eq!(Conviction::Locked2x.votes(u64::MAX / 2), Some(u64::MAX - 1));
+		assert_eq!(Conviction::Locked2x.votes(u64::MAX / 2 + 1), None);
+		assert_eq!(Conviction::Locked6x.votes(u64::MAX / 5), None);
+
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(alice.clone(), u128::MAX);
+		assert_eq!(Democracy::propose(&mut runtime, alice.clone(), approve_spend(0), 10), Ok(()));
+		run_to_block(&mut runtime, 5);
+
+		// Votes which overflow are refused, and the previous vote is kept.
+		assert_eq!(
+			Democracy::vote(&mut runtime, alice.clone(), 0, aye(Conviction::Locked1x), 10),
+			Ok(())
+		);
+		assert_eq!(
+			Democracy::vote(&mut runtime, alice.clone(), 0, aye(Conviction::Locked2x), u128::MAX),
+			Err("votes overflow".into())
+		);
+		let Some(ReferendumInfo::Ongoing(status)) = runtime.democracy.referendum(0) else {
+			panic!("referendum is ongoing");
+		};
+		assert_eq!(status.tally, Tally { ayes: 10, nays: 0, turnout: 10 });
+		assert_eq!(runtime.democracy.voting(&alice).unwrap().votes[&0].balance, 10);
+	}
+
+	#[test]
+	fn backing_overflow() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), u128::MAX);
+		runtime.balances.set_balance(bob.clone(), u128::MAX);
+
+		assert_eq!(
+			Democracy::propose(&mut runtime, alice.clone(), approve_spend(0), u128::MAX / 2 + 1),
+			Ok(())
+		);
+		assert_eq!(
+			Democracy::second(&mut runtime, bob.clone(), 0),
+			Err("proposal backing overflow".into())
+		);
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+		assert!(runtime.democracy.public_prop(0).unwrap().seconds.is_empty());
+	}
+
+	#[test]
+	fn conviction_locks_balance() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		runtime.balances.set_balance(bob.clone(), 100);
+
+		assert_eq!(Democracy::propose(&mut runtime, alice.clone(), approve_spend(0), 10), Ok(()));
+		run_to_block(&mut runtime, 5);
+		assert_eq!(
+			Democracy::vote(&mut runtime, alice.clone(), 0, aye(Conviction::Locked2x), 60),
+			Ok(())
+		);
+		assert_eq!(
+			Democracy::vote(&mut runtime, bob.clone(), 0, nay(Conviction::None), 100),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.locked_balance(&alice), 60);
+		assert_eq!(
+			runtime.balances.transfer(alice.clone(), bob.clone(), 41),
+			Err("Liquidity restrictions prevent withdrawal.".into())
+		);
+
+		// The referendum passes at block 9. Alice voted with conviction on the winning side, so
+		// her balance stays locked for two locking periods after that.
+		run_to_block(&mut runtime, 9);
+		assert_eq!(Democracy::remove_vote(&mut runtime, alice.clone(), 0), Ok(()));
+		assert_eq!(Democracy::remove_vote(&mut runtime, alice.clone(), 0), Err("not voted".into()));
+		assert_eq!(runtime.democracy.voting(&alice).map(|voting| voting.prior), Some((15, 60)));
+		run_to_block(&mut runtime, 14);
+		assert_eq!(Democracy::unlock(&mut runtime, bob.clone(), alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.locked_balance(&alice), 60);
+		run_to_block(&mut runtime, 15);
+		assert_eq!(Democracy::unlock(&mut runtime, bob.clone(), alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.locked_balance(&alice), 0);
+		assert!(runtime.democracy.voting(&alice).is_none());
+
+		// Bob voted without conviction, so his balance is unlocked as soon as the vote is removed.
+		assert_eq!(runtime.balances.locked_balance(&bob), 100);
+		assert_eq!(Democracy::remove_vote(&mut runtime, bob.clone(), 0), Ok(()));
+		assert_eq!(runtime.balances.locked_balance(&bob), 0);
+	}
+}
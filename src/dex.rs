@@ -0,0 +1,755 @@
+use crate::{
+	storage::{Hash64Concat, StorageDoubleMap, StorageMap, ValueQuery},
+	support::{self, DispatchResult, FromHash, GetPallet, Percent},
+};
+use num::{traits::NumOps, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Zero};
+use std::collections::BTreeSet;
+
+#[macros::config]
+pub trait Config:
+	Sized
+	+ crate::system::Config<AccountId: FromHash>
+	+ crate::balances::Config<Balance: From<u32> + NumOps + CheckedMul + CheckedDiv>
+	+ crate::assets::Config
+	+ GetPallet<crate::balances::Pallet<Self>>
+	+ GetPallet<crate::assets::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The fee taken from the input of every swap, which stays in the pool for its liquidity
+	/// providers.
+	const LP_FEE: Percent;
+	/// The liquidity tokens locked in a pool by its first deposit, so that its supply can never
+	/// be withdrawn back to zero and the price of a token cannot be inflated by a tiny deposit.
+	const MIN_LIQUIDITY: Self::Balance;
+}
+
+/// A tradable asset: either the native token of `balances::Pallet`, or an asset of
+/// `assets::Pallet`.
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	macros::Encode,
+	macros::Decode,
+	macros::TypeInfo,
+)]
+pub enum NativeOrAsset<AssetId> {
+	Native,
+	Asset(AssetId),
+}
+
+pub type AssetKind<T> = NativeOrAsset<<T as crate::assets::Config>::AssetId>;
+
+/// The pair of assets of a pool, in ascending order.
+pub type PoolId<T> = (AssetKind<T>, AssetKind<T>);
+
+/// A constant product pool. Its reserves are held by its own keyless account.
+#[derive(Debug, Clone, PartialEq, Eq, macros::TypeInfo)]
+pub struct Pool<Balance> {
+	/// The number of liquidity tokens issued for this pool.
+	pub lp_supply: Balance,
+}
+
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	pools: StorageMap<Hash64Concat, PoolId<T>, Pool<T::Balance>>,
+	lp_balances: StorageDoubleMap<
+		Hash64Concat,
+		PoolId<T>,
+		Hash64Concat,
+		T::AccountId,
+		T::Balance,
+		ValueQuery,
+	>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn pool_id(asset_a: AssetKind<T>, asset_b: AssetKind<T>) -> PoolId<T> {
+		if asset_a <= asset_b {
+			(asset_a, asset_b)
+		} else {
+			(asset_b, asset_a)
+		}
+	}
+
+	pub fn pool(&self, asset_a: AssetKind<T>, asset_b: AssetKind<T>) -> Option<&Pool<T::Balance>> {
+		self.pools.get_ref(&Self::pool_id(asset_a, asset_b))
+	}
+
+	/// The liquidity tokens of `who` for the pool of `asset_a` and `asset_b`.
+	pub fn lp_balance(
+		&self,
+		asset_a: AssetKind<T>,
+		asset_b: AssetKind<T>,
+		who: &T::AccountId,
+	) -> T::Balance {
+		self.lp_balances.get(&Self::pool_id(asset_a, asset_b), who)
+	}
+
+	/// The account holding the reserves of a pool.
+	pub fn pool_account(pool_id: &PoolId<T>) -> T::AccountId {
+		T::AccountId::from_hash(support::hash_of(&("dex", pool_id)))
+	}
+
+	fn balance_of(runtime: &T, asset: AssetKind<T>, who: &T::AccountId) -> T::Balance {
+		match asset {
+			NativeOrAsset::Native => {
+				let balances: &crate::balances::Pallet<T> = runtime.pallet();
+				balances.balance(who)
+			},
+			NativeOrAsset::Asset(id) => {
+				let assets: &crate::assets::Pallet<T> = runtime.pallet();
+				assets.balance(id, who)
+			},
+		}
+	}
+
+	fn ensure_can_transfer(
+		runtime: &T,
+		asset: AssetKind<T>,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		match asset {
+			NativeOrAsset::Native => {
+				let balances: &crate::balances::Pallet<T> = runtime.pallet();
+				balances.ensure_can_transfer(from, to, amount)
+			},
+			NativeOrAsset::Asset(id) => {
+				let assets: &crate::assets::Pallet<T> = runtime.pallet();
+				assets.ensure_can_transfer(id, from, to, amount)
+			},
+		}
+	}
+
+	fn transfer(
+		runtime: &mut T,
+		asset: AssetKind<T>,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		match asset {
+			NativeOrAsset::Native => {
+				let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+				balances.transfer(from.clone(), to.clone(), amount)
+			},
+			NativeOrAsset::Asset(id) => {
+				let assets: &mut crate::assets::Pallet<T> = runtime.pallet_mut();
+				assets.do_transfer(id, from, to, amount)
+			},
+		}
+	}
+
+	/// The reserves of the pool of `asset_a` and `asset_b`, in that order.
+	pub fn reserves(
+		runtime: &T,
+		asset_a: AssetKind<T>,
+		asset_b: AssetKind<T>,
+	) -> Result<(T::Balance, T::Balance), String> {
+		let pallet: &Self = runtime.pallet();
+		let pool_id = Self::pool_id(asset_a, asset_b);
+		if !pallet.pools.contains_key(&pool_id) {
+			return Err("pool does not exist".to_string());
+		}
+		let pool_account = Self::pool_account(&pool_id);
+		Ok((
+			Self::balance_of(runtime, asset_a, &pool_account),
+			Self::balance_of(runtime, asset_b, &pool_account),
+		))
+	}
+
+	/// The amount received for `amount_in`, after the fee. Rounds down, in favor of the pool.
+	pub fn get_amount_out(
+		amount_in: T::Balance,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+	) -> Result<T::Balance, String> {
+		if reserve_in.is_zero() || reserve_out.is_zero() {
+			return Err("pool is empty".to_string());
+		}
+		let fee = Self::mul_div_ceil(amount_in, Self::fee_percent(), 100u32.into())?;
+		let amount_in_less_fee = amount_in.checked_sub(&fee).ok_or("Overflow")?;
+		let new_reserve_in = reserve_in.checked_add(&amount_in_less_fee).ok_or("Overflow")?;
+		Self::mul_div(amount_in_less_fee, reserve_out, new_reserve_in)
+	}
+
+	/// The amount to pay, fee included, to receive `amount_out`. Rounds up, in favor of the pool.
+	pub fn get_amount_in(
+		amount_out: T::Balance,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+	) -> Result<T::Balance, String> {
+		if reserve_in.is_zero() || reserve_out.is_zero() {
+			return Err("pool is empty".to_string());
+		}
+		if amount_out >= reserve_out {
+			return Err("not enough liquidity".to_string());
+		}
+		let amount_in_less_fee =
+			Self::mul_div_ceil(reserve_in, amount_out, reserve_out - amount_out)?;
+		let hundred: T::Balance = 100u32.into();
+		Self::mul_div_ceil(amount_in_less_fee, hundred, hundred - Self::fee_percent())
+	}
+
+	fn fee_percent() -> T::Balance {
+		u32::from(T::LP_FEE.deconstruct()).into()
+	}
+
+	/// `a * b / c`, rounded down.
+	fn mul_div(a: T::Balance, b: T::Balance, c: T::Balance) -> Result<T::Balance, String> {
+		Ok(a.checked_mul(&b).and_then(|x| x.checked_div(&c)).ok_or("Overflow")?)
+	}
+
+	/// `a * b / c`, rounded up.
+	fn mul_div_ceil(a: T::Balance, b: T::Balance, c: T::Balance) -> Result<T::Balance, String> {
+		let product = a.checked_mul(&b).ok_or("Overflow")?;
+		let quotient = product.checked_div(&c).ok_or("Overflow")?;
+		if quotient * c < product {
+			Ok(quotient.checked_add(&1u32.into()).ok_or("Overflow")?)
+		} else {
+			Ok(quotient)
+		}
+	}
+
+	/// The integer square root of `n`, rounded down.
+	fn sqrt(n: T::Balance) -> T::Balance {
+		let two: T::Balance = 2u32.into();
+		let mut x = n;
+		let mut y = n / two + n % two;
+		while y < x {
+			x = y;
+			// The average of `x` and `n / x`, without overflowing.
+			let (a, b) = (x, n / x);
+			y = a / two + b / two + (a % two + b % two) / two;
+		}
+		x
+	}
+
+	/// Check that a swap path has at least two assets, and visits every asset at most once.
+	fn ensure_valid_path(path: &[AssetKind<T>]) -> DispatchResult {
+		let unique: BTreeSet<_> = path.iter().collect();
+		if path.len() < 2 || unique.len() != path.len() {
+			return Err("invalid path".into());
+		}
+		Ok(())
+	}
+
+	/// The amounts along `path` when paying `amount_in` for its first asset.
+	pub fn amounts_out(
+		runtime: &T,
+		path: &[AssetKind<T>],
+		amount_in: T::Balance,
+	) -> Result<Vec<T::Balance>, String> {
+		let mut amounts = vec![amount_in];
+		for pair in path.windows(2) {
+			let (reserve_in, reserve_out) = Self::reserves(runtime, pair[0], pair[1])?;
+			let amount = *amounts.last().expect("starts with the amount in; qed");
+			amounts.push(Self::get_amount_out(amount, reserve_in, reserve_out)?);
+		}
+		Ok(amounts)
+	}
+
+	/// The amounts along `path` to receive `amount_out` of its last asset.
+	pub fn amounts_in(
+		runtime: &T,
+		path: &[AssetKind<T>],
+		amount_out: T::Balance,
+	) -> Result<Vec<T::Balance>, String> {
+		let mut amounts = vec![amount_out];
+		for pair in path.windows(2).rev() {
+			let (reserve_in, reserve_out) = Self::reserves(runtime, pair[0], pair[1])?;
+			let amount = *amounts.last().expect("starts with the amount out; qed");
+			amounts.push(Self::get_amount_in(amount, reserve_in, reserve_out)?);
+		}
+		amounts.reverse();
+		Ok(amounts)
+	}
+
+	/// Move `amounts` along `path`: from the caller into the first pool, through every pool, and
+	/// out of the last pool to the caller. Either every transfer happens, or none does.
+	fn do_swap(
+		runtime: &mut T,
+		caller: &T::AccountId,
+		path: &[AssetKind<T>],
+		amounts: &[T::Balance],
+	) -> DispatchResult {
+		let pool_accounts: Vec<_> = path
+			.windows(2)
+			.map(|pair| Self::pool_account(&Self::pool_id(pair[0], pair[1])))
+			.collect();
+		let mut transfers = vec![(path[0], caller, &pool_accounts[0], amounts[0])];
+		for (i, pool_account) in pool_accounts.iter().enumerate() {
+			let to = pool_accounts.get(i + 1).unwrap_or(caller);
+			transfers.push((path[i + 1], pool_account, to, amounts[i + 1]));
+		}
+
+		// Every asset of the path is moved exactly once, so no transfer changes the balances
+		// checked for another.
+		for &(asset, from, to, amount) in &transfers {
+			Self::ensure_can_transfer(runtime, asset, from, to, amount)?;
+		}
+		for (asset, from, to, amount) in transfers {
+			Self::transfer(runtime, asset, from, to, amount).expect("checked above; qed");
+		}
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Create an empty pool trading `asset_a` against `asset_b`.
+	#[call_index(0)]
+	pub fn create_pool(
+		runtime: &mut T,
+		_caller: T::AccountId,
+		asset_a: AssetKind<T>,
+		asset_b: AssetKind<T>,
+	) -> DispatchResult {
+		if asset_a == asset_b {
+			return Err("identical assets".into());
+		}
+		let assets: &crate::assets::Pallet<T> = runtime.pallet();
+		for asset in [asset_a, asset_b] {
+			if let NativeOrAsset::Asset(id) = asset {
+				assets.asset(id).ok_or("asset does not exist")?;
+			}
+		}
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let pool_id = Self::pool_id(asset_a, asset_b);
+		if pallet.pools.contains_key(&pool_id) {
+			return Err("pool already exists".into());
+		}
+		pallet.pools.insert(pool_id, Pool { lp_supply: Zero::zero() });
+		Ok(())
+	}
+
+	/// Deposit both assets of a pool, in the ratio of its reserves, and mint liquidity tokens in
+	/// return. The first deposit sets the ratio, and locks `MIN_LIQUIDITY` of its tokens in the
+	/// pool.
+	#[allow(clippy::too_many_arguments)]
+	#[call_index(1)]
+	pub fn add_liquidity(
+		runtime: &mut T,
+		caller: T::AccountId,
+		asset_a: AssetKind<T>,
+		asset_b: AssetKind<T>,
+		amount_a_desired: T::Balance,
+		amount_b_desired: T::Balance,
+		amount_a_min: T::Balance,
+		amount_b_min: T::Balance,
+	) -> DispatchResult {
+		let (reserve_a, reserve_b) = Self::reserves(runtime, asset_a, asset_b)?;
+		let pool_id = Self::pool_id(asset_a, asset_b);
+		let pallet: &Self = runtime.pallet();
+		let lp_supply =
+			pallet.pools.get_ref(&pool_id).expect("checked by `reserves`; qed").lp_supply;
+
+		if amount_a_min > amount_a_desired || amount_b_min > amount_b_desired {
+			return Err("slippage exceeds the bounds".into());
+		}
+		let (amount_a, amount_b) = if lp_supply.is_zero() {
+			(amount_a_desired, amount_b_desired)
+		} else {
+			let amount_b_optimal = Self::mul_div_ceil(amount_a_desired, reserve_b, reserve_a)?;
+			if amount_b_optimal <= amount_b_desired {
+				(amount_a_desired, amount_b_optimal)
+			} else {
+				let amount_a_optimal = Self::mul_div_ceil(amount_b_desired, reserve_a, reserve_b)?;
+				(amount_a_optimal, amount_b_desired)
+			}
+		};
+		if amount_a > amount_a_desired || amount_a < amount_a_min || amount_b < amount_b_min {
+			return Err("slippage exceeds the bounds".into());
+		}
+		if amount_a.is_zero() || amount_b.is_zero() {
+			return Err("amount is zero".into());
+		}
+
+		// The first deposit mints the square root of the product, of which `MIN_LIQUIDITY` stays
+		// locked in the pool account, which nobody controls.
+		let (lp_minted, lp_locked) = if lp_supply.is_zero() {
+			let lp_total = Self::sqrt(amount_a.checked_mul(&amount_b).ok_or("Overflow")?);
+			let lp_minted = lp_total.checked_sub(&T::MIN_LIQUIDITY).unwrap_or_default();
+			(lp_minted, T::MIN_LIQUIDITY)
+		} else {
+			let lp_minted = Self::mul_div(amount_a, lp_supply, reserve_a)?
+				.min(Self::mul_div(amount_b, lp_supply, reserve_b)?);
+			(lp_minted, Zero::zero())
+		};
+		if lp_minted.is_zero() {
+			return Err("not enough liquidity minted".into());
+		}
+		let new_lp_supply = lp_supply
+			.checked_add(&lp_minted)
+			.and_then(|supply| supply.checked_add(&lp_locked))
+			.ok_or("Overflow")?;
+		let pallet: &Self = runtime.pallet();
+		let new_lp_balance = pallet
+			.lp_balance(asset_a, asset_b, &caller)
+			.checked_add(&lp_minted)
+			.ok_or("Overflow")?;
+
+		let pool_account = Self::pool_account(&pool_id);
+		Self::ensure_can_transfer(runtime, asset_a, &caller, &pool_account, amount_a)?;
+		Self::ensure_can_transfer(runtime, asset_b, &caller, &pool_account, amount_b)?;
+
+		Self::transfer(runtime, asset_a, &caller, &pool_account, amount_a)
+			.expect("checked above; qed");
+		Self::transfer(runtime, asset_b, &caller, &pool_account, amount_b)
+			.expect("checked above; qed");
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.pools.insert(pool_id, Pool { lp_supply: new_lp_supply });
+		pallet.lp_balances.insert(pool_id, caller, new_lp_balance);
+		if !lp_locked.is_zero() {
+			pallet.lp_balances.insert(pool_id, pool_account, lp_locked);
+		}
+		Ok(())
+	}
+
+	/// Burn `lp_amount` liquidity tokens, and withdraw the matching share of both reserves.
+	#[call_index(2)]
+	pub fn remove_liquidity(
+		runtime: &mut T,
+		caller: T::AccountId,
+		asset_a: AssetKind<T>,
+		asset_b: AssetKind<T>,
+		lp_amount: T::Balance,
+		amount_a_min: T::Balance,
+		amount_b_min: T::Balance,
+	) -> DispatchResult {
+		let (reserve_a, reserve_b) = Self::reserves(runtime, asset_a, asset_b)?;
+		let pool_id = Self::pool_id(asset_a, asset_b);
+		let pallet: &Self = runtime.pallet();
+		let lp_supply =
+			pallet.pools.get_ref(&pool_id).expect("checked by `reserves`; qed").lp_supply;
+		let new_lp_balance = pallet
+			.lp_balance(asset_a, asset_b, &caller)
+			.checked_sub(&lp_amount)
+			.ok_or("not enough liquidity tokens")?;
+
+		let amount_a = Self::mul_div(lp_amount, reserve_a, lp_supply)?;
+		let amount_b = Self::mul_div(lp_amount, reserve_b, lp_supply)?;
+		if amount_a < amount_a_min || amount_b < amount_b_min {
+			return Err("slippage exceeds the bounds".into());
+		}
+
+		let pool_account = Self::pool_account(&pool_id);
+		Self::ensure_can_transfer(runtime, asset_a, &pool_account, &caller, amount_a)?;
+		Self::ensure_can_transfer(runtime, asset_b, &pool_account, &caller, amount_b)?;
+
+		Self::transfer(runtime, asset_a, &pool_account, &caller, amount_a)
+			.expect("checked above; qed");
+		Self::transfer(runtime, asset_b, &pool_account, &caller, amount_b)
+			.expect("checked above; qed");
+		let pallet: &mut Self = runtime.pallet_mut();
+		// The supply is at least the sum of the balances, which covers `lp_amount`.
+		pallet.pools.insert(pool_id, Pool { lp_supply: lp_supply - lp_amount });
+		if new_lp_balance.is_zero() {
+			pallet.lp_balances.remove(&pool_id, &caller);
+		} else {
+			pallet.lp_balances.insert(pool_id, caller, new_lp_balance);
+		}
+		Ok(())
+	}
+
+	/// Swap exactly `amount_in` of the first asset of `path` for at least `amount_out_min` of its
+	/// last asset, through the pools of every consecutive pair of assets.
+	#[call_index(3)]
+	pub fn swap_exact_in(
+		runtime: &mut T,
+		caller: T::AccountId,
+		path: Vec<AssetKind<T>>,
+		amount_in: T::Balance,
+		amount_out_min: T::Balance,
+	) -> DispatchResult {
+		Self::ensure_valid_path(&path)?;
+		if amount_in.is_zero() {
+			return Err("amount is zero".into());
+		}
+		let amounts = Self::amounts_out(runtime, &path, amount_in)?;
+		if *amounts.last().expect("the path is not empty; qed") < amount_out_min {
+			return Err("slippage exceeds the bounds".into());
+		}
+		Self::do_swap(runtime, &caller, &path, &amounts)
+	}
+
+	/// Swap at most `amount_in_max` of the first asset of `path` for exactly `amount_out` of its
+	/// last asset, through the pools of every consecutive pair of assets.
+	#[call_index(4)]
+	pub fn swap_exact_out(
+		runtime: &mut T,
+		caller: T::AccountId,
+		path: Vec<AssetKind<T>>,
+		amount_out: T::Balance,
+		amount_in_max: T::Balance,
+	) -> DispatchResult {
+		Self::ensure_valid_path(&path)?;
+		if amount_out.is_zero() {
+			return Err("amount is zero".into());
+		}
+		let amounts = Self::amounts_in(runtime, &path, amount_out)?;
+		if amounts[0] > amount_in_max {
+			return Err("slippage exceeds the bounds".into());
+		}
+		Self::do_swap(runtime, &caller, &path, &amounts)
+	}
+}
+
+impl<T: Config> crate::support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> crate::support::Hooks<T> for Pallet<T> {}
+
+#[cfg(test)]
+mod tests {
+	use super::NativeOrAsset::{Asset, Native};
+	use crate::Runtime;
+
+	type Dex = super::Pallet<Runtime>;
+
+	fn new_runtime() -> Runtime {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(alice.clone(), 1000);
+		for id in [1, 2] {
+			runtime.assets.create(alice.clone(), id).unwrap();
+			runtime.assets.mint(alice.clone(), id, alice.clone(), 1000).unwrap();
+		}
+		runtime
+	}
+
+	#[test]
+	fn add_and_remove_liquidity() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(bob.clone(), 100);
+		runtime.assets.mint(alice.clone(), 1, bob.clone(), 250).unwrap();
+
+		assert_eq!(
+			Dex::create_pool(&mut runtime, alice.clone(), Native, Native),
+			Err("identical assets".into())
+		);
+		assert_eq!(
+			Dex::create_pool(&mut runtime, alice.clone(), Native, Asset(9)),
+			Err("asset does not exist".into())
+		);
+		assert_eq!(Dex::create_pool(&mut runtime, alice.clone(), Asset(1), Native), Ok(()));
+		assert_eq!(
+			Dex::create_pool(&mut runtime, alice.clone(), Native, Asset(1)),
+			Err("pool already exists".into())
+		);
+
+		// The first deposit sets the price, and mints the square root of the product, less the
+		// locked minimum.
+		assert_eq!(
+			Dex::add_liquidity(&mut runtime, alice.clone(), Native, Asset(1), 100, 400, 0, 0),
+			Ok(())
+		);
+		assert_eq!(runtime.dex.lp_balance(Native, Asset(1), &alice), 190);
+		assert_eq!(runtime.dex.pool(Native, Asset(1)).unwrap().lp_supply, 200);
+		assert_eq!(Dex::reserves(&runtime, Native, Asset(1)), Ok((100, 400)));
+
+		// Later deposits follow the price.
+		assert_eq!(
+			Dex::add_liquidity(&mut runtime, bob.clone(), Native, Asset(1), 50, 250, 0, 201),
+			Err("slippage exceeds the bounds".into())
+		);
+		assert_eq!(
+			Dex::add_liquidity(&mut runtime, bob.clone(), Native, Asset(1), 50, 250, 0, 200),
+			Ok(())
+		);
+		assert_eq!(runtime.dex.lp_balance(Asset(1), Native, &bob), 100);
+		assert_eq!(runtime.dex.pool(Native, Asset(1)).unwrap().lp_supply, 300);
+		assert_eq!(runtime.balances.balance(&bob), 50);
+		assert_eq!(runtime.assets.balance(1, &bob), 50);
+
+		assert_eq!(
+			Dex::remove_liquidity(&mut runtime, bob.clone(), Native, Asset(1), 101, 0, 0),
+			Err("not enough liquidity tokens".into())
+		);
+		assert_eq!(
+			Dex::remove_liquidity(&mut runtime, bob.clone(), Native, Asset(1), 100, 50, 200),
+			Ok(())
+		);
+		assert_eq!(runtime.dex.lp_balance(Native, Asset(1), &bob), 0);
+		assert_eq!(runtime.dex.pool(Native, Asset(1)).unwrap().lp_supply, 200);
+		assert_eq!(runtime.balances.balance(&bob), 100);
+		assert_eq!(runtime.assets.balance(1, &bob), 250);
+	}
+
+	#[test]
+	fn first_deposit_is_checked_and_locks_the_minimum() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(bob.clone(), 100);
+		Dex::create_pool(&mut runtime, alice.clone(), Native, Asset(1)).unwrap();
+
+		assert_eq!(
+			Dex::add_liquidity(&mut runtime, alice.clone(), Native, Asset(1), 100, 400, 101, 0),
+			Err("slippage exceeds the bounds".into())
+		);
+		assert_eq!(
+			Dex::add_liquidity(&mut runtime, alice.clone(), Native, Asset(1), 10, 10, 0, 0),
+			Err("not enough liquidity minted".into())
+		);
+		// Bob has no asset 1, so neither asset is deposited.
+		assert_eq!(
+			Dex::add_liquidity(&mut runtime, bob.clone(), Native, Asset(1), 100, 400, 0, 0),
+			Err("Not enough funds.".into())
+		);
+		assert_eq!(runtime.balances.balance(&bob), 100);
+		assert_eq!(Dex::reserves(&runtime, Native, Asset(1)), Ok((0, 0)));
+		assert_eq!(runtime.dex.pool(Native, Asset(1)).unwrap().lp_supply, 0);
+
+		// Withdrawing every token of Alice leaves the locked share of the reserves in the pool.
+		Dex::add_liquidity(&mut runtime, alice.clone(), Native, Asset(1), 100, 400, 0, 0).unwrap();
+		assert_eq!(
+			Dex::remove_liquidity(&mut runtime, alice.clone(), Native, Asset(1), 190, 0, 0),
+			Ok(())
+		);
+		assert_eq!(Dex::reserves(&runtime, Native, Asset(1)), Ok((5, 20)));
+		assert_eq!(runtime.dex.pool(Native, Asset(1)).unwrap().lp_supply, 10);
+		assert_eq!(
+			Dex::remove_liquidity(&mut runtime, alice, Native, Asset(1), 1, 0, 0),
+			Err("not enough liquidity tokens".into())
+		);
+	}
+
+	#[test]
+	fn failed_removal_changes_nothing() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(bob.clone(), 50);
+		runtime.assets.mint(alice.clone(), 1, bob.clone(), 200).unwrap();
+		Dex::create_pool(&mut runtime, alice.clone(), Native, Asset(1)).unwrap();
+		Dex::add_liquidity(&mut runtime, alice, Native, Asset(1), 100, 400, 0, 0).unwrap();
+		Dex::add_liquidity(&mut runtime, bob.clone(), Native, Asset(1), 50, 200, 0, 0).unwrap();
+		assert_eq!(runtime.dex.lp_balance(Native, Asset(1), &bob), 100);
+
+		// Asset 1 could be paid out, but the native tokens cannot.
+		runtime.balances.set_balance(bob.clone(), u128::MAX);
+		assert_eq!(
+			Dex::remove_liquidity(&mut runtime, bob.clone(), Asset(1), Native, 100, 0, 0),
+			Err("Overflow".into())
+		);
+		assert_eq!(runtime.dex.lp_balance(Native, Asset(1), &bob), 100);
+		assert_eq!(runtime.dex.pool(Native, Asset(1)).unwrap().lp_supply, 300);
+		assert_eq!(runtime.assets.balance(1, &bob), 0);
+		assert_eq!(Dex::reserves(&runtime, Native, Asset(1)), Ok((150, 600)));
+	}
+
+	#[test]
+	fn swap_with_slippage_bounds() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(bob.clone(), 100);
+		Dex::create_pool(&mut runtime, alice.clone(), Native, Asset(1)).unwrap();
+		Dex::add_liquidity(&mut runtime, alice, Native, Asset(1), 100, 400, 0, 0).unwrap();
+
+		// A fee of 1 is rounded up from 0.1, leaving 9 to buy 400 * 9 / 109 = 33.02.
+		assert_eq!(
+			Dex::swap_exact_in(&mut runtime, bob.clone(), vec![Native, Asset(1)], 10, 34),
+			Err("slippage exceeds the bounds".into())
+		);
+		assert_eq!(
+			Dex::swap_exact_in(&mut runtime, bob.clone(), vec![Native, Asset(1)], 10, 33),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.balance(&bob), 90);
+		assert_eq!(runtime.assets.balance(1, &bob), 33);
+		assert_eq!(Dex::reserves(&runtime, Native, Asset(1)), Ok((110, 367)));
+
+		// Buying 20 costs 110 * 20 / 347 = 6.34 before the fee, rounded up to 7, and 8 with it.
+		assert_eq!(
+			Dex::swap_exact_out(&mut runtime, bob.clone(), vec![Native, Asset(1)], 20, 7),
+			Err("slippage exceeds the bounds".into())
+		);
+		assert_eq!(
+			Dex::swap_exact_out(&mut runtime, bob.clone(), vec![Native, Asset(1)], 20, 8),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.balance(&bob), 82);
+		assert_eq!(runtime.assets.balance(1, &bob), 53);
+		assert_eq!(Dex::reserves(&runtime, Native, Asset(1)), Ok((118, 347)));
+
+		assert_eq!(
+			Dex::swap_exact_out(&mut runtime, bob.clone(), vec![Native, Asset(1)], 347, 1000),
+			Err("not enough liquidity".into())
+		);
+		assert_eq!(
+			Dex::swap_exact_in(&mut runtime, bob, vec![Native], 10, 0),
+			Err("invalid path".into())
+		);
+	}
+
+	#[test]
+	fn multi_hop_swap() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.assets.mint(alice.clone(), 1, bob.clone(), 40).unwrap();
+		for (id, amount) in [(1, 400), (2, 100)] {
+			Dex::create_pool(&mut runtime, alice.clone(), Native, Asset(id)).unwrap();
+			Dex::add_liquidity(&mut runtime, alice.clone(), Native, Asset(id), 100, amount, 0, 0)
+				.unwrap();
+		}
+
+		assert_eq!(
+			Dex::swap_exact_in(&mut runtime, bob.clone(), vec![Asset(1), Asset(2)], 40, 0),
+			Err("pool does not exist".into())
+		);
+		// 39 of asset 1 buy 8 native tokens, of which 7 buy 6 of asset 2.
+		assert_eq!(
+			Dex::swap_exact_in(&mut runtime, bob.clone(), vec![Asset(1), Native, Asset(2)], 40, 6),
+			Ok(())
+		);
+		assert_eq!(runtime.assets.balance(1, &bob), 0);
+		assert_eq!(runtime.assets.balance(2, &bob), 6);
+		assert_eq!(runtime.balances.balance(&bob), 0);
+		assert_eq!(Dex::reserves(&runtime, Asset(1), Native), Ok((440, 92)));
+		assert_eq!(Dex::reserves(&runtime, Native, Asset(2)), Ok((108, 94)));
+	}
+	#[test]
+	fn failed_multi_hop_swap_changes_nothing() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.assets.mint(alice.clone(), 1, bob.clone(), 40).unwrap();
+		for (id, amount) in [(1, 400), (2, 100)] {
+			Dex::create_pool(&mut runtime, alice.clone(), Native, Asset(id)).unwrap();
+			Dex::add_liquidity(&mut runtime, alice.clone(), Native, Asset(id), 100, amount, 0, 0)
+				.unwrap();
+		}
+
+		// The first pool can take asset 1, but cannot pass its native tokens on to the second.
+		let pool_account = Dex::pool_account(&Dex::pool_id(Native, Asset(1)));
+		runtime.balances.set_lock(*b"testlock", &pool_account, 100);
+		assert_eq!(
+			Dex::swap_exact_in(&mut runtime, bob.clone(), vec![Asset(1), Native, Asset(2)], 40, 6),
+			Err("Liquidity restrictions prevent withdrawal.".into())
+		);
+		assert_eq!(runtime.assets.balance(1, &bob), 40);
+		assert_eq!(runtime.assets.balance(2, &bob), 0);
+		assert_eq!(Dex::reserves(&runtime, Asset(1), Native), Ok((400, 100)));
+		assert_eq!(Dex::reserves(&runtime, Native, Asset(2)), Ok((100, 100)));
+
+		runtime.balances.remove_lock(*b"testlock", &pool_account);
+		assert_eq!(
+			Dex::swap_exact_in(&mut runtime, bob.clone(), vec![Asset(1), Native, Asset(2)], 40, 6),
+			Ok(())
+		);
+		assert_eq!(runtime.assets.balance(2, &bob), 6);
+	}
+}
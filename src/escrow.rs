@@ -0,0 +1,285 @@
+use crate::{
+	storage::{Hash64Concat, StorageMap, StorageValue, ValueQuery},
+	support::{DispatchResult, GetPallet},
+};
+use num::CheckedAdd;
+
+#[macros::config]
+pub trait Config:
+	Sized
+	+ crate::balances::Config
+	+ crate::proof_of_existence::Config
+	+ GetPallet<crate::system::Pallet<Self>>
+	+ GetPallet<crate::balances::Pallet<Self>>
+	+ GetPallet<crate::proof_of_existence::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+}
+
+/// The index of an escrow.
+pub type EscrowIndex = u32;
+
+/// What must happen for an escrow to be released to the payee.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, macros::Encode, macros::Decode, macros::TypeInfo)]
+pub enum Condition<Content, AccountId> {
+	/// The content is claimed by `owner` in the proof of existence pallet, for example a document
+	/// notarized by a third party. A claim by anyone else, such as the payee, does not count.
+	Claim { owner: AccountId, content: Content },
+	/// The arbiter approved the escrow.
+	Arbiter(AccountId),
+}
+
+/// Funds reserved by a payer, to be paid to a payee once a condition is met.
+#[derive(Debug, macros::TypeInfo)]
+pub struct Escrow<T: Config> {
+	pub payer: T::AccountId,
+	pub payee: T::AccountId,
+	pub amount: T::Balance,
+	pub condition: Condition<T::Content, T::AccountId>,
+	/// The block after which the payer can get the funds back, if the condition is not met.
+	pub deadline: T::BlockNumber,
+	/// Whether the arbiter approved the escrow.
+	pub approved: bool,
+}
+
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	/// The number of escrows opened so far, used as the index of the next one.
+	escrow_count: StorageValue<EscrowIndex, ValueQuery>,
+	escrows: StorageMap<Hash64Concat, EscrowIndex, Escrow<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn escrow(&self, index: EscrowIndex) -> Option<&Escrow<T>> {
+		self.escrows.get_ref(&index)
+	}
+
+	/// Whether the condition of an escrow is met.
+	fn is_met(runtime: &T, escrow: &Escrow<T>) -> bool {
+		match &escrow.condition {
+			Condition::Claim { owner, content } => {
+				let poe: &crate::proof_of_existence::Pallet<T> = runtime.pallet();
+				poe.get_claim(content).as_ref() == Some(owner)
+			},
+			Condition::Arbiter(_) => escrow.approved,
+		}
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Reserve `amount` from the caller, to be paid to `payee` once `condition` is met. If it is
+	/// not met by `deadline`, the caller can get the funds back.
+	#[call_index(0)]
+	pub fn open_escrow(
+		runtime: &mut T,
+		caller: T::AccountId,
+		payee: T::AccountId,
+		amount: T::Balance,
+		condition: Condition<T::Content, T::AccountId>,
+		deadline: T::BlockNumber,
+	) -> DispatchResult {
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		if deadline <= system.block_number() {
+			return Err("deadline is in the past".into());
+		}
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.reserve(&caller, amount)?;
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let index = pallet.escrow_count.get();
+		pallet.escrow_count.put(index.checked_add(1).ok_or("too many escrows")?);
+		let escrow = Escrow { payer: caller, payee, amount, condition, deadline, approved: false };
+		pallet.escrows.insert(index, escrow);
+		Ok(())
+	}
+
+	/// Approve an escrow, as its arbiter.
+	#[call_index(1)]
+	pub fn approve(&mut self, caller: T::AccountId, index: EscrowIndex) -> DispatchResult {
+		let escrow = self.escrows.get_mut(&index).ok_or("escrow does not exist")?;
+		if escrow.condition != Condition::Arbiter(caller) {
+			return Err("not the arbiter".into());
+		}
+		escrow.approved = true;
+		Ok(())
+	}
+
+	/// Pay an escrow to its payee. Anyone can release an escrow once its condition is met, and
+	/// the payer can release it at any time.
+	#[call_index(2)]
+	pub fn release(runtime: &mut T, caller: T::AccountId, index: EscrowIndex) -> DispatchResult {
+		let pallet: &Self = runtime.pallet();
+		let escrow = pallet.escrows.get_ref(&index).ok_or("escrow does not exist")?;
+		if caller != escrow.payer && !Self::is_met(runtime, escrow) {
+			return Err("condition is not met".into());
+		}
+
+		// The amount moves from the reserved balance of the payer to the payee. Nothing is changed
+		// if it cannot.
+		let (payer, payee, amount) = (escrow.payer.clone(), escrow.payee.clone(), escrow.amount);
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.balance(&payee).checked_add(&amount).ok_or("Overflow")?;
+		balances.slash_reserved(&payer, amount)?;
+		balances.deposit(&payee, amount).expect("checked above; qed");
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.escrows.remove(&index);
+		Ok(())
+	}
+
+	/// Return an escrow to its payer, once its deadline passed without the condition being met.
+	#[call_index(3)]
+	pub fn refund(runtime: &mut T, _caller: T::AccountId, index: EscrowIndex) -> DispatchResult {
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		let now = system.block_number();
+		let pallet: &Self = runtime.pallet();
+		let escrow = pallet.escrows.get_ref(&index).ok_or("escrow does not exist")?;
+		if now <= escrow.deadline {
+			return Err("deadline has not passed".into());
+		}
+		if Self::is_met(runtime, escrow) {
+			return Err("condition is met".into());
+		}
+
+		let (payer, amount) = (escrow.payer.clone(), escrow.amount);
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.unreserve(&payer, amount)?;
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.escrows.remove(&index);
+		Ok(())
+	}
+}
+
+impl<T: Config> crate::support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> crate::support::Hooks<T> for Pallet<T> {}
+
+#[cfg(test)]
+mod tests {
+	use super::Condition;
+	use crate::Runtime;
+
+	type Escrow = super::Pallet<Runtime>;
+
+	fn new_runtime() -> Runtime {
+		let mut runtime = Runtime::new();
+		runtime.balances.set_balance("alice".to_string(), 100);
+		runtime
+	}
+
+	#[test]
+	fn release_when_claimed() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let notary = "notary".to_string();
+		let condition = Condition::Claim { owner: notary.clone(), content: "notarized document" };
+
+		assert_eq!(
+			Escrow::open_escrow(&mut runtime, alice.clone(), bob.clone(), 40, condition.clone(), 0),
+			Err("deadline is in the past".into())
+		);
+		assert_eq!(
+			Escrow::open_escrow(&mut runtime, alice.clone(), bob.clone(), 40, condition, 10),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 40);
+
+		assert_eq!(
+			Escrow::release(&mut runtime, bob.clone(), 0),
+			Err("condition is not met".into())
+		);
+		assert_eq!(runtime.proof_of_existence.create_claim(notary, "notarized document"), Ok(()));
+		assert_eq!(Escrow::release(&mut runtime, bob.clone(), 0), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 60);
+		assert_eq!(runtime.balances.balance(&bob), 40);
+		assert!(runtime.escrow.escrow(0).is_none());
+	}
+
+	#[test]
+	fn claim_by_someone_else_does_not_release() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let notary = "notary".to_string();
+		let condition = Condition::Claim { owner: notary.clone(), content: "notarized document" };
+
+		assert_eq!(
+			Escrow::open_escrow(&mut runtime, alice.clone(), bob.clone(), 40, condition, 10),
+			Ok(())
+		);
+		// The payee claiming the content themselves does not meet the condition.
+		assert_eq!(
+			runtime.proof_of_existence.create_claim(bob.clone(), "notarized document"),
+			Ok(())
+		);
+		assert_eq!(
+			Escrow::release(&mut runtime, bob.clone(), 0),
+			Err("condition is not met".into())
+		);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 40);
+		assert_eq!(runtime.balances.balance(&bob), 0);
+
+		assert_eq!(
+			runtime.proof_of_existence.revoke_claim(bob.clone(), "notarized document"),
+			Ok(())
+		);
+		assert_eq!(runtime.proof_of_existence.create_claim(notary, "notarized document"), Ok(()));
+		assert_eq!(Escrow::release(&mut runtime, bob.clone(), 0), Ok(()));
+		assert_eq!(runtime.balances.balance(&bob), 40);
+	}
+
+	#[test]
+	fn release_when_approved() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		let condition = Condition::Arbiter(charlie.clone());
+
+		assert_eq!(
+			Escrow::open_escrow(&mut runtime, alice.clone(), bob.clone(), 40, condition, 10),
+			Ok(())
+		);
+		assert_eq!(runtime.escrow.approve(bob.clone(), 0), Err("not the arbiter".into()));
+		assert_eq!(runtime.escrow.approve(charlie, 0), Ok(()));
+
+		// Bob cannot receive the amount, so the escrow is kept.
+		runtime.balances.set_balance(bob.clone(), u128::MAX);
+		assert_eq!(Escrow::release(&mut runtime, bob.clone(), 0), Err("Overflow".into()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 40);
+		assert!(runtime.escrow.escrow(0).is_some());
+
+		runtime.balances.set_balance(bob.clone(), 0);
+		assert_eq!(Escrow::release(&mut runtime, bob.clone(), 0), Ok(()));
+		assert_eq!(runtime.balances.balance(&bob), 40);
+	}
+
+	#[test]
+	fn refund_after_deadline() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let condition =
+			Condition::Claim { owner: "notary".to_string(), content: "notarized document" };
+
+		assert_eq!(
+			Escrow::open_escrow(&mut runtime, alice.clone(), bob.clone(), 40, condition, 1),
+			Ok(())
+		);
+		runtime.system.inc_block_number().unwrap();
+		assert_eq!(
+			Escrow::refund(&mut runtime, alice.clone(), 0),
+			Err("deadline has not passed".into())
+		);
+		runtime.system.inc_block_number().unwrap();
+		assert_eq!(Escrow::refund(&mut runtime, alice.clone(), 0), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(Escrow::release(&mut runtime, alice, 0), Err("escrow does not exist".into()));
+	}
+}
@@ -0,0 +1,552 @@
+use crate::{
+	storage::{Hash64Concat, StorageMap, StorageValue, ValueQuery},
+	support::{self, DispatchResult, GetPallet},
+	system::Origin,
+};
+use num::{traits::NumOps, CheckedAdd, CheckedMul, CheckedSub, Zero};
+
+#[macros::config]
+pub trait Config:
+	Sized
+	+ crate::balances::Config<Balance: NumOps + CheckedMul + From<u32>>
+	+ GetPallet<crate::balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The amount reserved for an identity, on top of its per-byte deposit.
+	const BASIC_DEPOSIT: Self::Balance;
+	/// The amount reserved for each byte of the information of an identity.
+	const BYTE_DEPOSIT: Self::Balance;
+	/// The amount reserved for each sub-account.
+	const SUB_ACCOUNT_DEPOSIT: Self::Balance;
+	/// The maximum number of sub-accounts of an identity.
+	const MAX_SUB_ACCOUNTS: u32;
+}
+
+/// The index of a registrar.
+pub type RegistrarIndex = u32;
+
+/// The information of an identity. Empty fields are not set.
+#[derive(
+	Debug, Clone, Default, PartialEq, Eq, Hash, macros::Encode, macros::Decode, macros::TypeInfo,
+)]
+pub struct IdentityInfo {
+	pub display: String,
+	pub email: String,
+	pub web: String,
+	pub legal: String,
+}
+
+impl IdentityInfo {
+	/// The size of the information, which the deposit is based on.
+	pub fn size(&self) -> usize {
+		self.display.len() + self.email.len() + self.web.len() + self.legal.len()
+	}
+}
+
+/// What a registrar thinks of an identity.
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, Hash, macros::Encode, macros::Decode, macros::TypeInfo,
+)]
+pub enum Judgement<Balance> {
+	/// No judgement yet, but a judgement was requested and this fee is reserved to pay for it.
+	FeePaid(Balance),
+	/// The information looks right, but was not checked in depth.
+	Reasonable,
+	/// The information was checked, and is known to be right.
+	KnownGood,
+	/// The information was right, but is not anymore.
+	OutOfDate,
+	/// The information is not good enough to be judged.
+	LowQuality,
+	/// The information is wrong.
+	Erroneous,
+}
+
+/// An identity, with its deposit and the judgements of registrars.
+#[derive(Debug, Clone, PartialEq, Eq, macros::TypeInfo)]
+pub struct Registration<Balance> {
+	/// The judgements of registrars, sorted by registrar index.
+	pub judgements: Vec<(RegistrarIndex, Judgement<Balance>)>,
+	pub deposit: Balance,
+	pub info: IdentityInfo,
+}
+
+/// A registrar, who judges identities for a fee.
+#[derive(Debug, Clone, PartialEq, Eq, macros::TypeInfo)]
+pub struct RegistrarInfo<AccountId, Balance> {
+	pub account: AccountId,
+	pub fee: Balance,
+}
+
+/// The deposit reserved for the sub-accounts of an identity, and the sub-accounts.
+pub type SubAccounts<T> =
+	(<T as crate::balances::Config>::Balance, Vec<<T as crate::system::Config>::AccountId>);
+
+/// The registrars, by registrar index.
+pub type Registrars<T> = Vec<
+	RegistrarInfo<<T as crate::system::Config>::AccountId, <T as crate::balances::Config>::Balance>,
+>;
+
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	identities: StorageMap<Hash64Concat, T::AccountId, Registration<T::Balance>>,
+	/// The parent of each sub-account, and the name of the sub-account.
+	super_of: StorageMap<Hash64Concat, T::AccountId, (T::AccountId, String)>,
+	/// The sub-accounts of each identity, and the deposit reserved for them.
+	subs_of: StorageMap<Hash64Concat, T::AccountId, SubAccounts<T>>,
+	registrars: StorageValue<Registrars<T>, ValueQuery>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn identity(&self, who: &T::AccountId) -> Option<&Registration<T::Balance>> {
+		self.identities.get_ref(who)
+	}
+
+	/// The parent account and the name of a sub-account.
+	pub fn super_of(&self, who: &T::AccountId) -> Option<&(T::AccountId, String)> {
+		self.super_of.get_ref(who)
+	}
+
+	pub fn subs_of(&self, who: &T::AccountId) -> &[T::AccountId] {
+		self.subs_of.get_ref(who).map_or(&[], |(_, subs)| subs)
+	}
+
+	pub fn registrars(&self) -> &[RegistrarInfo<T::AccountId, T::Balance>] {
+		self.registrars.get_ref().map_or(&[], Vec::as_slice)
+	}
+
+	/// Reserve or unreserve funds of `who`, so that `new` is reserved instead of `old`.
+	fn update_deposit(
+		runtime: &mut T,
+		who: &T::AccountId,
+		old: T::Balance,
+		new: T::Balance,
+	) -> DispatchResult {
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		if let Some(extra) = new.checked_sub(&old).filter(|extra| !extra.is_zero()) {
+			balances.reserve(who, extra)?;
+		} else if let Some(refund) = old.checked_sub(&new) {
+			balances.unreserve(who, refund)?;
+		}
+		Ok(())
+	}
+
+	/// Check that `who` is the account of registrar `reg_index`.
+	fn ensure_registrar(&self, who: &T::AccountId, reg_index: RegistrarIndex) -> DispatchResult {
+		let registrar =
+			self.registrars().get(reg_index as usize).ok_or("registrar does not exist")?;
+		if registrar.account != *who {
+			return Err("not the registrar".into());
+		}
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Add a registrar, who starts with no fee. Only root may add registrars.
+	#[call_index(0)]
+	pub fn add_registrar(&mut self, origin: Origin<T>, account: T::AccountId) -> DispatchResult {
+		support::ensure_root(origin)?;
+		self.registrars
+			.mutate(|registrars| registrars.push(RegistrarInfo { account, fee: Zero::zero() }));
+		Ok(())
+	}
+
+	/// Set the fee a registrar asks for a judgement.
+	#[call_index(1)]
+	pub fn set_fee(
+		&mut self,
+		caller: T::AccountId,
+		reg_index: RegistrarIndex,
+		fee: T::Balance,
+	) -> DispatchResult {
+		self.ensure_registrar(&caller, reg_index)?;
+		self.registrars.mutate(|registrars| registrars[reg_index as usize].fee = fee);
+		Ok(())
+	}
+
+	/// Set the identity of the caller, replacing any previous one.
+	///
+	/// The deposit is `BASIC_DEPOSIT`, plus `BYTE_DEPOSIT` for each byte of information. Changing
+	/// the identity removes the judgements on it, except the ones which are requested and paid.
+	#[call_index(2)]
+	pub fn set_identity(
+		runtime: &mut T,
+		caller: T::AccountId,
+		info: IdentityInfo,
+	) -> DispatchResult {
+		let size = u32::try_from(info.size()).map_err(|_| "Overflow")?;
+		let deposit = T::BYTE_DEPOSIT
+			.checked_mul(&size.into())
+			.and_then(|bytes_deposit| bytes_deposit.checked_add(&T::BASIC_DEPOSIT))
+			.ok_or("Overflow")?;
+		let pallet: &Self = runtime.pallet();
+		let (old_deposit, mut judgements) = match pallet.identities.get_ref(&caller) {
+			Some(registration) => (registration.deposit, registration.judgements.clone()),
+			None => (Zero::zero(), Vec::new()),
+		};
+		Self::update_deposit(runtime, &caller, old_deposit, deposit)?;
+
+		judgements.retain(|(_, judgement)| matches!(judgement, Judgement::FeePaid(_)));
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.identities.insert(caller, Registration { judgements, deposit, info });
+		Ok(())
+	}
+
+	/// Set the sub-accounts of the caller, with a name for each, replacing any previous ones. The
+	/// caller must have an identity, and reserves `SUB_ACCOUNT_DEPOSIT` for each sub-account.
+	#[call_index(3)]
+	pub fn set_subs(
+		runtime: &mut T,
+		caller: T::AccountId,
+		subs: Vec<(T::AccountId, String)>,
+	) -> DispatchResult {
+		if subs.len() > T::MAX_SUB_ACCOUNTS as usize {
+			return Err("too many sub-accounts".into());
+		}
+		let pallet: &Self = runtime.pallet();
+		if !pallet.identities.contains_key(&caller) {
+			return Err("no identity".into());
+		}
+		for (position, (sub, _)) in subs.iter().enumerate() {
+			if *sub == caller {
+				return Err("an identity cannot be its own sub-account".into());
+			}
+			if subs[..position].iter().any(|(other, _)| other == sub) {
+				return Err("duplicate sub-accounts".into());
+			}
+			if pallet.super_of.get_ref(sub).is_some_and(|(parent, _)| *parent != caller) {
+				return Err("sub-account already belongs to another identity".into());
+			}
+		}
+		let old_deposit =
+			pallet.subs_of.get_ref(&caller).map_or(Zero::zero(), |(deposit, _)| *deposit);
+		let sub_count = u32::try_from(subs.len()).map_err(|_| "Overflow")?;
+		let deposit = T::SUB_ACCOUNT_DEPOSIT.checked_mul(&sub_count.into()).ok_or("Overflow")?;
+		Self::update_deposit(runtime, &caller, old_deposit, deposit)?;
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let old_subs = pallet.subs_of.take(&caller).map(|(_, subs)| subs).unwrap_or_default();
+		for sub in old_subs {
+			pallet.super_of.remove(&sub);
+		}
+		let accounts = subs.iter().map(|(sub, _)| sub.clone()).collect::<Vec<_>>();
+		for (sub, name) in subs {
+			pallet.super_of.insert(sub, (caller.clone(), name));
+		}
+		if !accounts.is_empty() {
+			pallet.subs_of.insert(caller, (deposit, accounts));
+		}
+		Ok(())
+	}
+
+	/// Clear the identity and the sub-accounts of the caller, and return their deposits and the
+	/// fees of pending judgements.
+	#[call_index(4)]
+	pub fn clear_identity(runtime: &mut T, caller: T::AccountId) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let registration = pallet.identities.take(&caller).ok_or("no identity")?;
+		let (subs_deposit, subs) =
+			pallet.subs_of.take(&caller).unwrap_or_else(|| (Zero::zero(), Vec::new()));
+		for sub in subs {
+			pallet.super_of.remove(&sub);
+		}
+
+		let fees = registration.judgements.iter().filter_map(|(_, judgement)| match judgement {
+			Judgement::FeePaid(fee) => Some(*fee),
+			_ => None,
+		});
+		let refund = fees
+			.chain([registration.deposit, subs_deposit])
+			.try_fold(T::Balance::zero(), |total, value| total.checked_add(&value))
+			.ok_or("Overflow")?;
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.unreserve(&caller, refund)
+	}
+
+	/// Ask registrar `reg_index` to judge the identity of the caller, and reserve their fee. The
+	/// request fails if the fee is higher than `max_fee`.
+	#[call_index(5)]
+	pub fn request_judgement(
+		runtime: &mut T,
+		caller: T::AccountId,
+		reg_index: RegistrarIndex,
+		max_fee: T::Balance,
+	) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let fee = pallet
+			.registrars()
+			.get(reg_index as usize)
+			.ok_or("registrar does not exist")?
+			.fee;
+		if fee > max_fee {
+			return Err("fee is higher than the maximum".into());
+		}
+		let registration = pallet.identities.get_ref(&caller).ok_or("no identity")?;
+		let position =
+			registration.judgements.binary_search_by_key(&reg_index, |(index, _)| *index);
+		match position.map(|position| registration.judgements[position].1) {
+			Ok(Judgement::FeePaid(_)) => return Err("judgement already requested".into()),
+			// A judgement that the information is wrong stays until the information changes.
+			Ok(Judgement::Erroneous) => return Err("judgement is sticky".into()),
+			_ => {},
+		}
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.reserve(&caller, fee)?;
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let judgements =
+			&mut pallet.identities.get_mut(&caller).expect("checked above; qed").judgements;
+		match position {
+			Ok(position) => judgements[position].1 = Judgement::FeePaid(fee),
+			Err(position) => judgements.insert(position, (reg_index, Judgement::FeePaid(fee))),
+		}
+		Ok(())
+	}
+
+	/// Cancel a judgement request which was not answered yet, and return its fee.
+	#[call_index(6)]
+	pub fn cancel_request(
+		runtime: &mut T,
+		caller: T::AccountId,
+		reg_index: RegistrarIndex,
+	) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let registration = pallet.identities.get_mut(&caller).ok_or("no identity")?;
+		let position = registration
+			.judgements
+			.binary_search_by_key(&reg_index, |(index, _)| *index)
+			.map_err(|_| "judgement not requested")?;
+		let Judgement::FeePaid(fee) = registration.judgements[position].1 else {
+			return Err("judgement already given".into());
+		};
+		registration.judgements.remove(position);
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.unreserve(&caller, fee)
+	}
+
+	/// Judge the identity of `target`, as registrar `reg_index`, who is paid the reserved fee. The
+	/// judgement must have been requested with `request_judgement`.
+	#[call_index(7)]
+	pub fn provide_judgement(
+		runtime: &mut T,
+		caller: T::AccountId,
+		reg_index: RegistrarIndex,
+		target: T::AccountId,
+		judgement: Judgement<T::Balance>,
+	) -> DispatchResult {
+		if matches!(judgement, Judgement::FeePaid(_)) {
+			return Err("invalid judgement".into());
+		}
+		let pallet: &Self = runtime.pallet();
+		pallet.ensure_registrar(&caller, reg_index)?;
+		let registration = pallet.identities.get_ref(&target).ok_or("no identity")?;
+		let position = registration
+			.judgements
+			.binary_search_by_key(&reg_index, |(index, _)| *index)
+			.map_err(|_| "judgement not requested")?;
+		let Judgement::FeePaid(fee) = registration.judgements[position].1 else {
+			return Err("judgement not requested".into());
+		};
+
+		// The fee moves from the reserved balance of the target to the registrar.
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.balance(&caller).checked_add(&fee).ok_or("Overflow")?;
+		balances.slash_reserved(&target, fee)?;
+		balances.deposit(&caller, fee).expect("checked above; qed");
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let registration = pallet.identities.get_mut(&target).expect("checked above; qed");
+		registration.judgements[position].1 = judgement;
+		Ok(())
+	}
+}
+
+impl<T: Config> support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> support::Hooks<T> for Pallet<T> {}
+
+#[cfg(test)]
+mod tests {
+	use super::{IdentityInfo, Judgement};
+	use crate::{support::RawOrigin, Runtime};
+
+	type Identity = super::Pallet<Runtime>;
+
+	fn info(display: &str) -> IdentityInfo {
+		IdentityInfo { display: display.to_string(), ..Default::default() }
+	}
+
+	#[test]
+	fn set_and_clear_identity() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+
+		// The deposit is 10, and 1 for each byte.
+		assert_eq!(Identity::set_identity(&mut runtime, alice.clone(), info("Alice")), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 15);
+		let info = IdentityInfo {
+			display: "Alice".to_string(),
+			email: "alice@example.com".to_string(),
+			web: "".to_string(),
+			legal: "Alice Liddell".to_string(),
+		};
+		assert_eq!(Identity::set_identity(&mut runtime, alice.clone(), info.clone()), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 45);
+		assert_eq!(
+			runtime.identity.identity(&alice).map(|registration| &registration.info),
+			Some(&info)
+		);
+
+		assert_eq!(
+			Identity::set_subs(
+				&mut runtime,
+				bob.clone(),
+				vec![(alice.clone(), "main".to_string())]
+			),
+			Err("no identity".into())
+		);
+		assert_eq!(
+			Identity::set_subs(
+				&mut runtime,
+				alice.clone(),
+				vec![(bob.clone(), "savings".to_string())]
+			),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 47);
+		assert_eq!(runtime.identity.super_of(&bob), Some(&(alice.clone(), "savings".to_string())));
+
+		// The subs must be other accounts, each given once.
+		assert_eq!(
+			Identity::set_subs(
+				&mut runtime,
+				alice.clone(),
+				vec![(alice.clone(), "main".to_string())]
+			),
+			Err("an identity cannot be its own sub-account".into())
+		);
+		assert_eq!(
+			Identity::set_subs(
+				&mut runtime,
+				alice.clone(),
+				vec![(bob.clone(), "savings".to_string()), (bob.clone(), "spending".to_string())]
+			),
+			Err("duplicate sub-accounts".into())
+		);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 47);
+		assert_eq!(runtime.identity.subs_of(&alice), ["bob".to_string()]);
+
+		assert_eq!(Identity::clear_identity(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(
+			Identity::clear_identity(&mut runtime, alice.clone()),
+			Err("no identity".into())
+		);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert!(runtime.identity.super_of(&bob).is_none());
+	}
+
+	#[test]
+	fn judgements() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let registrar = "registrar".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+
+		assert_eq!(
+			runtime
+				.identity
+				.add_registrar(RawOrigin::Signed(alice.clone()), registrar.clone()),
+			Err("bad origin: expected the root origin".into())
+		);
+		assert_eq!(runtime.identity.add_registrar(RawOrigin::Root, registrar.clone()), Ok(()));
+		assert_eq!(runtime.identity.set_fee(alice.clone(), 0, 1), Err("not the registrar".into()));
+		assert_eq!(runtime.identity.set_fee(registrar.clone(), 0, 20), Ok(()));
+
+		assert_eq!(
+			Identity::request_judgement(&mut runtime, alice.clone(), 0, 20),
+			Err("no identity".into())
+		);
+		assert_eq!(Identity::set_identity(&mut runtime, alice.clone(), info("Alice")), Ok(()));
+
+		// Registrars only judge identities which requested it.
+		assert_eq!(
+			Identity::provide_judgement(
+				&mut runtime,
+				registrar.clone(),
+				0,
+				alice.clone(),
+				Judgement::Reasonable
+			),
+			Err("judgement not requested".into())
+		);
+		assert!(runtime
+			.identity
+			.identity(&alice)
+			.is_some_and(|registration| registration.judgements.is_empty()));
+
+		assert_eq!(
+			Identity::request_judgement(&mut runtime, alice.clone(), 0, 19),
+			Err("fee is higher than the maximum".into())
+		);
+		assert_eq!(Identity::request_judgement(&mut runtime, alice.clone(), 0, 20), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 35);
+
+		assert_eq!(
+			Identity::provide_judgement(
+				&mut runtime,
+				registrar.clone(),
+				0,
+				alice.clone(),
+				Judgement::FeePaid(0)
+			),
+			Err("invalid judgement".into())
+		);
+		assert_eq!(
+			Identity::provide_judgement(
+				&mut runtime,
+				registrar.clone(),
+				0,
+				alice.clone(),
+				Judgement::KnownGood
+			),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.balance(&registrar), 20);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 15);
+		assert_eq!(
+			runtime
+				.identity
+				.identity(&alice)
+				.map(|registration| &registration.judgements[..]),
+			Some(&[(0, Judgement::KnownGood)][..])
+		);
+
+		// The request is consumed by the judgement.
+		assert_eq!(
+			Identity::provide_judgement(
+				&mut runtime,
+				registrar.clone(),
+				0,
+				alice.clone(),
+				Judgement::Erroneous
+			),
+			Err("judgement not requested".into())
+		);
+		assert_eq!(runtime.balances.balance(&registrar), 20);
+
+		// Changing the identity removes the judgement.
+		assert_eq!(Identity::set_identity(&mut runtime, alice.clone(), info("Alicia")), Ok(()));
+		assert!(runtime
+			.identity
+			.identity(&alice)
+			.is_some_and(|registration| registration.judgements.is_empty()));
+	}
+}
@@ -0,0 +1,318 @@
+use crate::{
+	storage::{Hash64Concat, StorageMap},
+	support::{self, DispatchError, DispatchResult, GetPallet, MultiAddress},
+	system::Origin,
+};
+use core::fmt::Debug;
+use num::Zero;
+
+#[macros::config]
+pub trait Config:
+	Sized
+	+ crate::system::Config<RuntimeEvent: From<Event<Self>>>
+	+ crate::balances::Config
+	+ GetPallet<crate::system::Pallet<Self>>
+	+ GetPallet<crate::balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The index of an account, which is shorter than the account itself.
+	type AccountIndex: Ord + Copy + Debug + std::hash::Hash;
+	/// The amount reserved for each claimed index.
+	const DEPOSIT: Self::Balance;
+}
+
+#[macros::event]
+pub enum Event<T: Config> {
+	/// An index was assigned to an account, by a claim or a transfer.
+	IndexAssigned { who: T::AccountId, index: T::AccountIndex },
+	/// An index was freed, and can be claimed again.
+	IndexFreed { index: T::AccountIndex },
+}
+
+#[macros::error]
+pub enum Error<T> {
+	/// The index is not claimed.
+	NotAssigned,
+	/// The index is claimed by another account.
+	NotOwner,
+	/// The index is already claimed.
+	InUse,
+	/// The index is already owned by this account.
+	NotTransfer,
+}
+
+/// Short indices for accounts, so that extrinsics and calls can refer to an account by its index
+/// instead of its full id. See `support::Lookup`.
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	/// The account of each claimed index, and the deposit reserved for it.
+	accounts: StorageMap<Hash64Concat, T::AccountIndex, (T::AccountId, T::Balance)>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// The account which owns `index`, if it is claimed.
+	pub fn lookup_index(&self, index: T::AccountIndex) -> Option<&T::AccountId> {
+		self.accounts.get_ref(&index).map(|(who, _)| who)
+	}
+
+	/// Resolve an address to its account.
+	pub fn lookup_address(
+		&self,
+		address: MultiAddress<T::AccountId, T::AccountIndex>,
+	) -> Result<T::AccountId, DispatchError> {
+		match address {
+			MultiAddress::Id(who) => Ok(who),
+			MultiAddress::Index(index) =>
+				self.lookup_index(index).cloned().ok_or(Error::<T>::NotAssigned.into()),
+		}
+	}
+
+	/// The deposit held for `index`, if it is owned by `who`.
+	fn owned_deposit(
+		&self,
+		who: &T::AccountId,
+		index: T::AccountIndex,
+	) -> Result<T::Balance, DispatchError> {
+		let (owner, deposit) = self.accounts.get_ref(&index).ok_or(Error::<T>::NotAssigned)?;
+		if owner != who {
+			return Err(Error::<T>::NotOwner.into());
+		}
+		Ok(*deposit)
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Claim an unused index for the caller, reserving the deposit.
+	#[call_index(0)]
+	pub fn claim(runtime: &mut T, caller: T::AccountId, index: T::AccountIndex) -> DispatchResult {
+		let pallet: &Self = runtime.pallet();
+		if pallet.accounts.contains_key(&index) {
+			return Err(Error::<T>::InUse.into());
+		}
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.reserve(&caller, T::DEPOSIT)?;
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.accounts.insert(index, (caller.clone(), T::DEPOSIT));
+		Self::deposit_event(runtime, Event::IndexAssigned { who: caller, index });
+		Ok(())
+	}
+
+	/// Give an index owned by the caller to `new`, along with its deposit.
+	#[call_index(1)]
+	pub fn transfer(
+		runtime: &mut T,
+		caller: T::AccountId,
+		new: T::AccountId,
+		index: T::AccountIndex,
+	) -> DispatchResult {
+		if caller == new {
+			return Err(Error::<T>::NotTransfer.into());
+		}
+		let pallet: &Self = runtime.pallet();
+		let deposit = pallet.owned_deposit(&caller, index)?;
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.repatriate_reserved(&caller, &new, deposit)?;
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.accounts.insert(index, (new.clone(), deposit));
+		Self::deposit_event(runtime, Event::IndexAssigned { who: new, index });
+		Ok(())
+	}
+
+	/// Free an index owned by the caller, and return its deposit.
+	#[call_index(2)]
+	pub fn free(runtime: &mut T, caller: T::AccountId, index: T::AccountIndex) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let deposit = pallet.owned_deposit(&caller, index)?;
+		pallet.accounts.remove(&index);
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.unreserve(&caller, deposit)?;
+		Self::deposit_event(runtime, Event::IndexFreed { index });
+		Ok(())
+	}
+
+	/// Give an index to `new` as the root origin, whether it is claimed or not. The previous owner
+	/// gets their deposit back, and `new` holds the index without a deposit.
+	#[call_index(3)]
+	pub fn force_transfer(
+		runtime: &mut T,
+		origin: Origin<T>,
+		new: T::AccountId,
+		index: T::AccountIndex,
+	) -> DispatchResult {
+		support::ensure_root(origin)?;
+		let pallet: &mut Self = runtime.pallet_mut();
+		let previous = pallet.accounts.take(&index);
+		pallet.accounts.insert(index, (new.clone(), Zero::zero()));
+		if let Some((previous, deposit)) = previous {
+			let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+			balances.unreserve(&previous, deposit)?;
+		}
+		Self::deposit_event(runtime, Event::IndexAssigned { who: new, index });
+		Ok(())
+	}
+}
+
+impl<T: Config> crate::support::PalletInfo for Pallet<T> {
+	const HAS_EVENT: bool = true;
+	const HAS_ERROR: bool = true;
+}
+
+impl<T: Config> crate::support::Hooks<T> for Pallet<T> {}
+
+#[cfg(test)]
+mod tests {
+	use super::Event;
+	use crate::{
+		support::{self, Dispatch, DispatchError, ModuleError, MultiAddress, RawOrigin},
+		Runtime, RuntimeCall, RuntimeEvent,
+	};
+
+	type Indices = super::Pallet<Runtime>;
+	type Error = super::Error<Runtime>;
+
+	#[test]
+	fn claim_transfer_and_free() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+
+		assert_eq!(Indices::claim(&mut runtime, alice.clone(), 7), Ok(()));
+		assert_eq!(Indices::claim(&mut runtime, bob.clone(), 7), Err(Error::InUse.into()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 10);
+		assert_eq!(runtime.indices.lookup_address(MultiAddress::Index(7)), Ok(alice.clone()));
+		assert_eq!(
+			runtime.indices.lookup_address(MultiAddress::Index(8)),
+			Err(Error::NotAssigned.into())
+		);
+
+		assert_eq!(
+			Indices::transfer(&mut runtime, bob.clone(), alice.clone(), 7),
+			Err(Error::NotOwner.into())
+		);
+		assert_eq!(Indices::transfer(&mut runtime, alice.clone(), bob.clone(), 7), Ok(()));
+		assert_eq!(runtime.indices.lookup_index(7), Some(&bob));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.reserved_balance(&bob), 10);
+
+		assert_eq!(Indices::free(&mut runtime, alice.clone(), 7), Err(Error::NotOwner.into()));
+		assert_eq!(Indices::free(&mut runtime, bob.clone(), 7), Ok(()));
+		assert_eq!(runtime.indices.lookup_index(7), None);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+
+		// Failed calls do not deposit events.
+		let assigned = |who: &String| {
+			RuntimeEvent::indices(Event::IndexAssigned { who: who.clone(), index: 7 })
+		};
+		assert_eq!(
+			runtime.system.events(),
+			[
+				assigned(&alice),
+				assigned(&bob),
+				RuntimeEvent::indices(Event::IndexFreed { index: 7 })
+			]
+		);
+	}
+
+	#[test]
+	fn errors_and_events_are_described() {
+		let error: DispatchError = Error::NotOwner.into();
+		assert_eq!(
+			error,
+			DispatchError::Module(ModuleError {
+				pallet: "indices",
+				index: 1,
+				name: "NotOwner",
+				description: "The index is claimed by another account.",
+			})
+		);
+		assert_eq!(error.to_string(), "The index is claimed by another account.");
+		assert_eq!(Error::NotOwner, Error::NotOwner);
+		assert_ne!(Error::NotOwner, Error::InUse);
+
+		let event = Event::<Runtime>::IndexFreed { index: 7 };
+		assert_eq!((event.index(), event.name()), (1, "IndexFreed"));
+		assert_eq!(event.description(), "An index was freed, and can be claimed again.");
+		assert_eq!(Event::<Runtime>::VARIANTS.len(), 2);
+	}
+
+	#[test]
+	fn force_transfer_returns_the_deposit() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		Indices::claim(&mut runtime, alice.clone(), 7).unwrap();
+
+		assert_eq!(
+			Indices::force_transfer(&mut runtime, RawOrigin::Signed(alice.clone()), bob.clone(), 7),
+			Err("bad origin: expected the root origin".into())
+		);
+		assert_eq!(Indices::force_transfer(&mut runtime, RawOrigin::Root, bob.clone(), 7), Ok(()));
+		assert_eq!(runtime.indices.lookup_index(7), Some(&bob));
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+
+		// Bob holds the index without a deposit, so freeing it returns nothing.
+		assert_eq!(Indices::free(&mut runtime, bob.clone(), 7), Ok(()));
+		assert_eq!(runtime.balances.balance(&bob), 0);
+	}
+
+	#[test]
+	fn calls_and_extrinsics_accept_indices() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		Indices::claim(&mut runtime, alice.clone(), 1).unwrap();
+		Indices::force_transfer(&mut runtime, RawOrigin::Root, bob.clone(), 2).unwrap();
+
+		// The destination of the transfer is resolved from bob's index before it is dispatched.
+		let transfer =
+			|to, amount| RuntimeCall::balances(crate::balances::Call::transfer { to, amount });
+		assert_eq!(
+			runtime
+				.dispatch(RawOrigin::Signed(alice.clone()), transfer(MultiAddress::Index(2), 20)),
+			Ok(())
+		);
+		assert_eq!(
+			runtime
+				.dispatch(RawOrigin::Signed(alice.clone()), transfer(MultiAddress::Index(3), 20)),
+			Err(Error::NotAssigned.into())
+		);
+		assert_eq!(runtime.balances.balance(&bob), 20);
+
+		// The caller of an extrinsic is resolved from alice's index. Extrinsics from an unknown
+		// index are skipped.
+		let block = crate::types::Block {
+			header: support::Header { block_number: 1 },
+			extrinsics: vec![
+				support::Extrinsic {
+					caller: None,
+					call: RuntimeCall::timestamp(crate::timestamp::Call::set { now: 6_000 }),
+				},
+				support::Extrinsic {
+					caller: Some(MultiAddress::Index(1)),
+					call: transfer(bob.clone().into(), 30),
+				},
+				support::Extrinsic {
+					caller: Some(MultiAddress::Index(3)),
+					call: transfer(alice.clone().into(), 30),
+				},
+			],
+		};
+		let report = runtime.execute_block(block).expect("valid block");
+		assert_eq!(report.applied, [0, 1]);
+		assert_eq!(report.failed, [(2, DispatchError::from(Error::NotAssigned))]);
+		assert_eq!(runtime.balances.balance(&bob), 50);
+		assert_eq!(runtime.system.nonce(&alice), 1);
+		// The events of the claims were deposited before the block, so they are removed.
+		assert_eq!(runtime.system.events(), []);
+	}
+}
@@ -1,64 +1,305 @@
+mod assets;
 mod balances;
+mod codec;
+mod collective;
+mod democracy;
+mod dex;
+mod escrow;
+mod identity;
+mod indices;
+mod metadata;
+mod multisig;
+mod preimage;
 mod proof_of_existence;
+mod proxy;
+mod randomness;
+mod recovery;
+mod scheduler;
+mod staking;
+mod storage;
 mod support;
 mod system;
+#[cfg(test)]
+mod test_helpers;
+mod timestamp;
+mod treasury;
 
-use crate::support::Dispatch;
+use crate::{
+	codec::Encode,
+	metadata::ToJson,
+	support::{
+		ChargeTransaction, EitherOf, EnsureProportionAtLeast, EnsureRoot, InstanceFilter, Lookup,
+		Percent,
+	},
+};
 
 mod types {
 	pub type AccountId = String;
 	pub type Balance = u128;
 	pub type BlockNumber = u32;
 	pub type Nonce = u32;
-	pub type Extrinsic = crate::support::Extrinsic<AccountId, crate::RuntimeCall>;
+	pub type AccountIndex = u32;
+	pub type Address = crate::support::MultiAddress<AccountId, AccountIndex>;
+	pub type Extrinsic = crate::support::Extrinsic<Address, crate::RuntimeCall>;
 	pub type Header = crate::support::Header<BlockNumber>;
 	pub type Block = crate::support::Block<Header, Extrinsic>;
 	pub type Content = &'static str;
+	pub type Moment = u64;
+	pub type AssetId = u32;
 }
 
 #[derive(Debug)]
-#[macros::runtime] // This macro doesn't compile
+#[macros::runtime]
 pub struct Runtime {
 	system: system::Pallet<Self>,
+	#[pallet_index(0)]
+	#[genesis]
 	balances: balances::Pallet<Self>,
+	#[pallet_index(1)]
+	#[event]
+	#[error]
+	indices: indices::Pallet<Self>,
+	#[pallet_index(2)]
 	proof_of_existence: proof_of_existence::Pallet<Self>,
+	#[pallet_index(3)]
+	multisig: multisig::Pallet<Self>,
+	#[pallet_index(4)]
+	proxy: proxy::Pallet<Self>,
+	#[pallet_index(5)]
+	scheduler: scheduler::Pallet<Self>,
+	#[pallet_index(6)]
+	timestamp: timestamp::Pallet<Self>,
+	#[pallet_index(7)]
+	treasury: treasury::Pallet<Self>,
+	#[pallet_index(8)]
+	democracy: democracy::Pallet<Self>,
+	#[pallet_index(9)]
+	collective: collective::Pallet<Self>,
+	#[pallet_index(10)]
+	staking: staking::Pallet<Self>,
+	#[pallet_index(11)]
+	identity: identity::Pallet<Self>,
+	#[pallet_index(12)]
+	escrow: escrow::Pallet<Self>,
+	#[pallet_index(13)]
+	assets: assets::Pallet<Self>,
+	#[pallet_index(14)]
+	dex: dex::Pallet<Self>,
+	#[pallet_index(15)]
+	randomness: randomness::Pallet<Self>,
+	#[pallet_index(16)]
+	recovery: recovery::Pallet<Self>,
+	#[pallet_index(17)]
+	preimage: preimage::Pallet<Self>,
+	/// A second token, held in a second instance of balances.
+	#[pallet_index(18)]
+	#[genesis]
+	tokens: balances::Pallet<Self, support::Instance1>,
 }
 
 impl system::Config for Runtime {
 	type AccountId = types::AccountId;
 	type BlockNumber = types::BlockNumber;
 	type Nonce = types::Nonce;
+	type RuntimeEvent = RuntimeEvent;
 }
 
 impl balances::Config for Runtime {
 	type Balance = types::Balance;
 }
 
+impl balances::Config<support::Instance1> for Runtime {
+	type Balance = types::Balance;
+}
+
+impl indices::Config for Runtime {
+	type AccountIndex = types::AccountIndex;
+	const DEPOSIT: Self::Balance = 10;
+}
+
 impl proof_of_existence::Config for Runtime {
 	type Content = types::Content;
 }
 
+impl multisig::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	const DEPOSIT: Self::Balance = 10;
+}
+
+/// The kinds of proxy in this runtime, and the calls they allow.
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, Hash, macros::Encode, macros::Decode, macros::TypeInfo,
+)]
+pub enum ProxyType {
+	Any,
+	Balances,
+	ProofOfExistence,
+}
+
+impl InstanceFilter<RuntimeCall> for ProxyType {
+	fn filter(&self, call: &RuntimeCall) -> bool {
+		match self {
+			ProxyType::Any => true,
+			ProxyType::Balances => matches!(call, RuntimeCall::balances(_)),
+			ProxyType::ProofOfExistence => matches!(call, RuntimeCall::proof_of_existence(_)),
+		}
+	}
+}
+
+impl proxy::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	type ProxyType = ProxyType;
+	const PROXY_DEPOSIT: Self::Balance = 5;
+	const ANNOUNCEMENT_DEPOSIT: Self::Balance = 1;
+}
+
+impl scheduler::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	const MAX_WEIGHT: support::Weight = 2 * support::CALL_WEIGHT;
+}
+
+impl timestamp::Config for Runtime {
+	type Moment = types::Moment;
+	const MINIMUM_PERIOD: Self::Moment = 3_000;
+}
+
+impl treasury::Config for Runtime {
+	type ApproveOrigin = EitherOf<EnsureRoot, EnsureProportionAtLeast<2, 3>>;
+	type RejectOrigin = EitherOf<EnsureRoot, EnsureProportionAtLeast<1, 2>>;
+	const PROPOSAL_BOND: Percent = Percent::from_percent(5);
+	const PROPOSAL_BOND_MINIMUM: Self::Balance = 1;
+	const SPEND_PERIOD: Self::BlockNumber = 4;
+	const BURN: Percent = Percent::from_percent(50);
+	const FEE_SHARE: Percent = Percent::from_percent(80);
+}
+
+impl democracy::Config for Runtime {
+	const MINIMUM_DEPOSIT: Self::Balance = 10;
+	const LAUNCH_PERIOD: Self::BlockNumber = 5;
+	const VOTING_PERIOD: Self::BlockNumber = 4;
+	const ENACTMENT_PERIOD: Self::BlockNumber = 2;
+	const VOTE_LOCKING_PERIOD: Self::BlockNumber = 3;
+}
+
+impl collective::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	const MOTION_DURATION: Self::BlockNumber = 3;
+	const MAX_MEMBERS: support::MemberCount = 100;
+}
+
+impl staking::Config for Runtime {
+	type SlashOrigin = EitherOf<EnsureRoot, EnsureProportionAtLeast<3, 4>>;
+	const ERA_LENGTH: Self::BlockNumber = 10;
+	const VALIDATOR_COUNT: u32 = 2;
+	const BONDING_DURATION: staking::EraIndex = 2;
+	const ERA_REWARD: Self::Balance = 100;
+}
+
+impl identity::Config for Runtime {
+	const BASIC_DEPOSIT: Self::Balance = 10;
+	const BYTE_DEPOSIT: Self::Balance = 1;
+	const SUB_ACCOUNT_DEPOSIT: Self::Balance = 2;
+	const MAX_SUB_ACCOUNTS: u32 = 10;
+}
+
+impl escrow::Config for Runtime {}
+
+impl assets::Config for Runtime {
+	type AssetId = types::AssetId;
+}
+
+impl dex::Config for Runtime {
+	const LP_FEE: Percent = Percent::from_percent(1);
+	const MIN_LIQUIDITY: Self::Balance = 10;
+}
+
+impl randomness::Config for Runtime {
+	const ROUND_LENGTH: Self::BlockNumber = 4;
+	const COMMIT_PERIOD: Self::BlockNumber = 2;
+	const DEPOSIT: Self::Balance = 10;
+}
+
+impl recovery::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	const CONFIG_DEPOSIT_BASE: Self::Balance = 10;
+	const FRIEND_DEPOSIT_FACTOR: Self::Balance = 1;
+	const RECOVERY_DEPOSIT: Self::Balance = 10;
+	const MAX_FRIENDS: u32 = 9;
+}
+
+impl preimage::Config for Runtime {
+	const BASE_DEPOSIT: Self::Balance = 10;
+	const BYTE_DEPOSIT: Self::Balance = 1;
+	const MAX_SIZE: u32 = 4096;
+}
+
+// Addresses are resolved through the indices pallet.
+impl Lookup for Runtime {
+	type Source = types::Address;
+	type Target = types::AccountId;
+
+	fn lookup(&self, address: types::Address) -> Result<types::AccountId, support::DispatchError> {
+		self.indices.lookup_address(address)
+	}
+}
+
+/// The fee paid by every signed extrinsic.
+const TRANSACTION_FEE: types::Balance = 5;
+
+impl ChargeTransaction<types::AccountId, RuntimeCall> for Runtime {
+	fn charge_transaction(
+		&mut self,
+		who: &types::AccountId,
+		_call: &RuntimeCall,
+	) -> support::DispatchResult {
+		treasury::Pallet::<Self>::charge_fee(self, who, TRANSACTION_FEE)
+	}
+}
+
 fn main() {
-	let mut runtime = Runtime::new();
+	// `cargo run -- metadata` prints the metadata of the runtime as JSON, for clients to generate
+	// bindings from, instead of running the demo blocks.
+	if std::env::args().nth(1).as_deref() == Some("metadata") {
+		println!("{}", Runtime::metadata().to_json());
+		return;
+	}
+
 	let alice = "alice".to_string();
 	let bob = "bob".to_string();
 	let charlie = "charlie".to_string();
 
-	runtime.balances.set_balance(alice, 100);
+	let mut runtime = Runtime::from_genesis(RuntimeGenesisConfig {
+		balances: balances::GenesisConfig { balances: vec![(alice.clone(), 200)] },
+		tokens: balances::GenesisConfig { balances: vec![(alice.clone(), 1_000)] },
+	});
 
 	let block_1 = types::Block {
 		header: support::Header { block_number: 1 },
 		extrinsics: vec![
 			support::Extrinsic {
-				caller: alice.clone(),
+				caller: None,
+				call: RuntimeCall::timestamp(timestamp::Call::set { now: 6_000 }),
+			},
+			support::Extrinsic {
+				caller: Some(alice.clone().into()),
+				call: RuntimeCall::balances(balances::Call::transfer {
+					to: bob.clone().into(),
+					amount: 20,
+				}),
+			},
+			support::Extrinsic {
+				caller: Some(alice.clone().into()),
 				call: RuntimeCall::balances(balances::Call::transfer {
-					to: bob.clone(),
+					to: charlie.clone().into(),
 					amount: 20,
 				}),
 			},
 			support::Extrinsic {
-				caller: alice.clone(),
-				call: RuntimeCall::balances(balances::Call::transfer { to: charlie, amount: 20 }),
+				caller: Some(alice.clone().into()),
+				call: RuntimeCall::tokens(balances::Call::transfer {
+					to: charlie.into(),
+					amount: 300,
+				}),
 			},
 		],
 	};
@@ -67,15 +308,19 @@ fn main() {
 		header: support::Header { block_number: 2 },
 		extrinsics: vec![
 			support::Extrinsic {
-				caller: alice.clone(),
+				caller: None,
+				call: RuntimeCall::timestamp(timestamp::Call::set { now: 12_000 }),
+			},
+			support::Extrinsic {
+				caller: Some(alice.clone().into()),
 				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-					claim: &"Hello, world!",
+					claim: "Hello, world!",
 				}),
 			},
 			support::Extrinsic {
-				caller: bob.clone(),
+				caller: Some(bob.clone().into()),
 				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-					claim: &"Hello, world!",
+					claim: "Hello, world!",
 				}),
 			},
 		],
@@ -85,22 +330,53 @@ fn main() {
 		header: support::Header { block_number: 3 },
 		extrinsics: vec![
 			support::Extrinsic {
-				caller: alice,
+				caller: None,
+				call: RuntimeCall::timestamp(timestamp::Call::set { now: 18_000 }),
+			},
+			support::Extrinsic {
+				caller: Some(alice.clone().into()),
 				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim {
-					claim: &"Hello, world!",
+					claim: "Hello, world!",
 				}),
 			},
 			support::Extrinsic {
-				caller: bob,
+				caller: Some(bob.clone().into()),
 				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-					claim: &"Hello, world!",
+					claim: "Hello, world!",
+				}),
+			},
+			// Store a call by its hash, so that it can be referred to later.
+			support::Extrinsic {
+				caller: Some(alice.into()),
+				call: RuntimeCall::preimage(preimage::Call::note_preimage {
+					bytes: RuntimeCall::balances(balances::Call::transfer {
+						to: bob.into(),
+						amount: 10,
+					})
+					.encode(),
 				}),
 			},
 		],
 	};
 
-	runtime.execute_block(block_1).expect("invalid block");
-	runtime.execute_block(block_2).expect("invalid block");
-	runtime.execute_block(block_3).expect("invalid block");
+	for block in [block_1, block_2, block_3] {
+		let report = runtime.execute_block(block).expect("invalid block");
+		for (index, error) in report.failed {
+			eprintln!(
+				"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tError: {}",
+				runtime.system.block_number(),
+				index,
+				error
+			);
+		}
+		for (pallet, error) in report.initialize_failed.into_iter().chain(report.finalize_failed) {
+			eprintln!(
+				"Hook Error\n\tBlock Number: {}\n\tPallet: {}\n\tError: {}",
+				runtime.system.block_number(),
+				pallet,
+				error
+			);
+		}
+	}
 	println!("{:#?}", runtime);
 }
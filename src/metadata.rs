@@ -0,0 +1,499 @@
+//! A description of the runtime, so that clients written in other languages can discover its
+//! pallets, and generate bindings for their calls, events, errors, storage items and constants.
+//!
+//! `#[macros::runtime]` generates `Runtime::metadata()`, from the metadata generated for each
+//! pallet by `#[macros::call]`, `#[macros::event]`, `#[macros::error]`, `#[macros::storage]` and
+//! `#[macros::config]`. Types are described by their structure, in a registry of types: every type
+//! is a composite, a variant, a sequence, a tuple or a primitive, and refers to the types it is
+//! made of by their index in the registry. Types implement `TypeInfo` to describe themselves, and
+//! `#[derive(macros::TypeInfo)]` implements it for structs and enums.
+//!
+//! The metadata is written as JSON with `ToJson`, for example by running `cargo run -- metadata`.
+
+/// The metadata of a runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeMetadata {
+	/// The types referred to by the rest of the metadata, by index.
+	pub types: Vec<TypeMetadata>,
+	/// The pallets of the runtime, in the order of the `Runtime` struct, starting with system.
+	pub pallets: Vec<PalletMetadata>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PalletMetadata {
+	/// The name of the field of the pallet in the `Runtime` struct.
+	pub name: &'static str,
+	/// The index of the pallet in the encoding of a `RuntimeCall`. System has no index, since it
+	/// is not callable.
+	pub index: Option<u8>,
+	pub calls: Vec<VariantMetadata>,
+	pub events: Vec<VariantMetadata>,
+	pub errors: Vec<VariantMetadata>,
+	pub storage: Vec<StorageMetadata>,
+	pub constants: Vec<ConstantMetadata>,
+}
+
+/// The metadata of a variant of a `Call`, an `Event` or an `Error` enum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantMetadata {
+	pub name: &'static str,
+	/// The index of the variant in its encoding.
+	pub index: u8,
+	/// The fields of the variant, which are the arguments of a call.
+	pub fields: Vec<FieldMetadata>,
+	/// The doc comment of the variant, or of the function of a call.
+	pub docs: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMetadata {
+	/// The name of the field, or `None` for the fields of a tuple variant.
+	pub name: Option<&'static str>,
+	pub ty: TypeIndex,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageMetadata {
+	pub name: &'static str,
+	pub kind: StorageKind,
+	/// The hasher of each key: `Identity`, `Hash64` or `Hash64Concat`, see `storage`.
+	pub hashers: Vec<&'static str>,
+	pub keys: Vec<TypeIndex>,
+	pub value: TypeIndex,
+	/// Whether reading the item gives `None` when it is empty, rather than a default value.
+	pub optional: bool,
+	pub docs: &'static str,
+}
+
+/// The kinds of storage items, named after their types in the `storage` module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+	Value,
+	Map,
+	DoubleMap,
+}
+
+/// The metadata of a constant of the config of a pallet, with its value in the runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstantMetadata {
+	pub name: &'static str,
+	pub ty: TypeIndex,
+	/// The value of the constant, formatted with `Debug`.
+	pub value: String,
+	pub docs: &'static str,
+}
+
+/// The index of a type in the registry of a `RuntimeMetadata`.
+pub type TypeIndex = u32;
+
+/// The metadata of a type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeMetadata {
+	/// The path of the type, like `rust_state_machine::support::MultiAddress`, or empty for the
+	/// types built into the language.
+	pub path: &'static str,
+	pub def: TypeDef,
+}
+
+/// The structure of a type, which tells how it is encoded. See `codec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeDef {
+	/// A struct, encoded as its fields in order.
+	Composite(Vec<FieldMetadata>),
+	/// An enum, encoded as the index of its variant, followed by the fields of the variant.
+	Variant(Vec<VariantMetadata>),
+	/// A `Vec`, encoded as its length as a `u32`, followed by its items.
+	Sequence(TypeIndex),
+	/// An array, encoded as its items in order.
+	Array(ArrayDef),
+	/// A tuple, encoded as its items in order.
+	Tuple(Vec<TypeIndex>),
+	Primitive(Primitive),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrayDef {
+	/// The number of items of the array.
+	pub len: u32,
+	pub ty: TypeIndex,
+}
+
+/// The types which are not made of other types. Integers are encoded in little endian, `Bool` as
+/// a `u8`, and `Str` as its length as a `u32`, followed by its UTF-8 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+	Bool,
+	Str,
+	U8,
+	U16,
+	U32,
+	U64,
+	U128,
+}
+
+/// Types which can be described in the metadata.
+pub trait TypeInfo: 'static {
+	/// Describe the type, registering the types it is made of.
+	fn type_info(registry: &mut TypeRegistry) -> TypeMetadata;
+
+	/// The index of the type in `registry`, which is registered if it is not yet. Transparent
+	/// types, like `Box<T>`, give the index of the type they wrap instead.
+	fn type_index(registry: &mut TypeRegistry) -> TypeIndex {
+		registry.insert::<Self>()
+	}
+}
+
+/// The types described so far, each registered once, by index.
+#[derive(Debug, Default)]
+pub struct TypeRegistry {
+	types: Vec<TypeMetadata>,
+	indices: std::collections::HashMap<core::any::TypeId, TypeIndex>,
+}
+
+impl TypeRegistry {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The index of `T`, registering it and the types it is made of if they are not yet.
+	pub fn register<T: TypeInfo>(&mut self) -> TypeIndex {
+		T::type_index(self)
+	}
+
+	fn insert<T: TypeInfo + ?Sized>(&mut self) -> TypeIndex {
+		let id = core::any::TypeId::of::<T>();
+		if let Some(index) = self.indices.get(&id) {
+			return *index;
+		}
+		// The index is taken before the type is described, so that recursive types, like calls
+		// which hold other calls, refer to themselves rather than registering themselves forever.
+		let index = TypeIndex::try_from(self.types.len()).expect("at most u32::MAX types; qed");
+		self.indices.insert(id, index);
+		self.types.push(TypeMetadata { path: "", def: TypeDef::Tuple(vec![]) });
+		let metadata = T::type_info(self);
+		self.types[index as usize] = metadata;
+		index
+	}
+
+	/// The types registered so far, by index.
+	pub fn into_types(self) -> Vec<TypeMetadata> {
+		self.types
+	}
+}
+
+macro_rules! impl_primitive {
+	($( $ty:ty => $primitive:ident ),* $(,)?) => {
+		$(
+			impl TypeInfo for $ty {
+				fn type_info(_registry: &mut TypeRegistry) -> TypeMetadata {
+					TypeMetadata { path: "", def: TypeDef::Primitive(Primitive::$primitive) }
+				}
+			}
+		)*
+	};
+}
+
+impl_primitive!(
+	bool => Bool,
+	String => Str,
+	&'static str => Str,
+	u8 => U8,
+	u16 => U16,
+	u32 => U32,
+	u64 => U64,
+	u128 => U128,
+);
+
+impl<T: TypeInfo> TypeInfo for Vec<T> {
+	fn type_info(registry: &mut TypeRegistry) -> TypeMetadata {
+		TypeMetadata { path: "", def: TypeDef::Sequence(registry.register::<T>()) }
+	}
+}
+
+/// Maps are described as sequences of their entries, in the order of their keys.
+impl<K: TypeInfo, V: TypeInfo> TypeInfo for std::collections::BTreeMap<K, V> {
+	fn type_info(registry: &mut TypeRegistry) -> TypeMetadata {
+		TypeMetadata { path: "", def: TypeDef::Sequence(registry.register::<(K, V)>()) }
+	}
+}
+
+impl<T: TypeInfo> TypeInfo for Option<T> {
+	fn type_info(registry: &mut TypeRegistry) -> TypeMetadata {
+		let some = FieldMetadata { name: None, ty: registry.register::<T>() };
+		let variant = |name, index, fields| VariantMetadata { name, index, fields, docs: "" };
+		TypeMetadata {
+			path: "core::option::Option",
+			def: TypeDef::Variant(vec![variant("None", 0, vec![]), variant("Some", 1, vec![some])]),
+		}
+	}
+}
+
+impl<T: TypeInfo> TypeInfo for Box<T> {
+	fn type_info(registry: &mut TypeRegistry) -> TypeMetadata {
+		T::type_info(registry)
+	}
+
+	fn type_index(registry: &mut TypeRegistry) -> TypeIndex {
+		registry.register::<T>()
+	}
+}
+
+impl<T: TypeInfo, const N: usize> TypeInfo for [T; N] {
+	fn type_info(registry: &mut TypeRegistry) -> TypeMetadata {
+		let len = u32::try_from(N).expect("arrays of the runtime are small; qed");
+		TypeMetadata {
+			path: "",
+			def: TypeDef::Array(ArrayDef { len, ty: registry.register::<T>() }),
+		}
+	}
+}
+
+impl TypeInfo for () {
+	fn type_info(_registry: &mut TypeRegistry) -> TypeMetadata {
+		TypeMetadata { path: "", def: TypeDef::Tuple(vec![]) }
+	}
+}
+
+impl<A: TypeInfo, B: TypeInfo> TypeInfo for (A, B) {
+	fn type_info(registry: &mut TypeRegistry) -> TypeMetadata {
+		let items = vec![registry.register::<A>(), registry.register::<B>()];
+		TypeMetadata { path: "", def: TypeDef::Tuple(items) }
+	}
+}
+
+/// Types which can be written as JSON.
+pub trait ToJson {
+	/// Append the JSON of `self` to `out`.
+	fn write_json(&self, out: &mut String);
+
+	fn to_json(&self) -> String {
+		let mut out = String::new();
+		self.write_json(&mut out);
+		out
+	}
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+	fn write_json(&self, out: &mut String) {
+		(**self).write_json(out)
+	}
+}
+
+impl ToJson for str {
+	fn write_json(&self, out: &mut String) {
+		out.push('"');
+		for c in self.chars() {
+			match c {
+				'"' => out.push_str("\\\""),
+				'\\' => out.push_str("\\\\"),
+				'\n' => out.push_str("\\n"),
+				'\r' => out.push_str("\\r"),
+				'\t' => out.push_str("\\t"),
+				c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+				c => out.push(c),
+			}
+		}
+		out.push('"');
+	}
+}
+
+impl ToJson for String {
+	fn write_json(&self, out: &mut String) {
+		self.as_str().write_json(out)
+	}
+}
+
+impl ToJson for u8 {
+	fn write_json(&self, out: &mut String) {
+		out.push_str(&self.to_string())
+	}
+}
+
+impl ToJson for u32 {
+	fn write_json(&self, out: &mut String) {
+		out.push_str(&self.to_string())
+	}
+}
+
+impl ToJson for bool {
+	fn write_json(&self, out: &mut String) {
+		out.push_str(if *self { "true" } else { "false" })
+	}
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+	fn write_json(&self, out: &mut String) {
+		match self {
+			Some(value) => value.write_json(out),
+			None => out.push_str("null"),
+		}
+	}
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+	fn write_json(&self, out: &mut String) {
+		out.push('[');
+		for (i, item) in self.iter().enumerate() {
+			if i > 0 {
+				out.push(',');
+			}
+			item.write_json(out);
+		}
+		out.push(']');
+	}
+}
+
+impl ToJson for StorageKind {
+	fn write_json(&self, out: &mut String) {
+		format!("{:?}", self).write_json(out)
+	}
+}
+
+impl ToJson for Primitive {
+	fn write_json(&self, out: &mut String) {
+		format!("{:?}", self).write_json(out)
+	}
+}
+
+/// Type definitions are written as an object with a single key, the kind of the definition.
+impl ToJson for TypeDef {
+	fn write_json(&self, out: &mut String) {
+		let (kind, value): (&str, &dyn ToJson) = match self {
+			TypeDef::Composite(fields) => ("Composite", fields),
+			TypeDef::Variant(variants) => ("Variant", variants),
+			TypeDef::Sequence(ty) => ("Sequence", ty),
+			TypeDef::Array(array) => ("Array", array),
+			TypeDef::Tuple(items) => ("Tuple", items),
+			TypeDef::Primitive(primitive) => ("Primitive", primitive),
+		};
+		out.push('{');
+		kind.write_json(out);
+		out.push(':');
+		value.write_json(out);
+		out.push('}');
+	}
+}
+
+/// Implement `ToJson` for a struct, as an object with the given fields as keys.
+macro_rules! impl_to_json {
+	($name:ident { $( $field:ident ),* $(,)? }) => {
+		impl ToJson for $name {
+			fn write_json(&self, out: &mut String) {
+				let fields: &[(&str, &dyn ToJson)] = &[ $( (stringify!($field), &self.$field) ),* ];
+				out.push('{');
+				for (i, (name, value)) in fields.iter().enumerate() {
+					if i > 0 {
+						out.push(',');
+					}
+					name.write_json(out);
+					out.push(':');
+					value.write_json(out);
+				}
+				out.push('}');
+			}
+		}
+	};
+}
+
+impl_to_json!(RuntimeMetadata { types, pallets });
+impl_to_json!(TypeMetadata { path, def });
+impl_to_json!(ArrayDef { len, ty });
+impl_to_json!(PalletMetadata { name, index, calls, events, errors, storage, constants });
+impl_to_json!(VariantMetadata { name, index, fields, docs });
+impl_to_json!(FieldMetadata { name, ty });
+impl_to_json!(StorageMetadata { name, kind, hashers, keys, value, optional, docs });
+impl_to_json!(ConstantMetadata { name, ty, value, docs });
+
+#[cfg(test)]
+mod tests {
+	use super::{FieldMetadata, Primitive, StorageKind, ToJson, TypeDef, TypeRegistry};
+	use crate::Runtime;
+
+	#[test]
+	fn writes_json() {
+		assert_eq!("say \"hi\"\n".to_json(), r#""say \"hi\"\n""#);
+		assert_eq!(vec![Some(1u8), None].to_json(), "[1,null]");
+		assert_eq!(StorageKind::DoubleMap.to_json(), r#""DoubleMap""#);
+		let field = FieldMetadata { name: Some("index"), ty: 3 };
+		assert_eq!(field.to_json(), r#"{"name":"index","ty":3}"#);
+		assert_eq!(TypeDef::Sequence(3).to_json(), r#"{"Sequence":3}"#);
+		assert_eq!(TypeDef::Primitive(Primitive::U8).to_json(), r#"{"Primitive":"U8"}"#);
+	}
+
+	#[test]
+	fn registers_each_type_once() {
+		let mut registry = TypeRegistry::new();
+		let pair = registry.register::<(u32, Vec<u32>)>();
+		assert_eq!(registry.register::<Box<(u32, Vec<u32>)>>(), pair);
+		assert_eq!(registry.register::<u32>(), 1);
+
+		let types = registry.into_types();
+		assert_eq!(types[0].def, TypeDef::Tuple(vec![1, 2]));
+		assert_eq!(types[1].def, TypeDef::Primitive(Primitive::U32));
+		assert_eq!(types[2].def, TypeDef::Sequence(1));
+		assert_eq!(types.len(), 3);
+	}
+
+	#[test]
+	fn describes_the_runtime() {
+		let metadata = Runtime::metadata();
+		let names = metadata.pallets.iter().map(|pallet| pallet.name).collect::<Vec<_>>();
+		assert_eq!(names[..3], ["system", "balances", "indices"]);
+		let ty = |index| &metadata.types[index as usize];
+
+		let system = &metadata.pallets[0];
+		assert_eq!(system.index, None);
+		assert!(system.calls.is_empty());
+		assert!(system.storage.iter().any(|item| item.name == "events"));
+
+		let indices = &metadata.pallets[2];
+		assert_eq!(indices.index, Some(1));
+		let claim = &indices.calls[0];
+		assert_eq!((claim.name, claim.index), ("claim", 0));
+		assert_eq!(claim.fields[0].name, Some("index"));
+		assert_eq!(ty(claim.fields[0].ty).def, TypeDef::Primitive(Primitive::U32));
+		assert_eq!(claim.docs, "Claim an unused index for the caller, reserving the deposit.");
+		// Accounts are given as addresses, which are resolved when the call is dispatched.
+		let address = ty(indices.calls[1].fields[0].ty);
+		assert_eq!(address.path, "rust_state_machine::support::MultiAddress");
+		let TypeDef::Variant(variants) = &address.def else { panic!("addresses are enums") };
+		let variants = variants.iter().map(|variant| variant.name).collect::<Vec<_>>();
+		assert_eq!(variants, ["Id", "Index"]);
+
+		let events = indices.events.iter().map(|event| event.name).collect::<Vec<_>>();
+		assert_eq!(events, ["IndexAssigned", "IndexFreed"]);
+		assert_eq!(indices.errors[1].name, "NotOwner");
+		assert_eq!(indices.errors[1].docs, "The index is claimed by another account.");
+
+		let accounts = &indices.storage[0];
+		assert_eq!((accounts.name, accounts.kind), ("accounts", StorageKind::Map));
+		assert_eq!(accounts.hashers, ["Hash64Concat"]);
+		assert_eq!(ty(accounts.keys[0]).def, TypeDef::Primitive(Primitive::U32));
+		assert!(accounts.optional);
+
+		let deposit = &indices.constants[0];
+		assert_eq!((deposit.name, deposit.value.as_str()), ("DEPOSIT", "10"));
+		assert_eq!(ty(deposit.ty).def, TypeDef::Primitive(Primitive::U128));
+		assert_eq!(deposit.docs, "The amount reserved for each claimed index.");
+
+		// Calls which hold other calls refer to `RuntimeCall` by its index, which describes the
+		// calls of every pallet.
+		let runtime_call = metadata.types.iter().position(|ty| ty.path.ends_with("::RuntimeCall"));
+		let TypeDef::Variant(pallets) = &metadata.types[runtime_call.unwrap()].def else {
+			panic!("calls are enums")
+		};
+		assert_eq!(pallets.len(), metadata.pallets.len() - 1);
+
+		// Events are indexed by pallet too, like calls.
+		let runtime_event =
+			metadata.types.iter().position(|ty| ty.path.ends_with("::RuntimeEvent"));
+		let TypeDef::Variant(pallets) = &metadata.types[runtime_event.unwrap()].def else {
+			panic!("events are enums")
+		};
+		assert_eq!((pallets[0].name, pallets[0].index), ("indices", 1));
+
+		// Pallets without events or errors describe none.
+		assert!(metadata.pallets[1].events.is_empty());
+		assert!(metadata.to_json().starts_with(r#"{"types":[{"path":"#));
+	}
+}
@@ -0,0 +1,444 @@
+use crate::{
+	storage::{Hash64Concat, StorageDoubleMap},
+	support::{self, Dispatch, DispatchResult, FromHash, GetPallet, Hash, RawOrigin},
+};
+
+#[macros::config]
+pub trait Config:
+	Sized
+	+ crate::system::Config<AccountId: FromHash + std::hash::Hash>
+	+ crate::balances::Config
+	+ GetPallet<crate::system::Pallet<Self>>
+	+ GetPallet<crate::balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+	+ Dispatch<Caller = crate::system::Origin<Self>, Call = <Self as Config>::RuntimeCall>
+{
+	/// The overarching call type, which multisig accounts dispatch once enough signatories
+	/// approved it.
+	type RuntimeCall: std::hash::Hash;
+	/// The amount reserved from the first approver of a multisig operation, and returned once the
+	/// operation is executed or cancelled.
+	const DEPOSIT: Self::Balance;
+}
+
+/// A point in the chain: the block number and the index of an extrinsic in that block.
+///
+/// This tells apart multisig operations which approve the same call at different times.
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, Hash, macros::Encode, macros::Decode, macros::TypeInfo,
+)]
+pub struct Timepoint<BlockNumber> {
+	pub height: BlockNumber,
+	pub index: u32,
+}
+
+/// An open multisig operation, waiting for enough approvals.
+#[derive(Debug, macros::TypeInfo)]
+pub struct Multisig<T: Config> {
+	/// When the operation was opened.
+	pub when: Timepoint<T::BlockNumber>,
+	/// The amount reserved from the depositor.
+	pub deposit: T::Balance,
+	/// The first approver, who paid the deposit and may cancel the operation.
+	pub depositor: T::AccountId,
+	/// The sorted list of signatories who approved the operation so far.
+	pub approvals: Vec<T::AccountId>,
+}
+
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	/// Open operations, indexed by the multisig account and the hash of the call.
+	multisigs: StorageDoubleMap<Hash64Concat, T::AccountId, Hash64Concat, Hash, Multisig<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn multisig(&self, id: &T::AccountId, call_hash: Hash) -> Option<&Multisig<T>> {
+		self.multisigs.get_ref(id, &call_hash)
+	}
+
+	/// Derive the account id of a multisig from its signatories and threshold. The signatories
+	/// are sorted first, so their order does not matter.
+	pub fn multi_account_id(signatories: &[T::AccountId], threshold: u16) -> T::AccountId {
+		let mut signatories = signatories.to_vec();
+		signatories.sort();
+		T::AccountId::from_hash(support::hash_of(&("multisig", signatories, threshold)))
+	}
+
+	/// The timepoint of the extrinsic which is currently executing.
+	pub fn timepoint(runtime: &T) -> Timepoint<T::BlockNumber> {
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		Timepoint { height: system.block_number(), index: system.extrinsic_index() }
+	}
+
+	/// Add `who` to `other_signatories`, and return the sorted list of all signatories.
+	fn signatories(
+		who: &T::AccountId,
+		other_signatories: Vec<T::AccountId>,
+	) -> Result<Vec<T::AccountId>, String> {
+		let mut signatories = other_signatories;
+		signatories.push(who.clone());
+		signatories.sort();
+
+		if signatories.windows(2).any(|pair| pair[0] == pair[1]) {
+			return Err("duplicate signatories".to_string());
+		}
+
+		Ok(signatories)
+	}
+
+	/// The shared logic of `as_multi` and `approve_as_multi`. The call is only dispatched when it
+	/// is given, which is never the case for `approve_as_multi`.
+	fn operate(
+		runtime: &mut T,
+		who: T::AccountId,
+		threshold: u16,
+		other_signatories: Vec<T::AccountId>,
+		maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+		call_hash: Hash,
+		maybe_call: Option<<T as Config>::RuntimeCall>,
+	) -> DispatchResult {
+		if threshold < 2 {
+			return Err("threshold must be at least 2".into());
+		}
+		let signatories = Self::signatories(&who, other_signatories)?;
+		if signatories.len() < threshold as usize {
+			return Err("threshold is higher than the number of signatories".into());
+		}
+
+		let id = Self::multi_account_id(&signatories, threshold);
+		let now = Self::timepoint(runtime);
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		if let Some(multisig) = pallet.multisigs.get_mut(&id, &call_hash) {
+			let timepoint = maybe_timepoint.ok_or("timepoint of the operation is required")?;
+			if timepoint != multisig.when {
+				return Err("timepoint does not match the operation".into());
+			}
+
+			let approved = multisig.approvals.contains(&who);
+			let approvals = multisig.approvals.len() + if approved { 0 } else { 1 };
+
+			if maybe_call.is_none() || approvals < threshold as usize {
+				// Not ready to dispatch, so we only record the approval.
+				let position =
+					multisig.approvals.binary_search(&who).err().ok_or("already approved")?;
+				multisig.approvals.insert(position, who);
+				return Ok(());
+			}
+
+			// The threshold is reached: dispatch the call from the multisig account, and close the
+			// operation once it succeeded. If the call fails, the operation stays open, so that it
+			// can be executed later.
+			let call = maybe_call.expect("checked above; qed");
+			runtime.dispatch(RawOrigin::Signed(id.clone()), call)?;
+
+			let pallet: &mut Self = runtime.pallet_mut();
+			let multisig = pallet.multisigs.take(&id, &call_hash).expect("checked above; qed");
+			let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+			balances.unreserve(&multisig.depositor, multisig.deposit)
+		} else {
+			if maybe_timepoint.is_some() {
+				return Err("unexpected timepoint for a new operation".into());
+			}
+
+			let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+			balances.reserve(&who, T::DEPOSIT)?;
+
+			let pallet: &mut Self = runtime.pallet_mut();
+			let multisig = Multisig {
+				when: now,
+				deposit: T::DEPOSIT,
+				depositor: who.clone(),
+				approvals: vec![who],
+			};
+			pallet.multisigs.insert(id, call_hash, multisig);
+			Ok(())
+		}
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Approve a call and dispatch it from the multisig account if the threshold is reached.
+	///
+	/// The first approval opens the operation and reserves the deposit. Later approvals must pass
+	/// the timepoint at which the operation was opened.
+	#[call_index(0)]
+	pub fn as_multi(
+		runtime: &mut T,
+		caller: T::AccountId,
+		threshold: u16,
+		other_signatories: Vec<T::AccountId>,
+		maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+		call: Box<<T as Config>::RuntimeCall>,
+	) -> DispatchResult {
+		let call_hash = support::hash_of(&call);
+		Self::operate(
+			runtime,
+			caller,
+			threshold,
+			other_signatories,
+			maybe_timepoint,
+			call_hash,
+			Some(*call),
+		)
+	}
+
+	/// Approve a call by its hash, without dispatching it.
+	#[call_index(1)]
+	pub fn approve_as_multi(
+		runtime: &mut T,
+		caller: T::AccountId,
+		threshold: u16,
+		other_signatories: Vec<T::AccountId>,
+		maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+		call_hash: Hash,
+	) -> DispatchResult {
+		Self::operate(
+			runtime,
+			caller,
+			threshold,
+			other_signatories,
+			maybe_timepoint,
+			call_hash,
+			None,
+		)
+	}
+
+	/// Cancel an open operation. Only the depositor can cancel, and gets the deposit back.
+	#[call_index(2)]
+	pub fn cancel_as_multi(
+		runtime: &mut T,
+		caller: T::AccountId,
+		threshold: u16,
+		other_signatories: Vec<T::AccountId>,
+		timepoint: Timepoint<T::BlockNumber>,
+		call_hash: Hash,
+	) -> DispatchResult {
+		let signatories = Self::signatories(&caller, other_signatories)?;
+		let id = Self::multi_account_id(&signatories, threshold);
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let multisig =
+			pallet.multisigs.get_ref(&id, &call_hash).ok_or("operation does not exist")?;
+		if multisig.when != timepoint {
+			return Err("timepoint does not match the operation".into());
+		}
+		if multisig.depositor != caller {
+			return Err("only the depositor can cancel the operation".into());
+		}
+
+		let multisig = pallet.multisigs.take(&id, &call_hash).expect("checked above; qed");
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.unreserve(&multisig.depositor, multisig.deposit)
+	}
+}
+
+impl<T: Config> crate::support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> crate::support::Hooks<T> for Pallet<T> {}
+
+#[cfg(test)]
+mod tests {
+	use super::Timepoint;
+	use crate::{support, test_helpers::transfer, Runtime};
+
+	type Multisig = super::Pallet<Runtime>;
+
+	#[test]
+	fn multi_account_id_is_deterministic() {
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+
+		let id = Multisig::multi_account_id(&[alice.clone(), bob.clone(), charlie.clone()], 2);
+		assert_eq!(
+			id,
+			Multisig::multi_account_id(&[charlie.clone(), alice.clone(), bob.clone()], 2)
+		);
+		assert_ne!(
+			id,
+			Multisig::multi_account_id(&[alice.clone(), bob.clone(), charlie.clone()], 3)
+		);
+		assert_ne!(id, Multisig::multi_account_id(&[alice.clone(), bob.clone()], 2));
+
+		// The account id is part of the state, so it must not depend on the platform or the
+		// release of Rust.
+		assert_eq!(Multisig::multi_account_id(&[bob, alice], 2), "b0b4cb3695d26b55");
+	}
+
+	#[test]
+	fn two_of_three_multisig() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		let id = Multisig::multi_account_id(&[alice.clone(), bob.clone(), charlie.clone()], 2);
+
+		runtime.balances.set_balance(alice.clone(), 100);
+		runtime.balances.set_balance(id.clone(), 50);
+
+		let call = transfer("dave", 30);
+		let call_hash = support::hash_of(&call);
+
+		// Alice opens the operation by approving the hash, and pays the deposit.
+		assert_eq!(
+			Multisig::approve_as_multi(
+				&mut runtime,
+				alice.clone(),
+				2,
+				vec![bob.clone(), charlie.clone()],
+				None,
+				call_hash
+			),
+			Ok(())
+		);
+		let when = Timepoint { height: 0, index: 0 };
+		assert_eq!(runtime.multisig.multisig(&id, call_hash).unwrap().when, when);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 10);
+
+		// Alice cannot approve twice.
+		assert_eq!(
+			Multisig::approve_as_multi(
+				&mut runtime,
+				alice.clone(),
+				2,
+				vec![bob.clone(), charlie.clone()],
+				Some(when),
+				call_hash
+			),
+			Err("already approved".into())
+		);
+
+		// Bob must refer to the operation with the right timepoint.
+		assert_eq!(
+			Multisig::as_multi(
+				&mut runtime,
+				bob.clone(),
+				2,
+				vec![alice.clone(), charlie.clone()],
+				Some(Timepoint { height: 0, index: 1 }),
+				transfer("dave", 30),
+			),
+			Err("timepoint does not match the operation".into())
+		);
+
+		// Bob reaches the threshold, which dispatches the call from the multisig account.
+		assert_eq!(
+			Multisig::as_multi(
+				&mut runtime,
+				bob,
+				2,
+				vec![alice.clone(), charlie],
+				Some(when),
+				call
+			),
+			Ok(())
+		);
+		assert!(runtime.multisig.multisig(&id, call_hash).is_none());
+		assert_eq!(runtime.balances.balance(&id), 20);
+		assert_eq!(runtime.balances.balance(&"dave".to_string()), 30);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+	}
+
+	#[test]
+	fn failed_call_keeps_the_operation_open() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let id = Multisig::multi_account_id(&[alice.clone(), bob.clone()], 2);
+		let when = Timepoint { height: 0, index: 0 };
+
+		runtime.balances.set_balance(alice.clone(), 100);
+		assert_eq!(
+			Multisig::as_multi(
+				&mut runtime,
+				alice.clone(),
+				2,
+				vec![bob.clone()],
+				None,
+				transfer("dave", 30)
+			),
+			Ok(())
+		);
+
+		// The multisig account cannot pay yet, so the operation and the deposit are kept.
+		let call_hash = support::hash_of(&transfer("dave", 30));
+		assert_eq!(
+			Multisig::as_multi(
+				&mut runtime,
+				bob.clone(),
+				2,
+				vec![alice.clone()],
+				Some(when),
+				transfer("dave", 30)
+			),
+			Err("Not enough funds.".into())
+		);
+		assert_eq!(
+			runtime.multisig.multisig(&id, call_hash).unwrap().approvals,
+			vec![alice.clone()]
+		);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 10);
+
+		// Once it can, the call goes through.
+		runtime.balances.set_balance(id.clone(), 50);
+		assert_eq!(
+			Multisig::as_multi(
+				&mut runtime,
+				bob,
+				2,
+				vec![alice.clone()],
+				Some(when),
+				transfer("dave", 30)
+			),
+			Ok(())
+		);
+		assert!(runtime.multisig.multisig(&id, call_hash).is_none());
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&"dave".to_string()), 30);
+	}
+
+	#[test]
+	fn cancel_multisig() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let call_hash = support::hash_of(&transfer("dave", 30));
+		let when = Timepoint { height: 0, index: 0 };
+
+		runtime.balances.set_balance(alice.clone(), 100);
+		assert_eq!(
+			Multisig::approve_as_multi(
+				&mut runtime,
+				alice.clone(),
+				2,
+				vec![bob.clone()],
+				None,
+				call_hash
+			),
+			Ok(())
+		);
+
+		// Only the depositor can cancel.
+		assert_eq!(
+			Multisig::cancel_as_multi(&mut runtime, bob, 2, vec![alice.clone()], when, call_hash),
+			Err("only the depositor can cancel the operation".into())
+		);
+		assert_eq!(
+			Multisig::cancel_as_multi(
+				&mut runtime,
+				alice.clone(),
+				2,
+				vec!["bob".to_string()],
+				when,
+				call_hash
+			),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+	}
+}
@@ -0,0 +1,263 @@
+use crate::{
+	codec::Decode,
+	storage::{Identity, StorageMap},
+	support::{self, DispatchResult, GetPallet, Hash},
+	system::Origin,
+};
+use num::{CheckedAdd, CheckedMul};
+
+#[macros::config]
+pub trait Config:
+	Sized
+	+ crate::balances::Config<Balance: From<u32> + CheckedMul>
+	+ GetPallet<crate::balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The amount reserved for each preimage noted without being requested.
+	const BASE_DEPOSIT: Self::Balance;
+	/// The amount reserved for each byte of a preimage noted without being requested.
+	const BYTE_DEPOSIT: Self::Balance;
+	/// The maximum length of a preimage, in bytes.
+	const MAX_SIZE: u32;
+}
+
+/// Who keeps a preimage alive.
+#[derive(Debug, Clone, PartialEq, Eq, macros::TypeInfo)]
+pub struct RequestStatus<AccountId, Balance> {
+	/// The account which noted the preimage and reserved a deposit for it, if any. Preimages
+	/// which are requested can be noted for free.
+	pub deposit: Option<(AccountId, Balance)>,
+	/// The number of requests for the preimage.
+	pub count: u32,
+	/// The length of the preimage, if it is noted.
+	pub len: Option<u32>,
+}
+
+/// Large values, like calls, stored as bytes by their hash. Other pallets can then refer to them by
+/// hash, and request them so they are kept alive.
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	/// Hashes are already uniformly distributed, so they are used as keys as is.
+	status: StorageMap<Identity, Hash, RequestStatus<T::AccountId, T::Balance>>,
+	preimages: StorageMap<Identity, Hash, Vec<u8>>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn status(&self, hash: &Hash) -> Option<&RequestStatus<T::AccountId, T::Balance>> {
+		self.status.get_ref(hash)
+	}
+
+	/// The preimage of `hash`, if it is noted.
+	pub fn fetch(&self, hash: &Hash) -> Option<&[u8]> {
+		self.preimages.get_ref(hash).map(|bytes| bytes.as_slice())
+	}
+
+	/// Decode the preimage of `hash`, for example into a `RuntimeCall`.
+	pub fn realize<V: Decode>(&self, hash: &Hash) -> Result<V, String> {
+		let bytes = self.fetch(hash).ok_or("preimage is not noted")?;
+		V::decode_all(bytes)
+	}
+
+	/// Request the preimage of `hash`, so that it is kept until it is unrequested. It can be
+	/// requested before it is noted, and then be noted for free.
+	pub fn request(&mut self, hash: Hash) {
+		self.status.mutate(&hash, |status| {
+			let status = status.get_or_insert(RequestStatus { deposit: None, count: 0, len: None });
+			status.count = status.count.saturating_add(1);
+		});
+	}
+
+	/// Remove a request for the preimage of `hash`. The preimage is removed with the last request,
+	/// unless it was noted with a deposit.
+	pub fn unrequest(&mut self, hash: &Hash) -> DispatchResult {
+		let status = self.status.get_mut(hash).ok_or("preimage is not requested")?;
+		if status.count == 0 {
+			return Err("preimage is not requested".into());
+		}
+		status.count -= 1;
+		if status.count == 0 && status.deposit.is_none() {
+			self.status.remove(hash);
+			self.preimages.remove(hash);
+		}
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Store `bytes` by their hash. Unless the preimage is requested, the caller reserves a deposit
+	/// for it, which grows with its length.
+	#[call_index(0)]
+	pub fn note_preimage(runtime: &mut T, caller: T::AccountId, bytes: Vec<u8>) -> DispatchResult {
+		let len = u32::try_from(bytes.len()).map_err(|_| "preimage is too big")?;
+		if len > T::MAX_SIZE {
+			return Err("preimage is too big".into());
+		}
+		let hash = support::hash_of(&bytes);
+		let pallet: &Self = runtime.pallet();
+		let status = pallet.status.get_ref(&hash);
+		if status.is_some_and(|status| status.len.is_some()) {
+			return Err("preimage is already noted".into());
+		}
+
+		let deposit = if status.is_some_and(|status| status.count > 0) {
+			None
+		} else {
+			let deposit = T::BYTE_DEPOSIT
+				.checked_mul(&len.into())
+				.and_then(|bytes_deposit| bytes_deposit.checked_add(&T::BASE_DEPOSIT))
+				.ok_or("Overflow")?;
+			let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+			balances.reserve(&caller, deposit)?;
+			Some((caller, deposit))
+		};
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.status.mutate(&hash, |status| {
+			let status = status.get_or_insert(RequestStatus { deposit: None, count: 0, len: None });
+			status.deposit = deposit;
+			status.len = Some(len);
+		});
+		pallet.preimages.insert(hash, bytes);
+		Ok(())
+	}
+
+	/// Remove a preimage noted by the caller, and return its deposit. A preimage which is
+	/// requested is kept until it is unrequested.
+	#[call_index(1)]
+	pub fn unnote_preimage(runtime: &mut T, caller: T::AccountId, hash: Hash) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let status = pallet.status.get_mut(&hash).ok_or("preimage is not noted")?;
+		let deposit = match &status.deposit {
+			Some((depositor, deposit)) if *depositor == caller => *deposit,
+			_ => return Err("preimage was not noted by the caller".into()),
+		};
+		status.deposit = None;
+		if status.count == 0 {
+			pallet.status.remove(&hash);
+			pallet.preimages.remove(&hash);
+		}
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.unreserve(&caller, deposit)
+	}
+
+	/// Request a preimage, as the root origin. See `request`.
+	#[call_index(2)]
+	pub fn request_preimage(&mut self, origin: Origin<T>, hash: Hash) -> DispatchResult {
+		support::ensure_root(origin)?;
+		self.request(hash);
+		Ok(())
+	}
+
+	/// Remove a request for a preimage, as the root origin. See `unrequest`.
+	#[call_index(3)]
+	pub fn unrequest_preimage(&mut self, origin: Origin<T>, hash: Hash) -> DispatchResult {
+		support::ensure_root(origin)?;
+		self.unrequest(&hash)
+	}
+}
+
+impl<T: Config> crate::support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> crate::support::Hooks<T> for Pallet<T> {}
+
+#[cfg(test)]
+mod tests {
+	use super::RequestStatus;
+	use crate::{
+		codec::Encode,
+		support::{self, Dispatch, RawOrigin},
+		test_helpers::transfer,
+		Runtime, RuntimeCall,
+	};
+
+	type Preimage = super::Pallet<Runtime>;
+
+	#[test]
+	fn note_and_realize_call() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+
+		// The pallet index, the call index, the address variant, the length and bytes of "bob", and
+		// a `u128`.
+		let bytes = transfer("bob", 30).encode();
+		assert_eq!(bytes.len(), 1 + 1 + 1 + 4 + 3 + 16);
+		let hash = support::hash_of(&bytes);
+		assert_eq!(Preimage::note_preimage(&mut runtime, alice.clone(), bytes.clone()), Ok(()));
+		assert_eq!(
+			Preimage::note_preimage(&mut runtime, alice.clone(), bytes.clone()),
+			Err("preimage is already noted".into())
+		);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 10 + 26);
+		assert_eq!(runtime.preimage.fetch(&hash), Some(bytes.as_slice()));
+
+		let call: RuntimeCall = runtime.preimage.realize(&hash).unwrap();
+		assert_eq!(call, *transfer("bob", 30));
+		assert_eq!(runtime.dispatch(RawOrigin::Signed(alice.clone()), call), Ok(()));
+		assert_eq!(runtime.balances.balance(&bob), 30);
+		let garbage = vec![u8::MAX; 4];
+		assert_eq!(
+			runtime.preimage.realize::<RuntimeCall>(&support::hash_of(&garbage)),
+			Err("preimage is not noted".to_string())
+		);
+		assert_eq!(Preimage::note_preimage(&mut runtime, alice.clone(), garbage.clone()), Ok(()));
+		assert_eq!(
+			runtime.preimage.realize::<RuntimeCall>(&support::hash_of(&garbage)),
+			Err("invalid pallet index".to_string())
+		);
+		assert_eq!(
+			Preimage::unnote_preimage(&mut runtime, alice.clone(), support::hash_of(&garbage)),
+			Ok(())
+		);
+
+		assert_eq!(
+			Preimage::unnote_preimage(&mut runtime, bob, hash),
+			Err("preimage was not noted by the caller".into())
+		);
+		assert_eq!(Preimage::unnote_preimage(&mut runtime, alice.clone(), hash), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.preimage.fetch(&hash), None);
+		assert_eq!(runtime.preimage.status(&hash), None);
+	}
+
+	#[test]
+	fn requests_keep_preimages_alive() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		let bytes = transfer("bob", 30).encode();
+		let hash = support::hash_of(&bytes);
+
+		// A requested preimage is noted for free, and removed with the last request.
+		assert_eq!(
+			runtime.preimage.request_preimage(RawOrigin::Signed(alice.clone()), hash),
+			Err("bad origin: expected the root origin".into())
+		);
+		assert_eq!(runtime.preimage.request_preimage(RawOrigin::Root, hash), Ok(()));
+		runtime.preimage.request(hash);
+		assert_eq!(Preimage::note_preimage(&mut runtime, alice.clone(), bytes.clone()), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(
+			runtime.preimage.status(&hash),
+			Some(&RequestStatus { deposit: None, count: 2, len: Some(26) })
+		);
+		assert_eq!(runtime.preimage.unrequest_preimage(RawOrigin::Root, hash), Ok(()));
+		assert!(runtime.preimage.fetch(&hash).is_some());
+		assert_eq!(runtime.preimage.unrequest(&hash), Ok(()));
+		assert_eq!(runtime.preimage.fetch(&hash), None);
+		assert_eq!(runtime.preimage.unrequest(&hash), Err("preimage is not requested".into()));
+
+		// A preimage noted with a deposit is kept while requested, even once it is unnoted.
+		assert_eq!(Preimage::note_preimage(&mut runtime, alice.clone(), bytes.clone()), Ok(()));
+		runtime.preimage.request(hash);
+		assert_eq!(Preimage::unnote_preimage(&mut runtime, alice.clone(), hash), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert!(runtime.preimage.fetch(&hash).is_some());
+		assert_eq!(runtime.preimage.unrequest(&hash), Ok(()));
+		assert_eq!(runtime.preimage.fetch(&hash), None);
+	}
+}
@@ -1,71 +1,74 @@
-use crate::support::DispatchResult;
-use core::fmt::Debug;
-use std::collections::BTreeMap;
+pub use pallet::*;
 
-pub trait Config: crate::system::Config {
-	type Content: Debug + Ord;
-}
-
-#[derive(Debug)]
-pub struct Pallet<T: Config> {
-	claims: BTreeMap<T::Content, T::AccountId>,
-}
+#[macros::pallet]
+pub mod pallet {
+	use crate::{
+		storage::{Hash64Concat, StorageMap},
+		support::DispatchResult,
+	};
+	use core::fmt::Debug;
 
-impl<T: Config> Pallet<T> {
-	pub fn new() -> Self {
-		Self { claims: BTreeMap::new() }
+	#[pallet::config]
+	pub trait Config: crate::system::Config {
+		type Content: Debug + Ord;
 	}
 
-	pub fn get_claim(&self, claim: &T::Content) -> Option<&T::AccountId> {
-		self.claims.get(&claim)
+	#[pallet::storage]
+	#[derive(Debug)]
+	pub struct Pallet<T: Config> {
+		#[getter(get_claim)]
+		claims: StorageMap<Hash64Concat, T::Content, T::AccountId>,
 	}
-}
 
-#[macros::call]
-impl<T: Config> Pallet<T> {
-	pub fn create_claim(&mut self, caller: T::AccountId, claim: T::Content) -> DispatchResult {
-		if self.claims.contains_key(&claim) {
-			return Err("this content is already claimed".to_string());
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		#[call_index(0)]
+		pub fn create_claim(&mut self, caller: T::AccountId, claim: T::Content) -> DispatchResult {
+			if self.claims.contains_key(&claim) {
+				return Err("this content is already claimed".into());
+			}
+			self.claims.insert(claim, caller);
+			Ok(())
 		}
-		self.claims.insert(claim, caller);
-		Ok(())
-	}
 
-	pub fn revoke_claim(&mut self, caller: T::AccountId, claim: T::Content) -> DispatchResult {
-		let owner = self.get_claim(&claim).ok_or("claim does not exist")?;
-		if caller != *owner {
-			return Err("this content is owned by someone else".to_string());
+		#[call_index(1)]
+		pub fn revoke_claim(&mut self, caller: T::AccountId, claim: T::Content) -> DispatchResult {
+			let owner = self.get_claim(&claim).ok_or("claim does not exist")?;
+			if caller != owner {
+				return Err("this content is owned by someone else".into());
+			}
+			self.claims.remove(&claim);
+			Ok(())
 		}
-		self.claims.remove(&claim);
-		Ok(())
 	}
-}
 
-#[cfg(test)]
-mod test {
-	struct TestConfig;
+	#[cfg(test)]
+	mod test {
+		struct TestConfig;
 
-	impl super::Config for TestConfig {
-		type Content = &'static str;
-	}
+		impl super::Config for TestConfig {
+			type Content = &'static str;
+		}
 
-	impl crate::system::Config for TestConfig {
-		type AccountId = &'static str;
-		type BlockNumber = u32;
-		type Nonce = u32;
-	}
+		impl crate::system::Config for TestConfig {
+			type AccountId = &'static str;
+			type BlockNumber = u32;
+			type Nonce = u32;
+			type RuntimeEvent = ();
+		}
 
-	#[test]
-	fn basic_proof_of_existence() {
-		let mut poe = super::Pallet::<TestConfig>::new();
-		assert_eq!(poe.get_claim(&"Hello, world!"), None);
-		assert_eq!(poe.create_claim(&"alice", &"Hello, world!"), Ok(()));
-		assert_eq!(poe.get_claim(&"Hello, world!"), Some(&"alice"));
-		assert_eq!(
-			poe.create_claim(&"bob", &"Hello, world!"),
-			Err("this content is already claimed".to_string())
-		);
-		assert_eq!(poe.revoke_claim(&"alice", &"Hello, world!"), Ok(()));
-		assert_eq!(poe.create_claim(&"bob", &"Hello, world!"), Ok(()));
+		#[test]
+		fn basic_proof_of_existence() {
+			let mut poe = super::Pallet::<TestConfig>::new();
+			assert_eq!(poe.get_claim(&"Hello, world!"), None);
+			assert_eq!(poe.create_claim("alice", "Hello, world!"), Ok(()));
+			assert_eq!(poe.get_claim(&"Hello, world!"), Some("alice"));
+			assert_eq!(
+				poe.create_claim("bob", "Hello, world!"),
+				Err("this content is already claimed".into())
+			);
+			assert_eq!(poe.revoke_claim("alice", "Hello, world!"), Ok(()));
+			assert_eq!(poe.create_claim("bob", "Hello, world!"), Ok(()));
+		}
 	}
 }
@@ -0,0 +1,569 @@
+use crate::{
+	storage::{Hash64Concat, StorageMap, ValueQuery},
+	support::{
+		self, Dispatch, DispatchError, DispatchResult, FromHash, GetPallet, Hash, InstanceFilter,
+		RawOrigin,
+	},
+};
+use num::{CheckedAdd, Zero};
+
+#[macros::config]
+pub trait Config:
+	Sized
+	+ crate::system::Config<AccountId: FromHash + std::hash::Hash, BlockNumber: std::hash::Hash>
+	+ crate::balances::Config
+	+ GetPallet<crate::system::Pallet<Self>>
+	+ GetPallet<crate::balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+	+ Dispatch<Caller = crate::system::Origin<Self>, Call = <Self as Config>::RuntimeCall>
+{
+	/// The overarching call type, which proxies dispatch on behalf of the real account.
+	type RuntimeCall: std::hash::Hash;
+	/// The kinds of proxy, which restrict the calls a delegate can make.
+	type ProxyType: InstanceFilter<<Self as Config>::RuntimeCall>
+		+ Copy
+		+ Eq
+		+ std::hash::Hash
+		+ std::fmt::Debug;
+	/// The amount reserved for each proxy.
+	const PROXY_DEPOSIT: Self::Balance;
+	/// The amount reserved for each announcement.
+	const ANNOUNCEMENT_DEPOSIT: Self::Balance;
+}
+
+/// A delegate which can dispatch calls on behalf of a real account.
+#[derive(Debug, macros::TypeInfo)]
+pub struct ProxyDefinition<T: Config> {
+	/// The account which can dispatch calls.
+	pub delegate: T::AccountId,
+	/// The calls the delegate is allowed to dispatch.
+	pub proxy_type: T::ProxyType,
+	/// The number of blocks a call must be announced for before it can be dispatched. With no
+	/// delay, the delegate can dispatch calls directly.
+	pub delay: T::BlockNumber,
+	/// The account which reserved the deposit for this proxy.
+	pub depositor: T::AccountId,
+}
+
+/// A call which a delegate announced it will dispatch on behalf of a real account.
+#[derive(Debug, macros::TypeInfo)]
+pub struct Announcement<T: Config> {
+	pub real: T::AccountId,
+	pub call_hash: Hash,
+	/// The block at which the announcement was made.
+	pub height: T::BlockNumber,
+}
+
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	/// The proxies of each real account.
+	proxies: StorageMap<Hash64Concat, T::AccountId, Vec<ProxyDefinition<T>>, ValueQuery>,
+	/// The announcements made by each delegate.
+	announcements: StorageMap<Hash64Concat, T::AccountId, Vec<Announcement<T>>, ValueQuery>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn proxies(&self, real: &T::AccountId) -> &[ProxyDefinition<T>] {
+		self.proxies.get_ref(real).map(|proxies| proxies.as_slice()).unwrap_or_default()
+	}
+
+	pub fn announcements(&self, delegate: &T::AccountId) -> &[Announcement<T>] {
+		self.announcements
+			.get_ref(delegate)
+			.map(|announcements| announcements.as_slice())
+			.unwrap_or_default()
+	}
+
+	/// Derive the account id of a pure proxy. The block number and extrinsic index at which it was
+	/// created make it unique, even for the same spawner and index.
+	pub fn pure_account(
+		spawner: &T::AccountId,
+		proxy_type: &T::ProxyType,
+		index: u16,
+		height: T::BlockNumber,
+		ext_index: u32,
+	) -> T::AccountId {
+		let entropy = ("pure", spawner, proxy_type, index, height, ext_index);
+		T::AccountId::from_hash(support::hash_of(&entropy))
+	}
+
+	/// Register `delegate` as a proxy of `real`, reserving the deposit from `depositor`.
+	fn add_proxy_delegate(
+		runtime: &mut T,
+		real: T::AccountId,
+		delegate: T::AccountId,
+		proxy_type: T::ProxyType,
+		delay: T::BlockNumber,
+		depositor: T::AccountId,
+	) -> DispatchResult {
+		if real == delegate {
+			return Err("an account cannot be its own proxy".into());
+		}
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let exists = pallet.proxies(&real).iter().any(|proxy| {
+			proxy.delegate == delegate && proxy.proxy_type == proxy_type && proxy.delay == delay
+		});
+		if exists {
+			return Err("proxy already exists".into());
+		}
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.reserve(&depositor, T::PROXY_DEPOSIT)?;
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let proxy = ProxyDefinition { delegate, proxy_type, delay, depositor };
+		pallet.proxies.mutate(&real, |proxies| proxies.push(proxy));
+		Ok(())
+	}
+
+	/// Remove all proxies of `real`, returning their deposits.
+	fn remove_all_proxies(runtime: &mut T, real: &T::AccountId) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let proxies = pallet.proxies.take(real);
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		for proxy in proxies {
+			balances.unreserve(&proxy.depositor, T::PROXY_DEPOSIT)?;
+		}
+		Ok(())
+	}
+
+	/// Remove the announcement of `delegate` for `real` and `call_hash`, returning its deposit.
+	fn remove_announcement_of(
+		runtime: &mut T,
+		delegate: &T::AccountId,
+		real: &T::AccountId,
+		call_hash: Hash,
+	) -> Result<Announcement<T>, DispatchError> {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let announcements =
+			pallet.announcements.get_mut(delegate).ok_or("announcement does not exist")?;
+		let position = announcements
+			.iter()
+			.position(|announcement| {
+				announcement.real == *real && announcement.call_hash == call_hash
+			})
+			.ok_or("announcement does not exist")?;
+		let announcement = announcements.remove(position);
+		if announcements.is_empty() {
+			pallet.announcements.remove(delegate);
+		}
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.unreserve(delegate, T::ANNOUNCEMENT_DEPOSIT)?;
+		Ok(announcement)
+	}
+
+	/// Dispatch `call` as `real`, if `proxy_type` allows it.
+	fn do_proxy(
+		runtime: &mut T,
+		proxy_type: T::ProxyType,
+		real: T::AccountId,
+		call: <T as Config>::RuntimeCall,
+	) -> DispatchResult {
+		if !proxy_type.filter(&call) {
+			return Err("call is not allowed for this proxy type".into());
+		}
+		runtime.dispatch(RawOrigin::Signed(real), call)
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Register `delegate` as a proxy of the caller, restricted to `proxy_type` calls. A non-zero
+	/// `delay` means calls must be announced that many blocks before they are dispatched.
+	#[call_index(0)]
+	pub fn add_proxy(
+		runtime: &mut T,
+		caller: T::AccountId,
+		delegate: T::AccountId,
+		proxy_type: T::ProxyType,
+		delay: T::BlockNumber,
+	) -> DispatchResult {
+		Self::add_proxy_delegate(runtime, caller.clone(), delegate, proxy_type, delay, caller)
+	}
+
+	/// Unregister a proxy of the caller, and return its deposit.
+	#[call_index(1)]
+	pub fn remove_proxy(
+		runtime: &mut T,
+		caller: T::AccountId,
+		delegate: T::AccountId,
+		proxy_type: T::ProxyType,
+		delay: T::BlockNumber,
+	) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let proxies = pallet.proxies.get_mut(&caller).ok_or("proxy does not exist")?;
+		let position = proxies
+			.iter()
+			.position(|proxy| {
+				proxy.delegate == delegate && proxy.proxy_type == proxy_type && proxy.delay == delay
+			})
+			.ok_or("proxy does not exist")?;
+		let proxy = proxies.remove(position);
+		if proxies.is_empty() {
+			pallet.proxies.remove(&caller);
+		}
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.unreserve(&proxy.depositor, T::PROXY_DEPOSIT)
+	}
+
+	/// Unregister all proxies of the caller, and return their deposits.
+	#[call_index(2)]
+	pub fn remove_proxies(runtime: &mut T, caller: T::AccountId) -> DispatchResult {
+		Self::remove_all_proxies(runtime, &caller)
+	}
+
+	/// Create a new account which has no key, with the caller as its proxy. The caller pays the
+	/// deposit of the proxy.
+	///
+	/// Calls can then only be made for the pure account through its proxies.
+	#[call_index(3)]
+	pub fn create_pure(
+		runtime: &mut T,
+		caller: T::AccountId,
+		proxy_type: T::ProxyType,
+		delay: T::BlockNumber,
+		index: u16,
+	) -> DispatchResult {
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		let (height, ext_index) = (system.block_number(), system.extrinsic_index());
+		let pure = Self::pure_account(&caller, &proxy_type, index, height, ext_index);
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		if pallet.proxies.contains_key(&pure) {
+			return Err("pure proxy already exists".into());
+		}
+
+		Self::add_proxy_delegate(runtime, pure, caller.clone(), proxy_type, delay, caller)
+	}
+
+	/// Remove a pure account created by `spawner` with `create_pure`, along with all its proxies.
+	/// This must be called by the pure account itself, through a proxy.
+	///
+	/// Any funds left in the pure account become inaccessible.
+	#[call_index(4)]
+	pub fn kill_pure(
+		runtime: &mut T,
+		caller: T::AccountId,
+		spawner: T::AccountId,
+		proxy_type: T::ProxyType,
+		index: u16,
+		height: T::BlockNumber,
+		ext_index: u32,
+	) -> DispatchResult {
+		let pure = Self::pure_account(&spawner, &proxy_type, index, height, ext_index);
+		if caller != pure {
+			return Err("caller is not the pure account".into());
+		}
+		Self::remove_all_proxies(runtime, &caller)
+	}
+
+	/// Dispatch `call` on behalf of `real`. The caller must be a proxy of `real` without a delay,
+	/// and its proxy type must allow the call.
+	#[call_index(5)]
+	pub fn proxy(
+		runtime: &mut T,
+		caller: T::AccountId,
+		real: T::AccountId,
+		call: Box<<T as Config>::RuntimeCall>,
+	) -> DispatchResult {
+		let pallet: &Self = runtime.pallet();
+		let proxy_type = pallet
+			.proxies(&real)
+			.iter()
+			.filter(|proxy| proxy.delegate == caller && proxy.delay.is_zero())
+			.map(|proxy| proxy.proxy_type)
+			.find(|proxy_type| proxy_type.filter(&call))
+			.ok_or("caller is not a proxy of this account for this call")?;
+
+		Self::do_proxy(runtime, proxy_type, real, *call)
+	}
+
+	/// Announce that the caller will dispatch the call with `call_hash` on behalf of `real`, once
+	/// the delay of its proxy has passed.
+	#[call_index(6)]
+	pub fn announce(
+		runtime: &mut T,
+		caller: T::AccountId,
+		real: T::AccountId,
+		call_hash: Hash,
+	) -> DispatchResult {
+		let pallet: &Self = runtime.pallet();
+		if !pallet.proxies(&real).iter().any(|proxy| proxy.delegate == caller) {
+			return Err("caller is not a proxy of this account".into());
+		}
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.reserve(&caller, T::ANNOUNCEMENT_DEPOSIT)?;
+
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		let height = system.block_number();
+		let pallet: &mut Self = runtime.pallet_mut();
+		let announcement = Announcement { real, call_hash, height };
+		pallet
+			.announcements
+			.mutate(&caller, |announcements| announcements.push(announcement));
+		Ok(())
+	}
+
+	/// Remove an announcement made by the caller, and return its deposit.
+	#[call_index(7)]
+	pub fn remove_announcement(
+		runtime: &mut T,
+		caller: T::AccountId,
+		real: T::AccountId,
+		call_hash: Hash,
+	) -> DispatchResult {
+		Self::remove_announcement_of(runtime, &caller, &real, call_hash).map(|_| ())
+	}
+
+	/// Reject an announcement made by `delegate` on behalf of the caller. The deposit is returned
+	/// to the delegate.
+	#[call_index(8)]
+	pub fn reject_announcement(
+		runtime: &mut T,
+		caller: T::AccountId,
+		delegate: T::AccountId,
+		call_hash: Hash,
+	) -> DispatchResult {
+		Self::remove_announcement_of(runtime, &delegate, &caller, call_hash).map(|_| ())
+	}
+
+	/// Dispatch an announced call on behalf of `real`, once the delay of the proxy has passed.
+	/// Anyone can do this for `delegate`.
+	#[call_index(9)]
+	pub fn proxy_announced(
+		runtime: &mut T,
+		_caller: T::AccountId,
+		delegate: T::AccountId,
+		real: T::AccountId,
+		call: Box<<T as Config>::RuntimeCall>,
+	) -> DispatchResult {
+		let call_hash = support::hash_of(&call);
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		let now = system.block_number();
+
+		let pallet: &Self = runtime.pallet();
+		let height = pallet
+			.announcements(&delegate)
+			.iter()
+			.find(|announcement| announcement.real == real && announcement.call_hash == call_hash)
+			.map(|announcement| announcement.height)
+			.ok_or("call was not announced")?;
+
+		// The call must be allowed by one of the proxies whose delay has passed.
+		let proxy_type = pallet
+			.proxies(&real)
+			.iter()
+			.filter(|proxy| proxy.delegate == delegate)
+			.filter(|proxy| height.checked_add(&proxy.delay).is_some_and(|ready| ready <= now))
+			.map(|proxy| proxy.proxy_type)
+			.find(|proxy_type| proxy_type.filter(&call))
+			.ok_or("announcement is not ready to be dispatched")?;
+
+		// The announcement is only consumed once the call succeeded. If the call fails, it stays
+		// announced, so that it can be dispatched later.
+		Self::do_proxy(runtime, proxy_type, real.clone(), *call)?;
+		Self::remove_announcement_of(runtime, &delegate, &real, call_hash)?;
+		Ok(())
+	}
+}
+
+impl<T: Config> crate::support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> crate::support::Hooks<T> for Pallet<T> {}
+
+#[cfg(test)]
+mod tests {
+	use crate::{support, test_helpers::transfer, ProxyType, Runtime, RuntimeCall};
+
+	type Proxy = super::Pallet<Runtime>;
+
+	fn create_claim(claim: &'static str) -> Box<RuntimeCall> {
+		Box::new(RuntimeCall::proof_of_existence(crate::proof_of_existence::Call::create_claim {
+			claim,
+		}))
+	}
+
+	#[test]
+	fn proxy_dispatches_filtered_calls() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		runtime.balances.set_balance(alice.clone(), 100);
+		assert_eq!(
+			Proxy::add_proxy(&mut runtime, alice.clone(), bob.clone(), ProxyType::Balances, 0),
+			Ok(())
+		);
+		assert_eq!(
+			Proxy::add_proxy(&mut runtime, alice.clone(), bob.clone(), ProxyType::Balances, 0),
+			Err("proxy already exists".into())
+		);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 5);
+
+		// Bob can transfer on behalf of Alice...
+		assert_eq!(
+			Proxy::proxy(&mut runtime, bob.clone(), alice.clone(), transfer("charlie", 20)),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.balance(&alice), 75);
+		assert_eq!(runtime.balances.balance(&"charlie".to_string()), 20);
+
+		// ...but not create claims.
+		assert_eq!(
+			Proxy::proxy(&mut runtime, bob.clone(), alice.clone(), create_claim("Hello, world!")),
+			Err("caller is not a proxy of this account for this call".into())
+		);
+
+		// Charlie is not a proxy at all.
+		assert_eq!(
+			Proxy::proxy(
+				&mut runtime,
+				"charlie".to_string(),
+				alice.clone(),
+				transfer("charlie", 20)
+			),
+			Err("caller is not a proxy of this account for this call".into())
+		);
+
+		assert_eq!(
+			Proxy::remove_proxy(&mut runtime, alice.clone(), bob.clone(), ProxyType::Balances, 0),
+			Ok(())
+		);
+		assert!(runtime.proxy.proxies(&alice).is_empty());
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+	}
+
+	#[test]
+	fn announced_calls_wait_for_the_delay() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		runtime.balances.set_balance(alice.clone(), 100);
+		runtime.balances.set_balance(bob.clone(), 100);
+		assert_eq!(
+			Proxy::add_proxy(&mut runtime, alice.clone(), bob.clone(), ProxyType::Any, 2),
+			Ok(())
+		);
+
+		// A delayed proxy cannot dispatch directly.
+		assert_eq!(
+			Proxy::proxy(&mut runtime, bob.clone(), alice.clone(), transfer("charlie", 20)),
+			Err("caller is not a proxy of this account for this call".into())
+		);
+
+		let call_hash = support::hash_of(&transfer("charlie", 20));
+		assert_eq!(Proxy::announce(&mut runtime, bob.clone(), alice.clone(), call_hash), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&bob), 1);
+
+		runtime.system.inc_block_number().unwrap();
+		assert_eq!(
+			Proxy::proxy_announced(
+				&mut runtime,
+				"dave".to_string(),
+				bob.clone(),
+				alice.clone(),
+				transfer("charlie", 20)
+			),
+			Err("announcement is not ready to be dispatched".into())
+		);
+
+		runtime.system.inc_block_number().unwrap();
+		assert_eq!(
+			Proxy::proxy_announced(
+				&mut runtime,
+				"dave".to_string(),
+				bob.clone(),
+				alice.clone(),
+				transfer("charlie", 20)
+			),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.balance(&"charlie".to_string()), 20);
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+		assert!(runtime.proxy.announcements(&bob).is_empty());
+
+		// The real account can reject announcements.
+		assert_eq!(Proxy::announce(&mut runtime, bob.clone(), alice.clone(), call_hash), Ok(()));
+		assert_eq!(Proxy::reject_announcement(&mut runtime, alice, bob.clone(), call_hash), Ok(()));
+		assert!(runtime.proxy.announcements(&bob).is_empty());
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+	}
+
+	#[test]
+	fn failed_announced_call_keeps_the_announcement() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		runtime.balances.set_balance(bob.clone(), 100);
+		assert_eq!(
+			Proxy::add_proxy(&mut runtime, alice.clone(), bob.clone(), ProxyType::Any, 1),
+			Ok(())
+		);
+		let call_hash = support::hash_of(&transfer("charlie", 200));
+		assert_eq!(Proxy::announce(&mut runtime, bob.clone(), alice.clone(), call_hash), Ok(()));
+		runtime.system.inc_block_number().unwrap();
+
+		// Alice cannot pay yet, so the announcement and its deposit are kept.
+		let proxy_announced = |runtime: &mut Runtime| {
+			Proxy::proxy_announced(
+				runtime,
+				bob.clone(),
+				bob.clone(),
+				alice.clone(),
+				transfer("charlie", 200),
+			)
+		};
+		assert_eq!(proxy_announced(&mut runtime), Err("Not enough funds.".into()));
+		assert_eq!(runtime.proxy.announcements(&bob).len(), 1);
+		assert_eq!(runtime.balances.reserved_balance(&bob), 1);
+
+		runtime.balances.set_balance(alice.clone(), 300);
+		assert_eq!(proxy_announced(&mut runtime), Ok(()));
+		assert_eq!(runtime.balances.balance(&"charlie".to_string()), 200);
+		assert!(runtime.proxy.announcements(&bob).is_empty());
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+	}
+
+	#[test]
+	fn pure_proxy() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+
+		runtime.balances.set_balance(alice.clone(), 100);
+		assert_eq!(Proxy::create_pure(&mut runtime, alice.clone(), ProxyType::Any, 0, 0), Ok(()));
+		let pure = Proxy::pure_account(&alice, &ProxyType::Any, 0, 0, 0);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 5);
+
+		// Alice controls the pure account.
+		runtime.balances.set_balance(pure.clone(), 50);
+		assert_eq!(
+			Proxy::proxy(&mut runtime, alice.clone(), pure.clone(), transfer("bob", 20)),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.balance(&pure), 30);
+
+		// Only the pure account can kill itself.
+		assert_eq!(
+			Proxy::kill_pure(&mut runtime, alice.clone(), alice.clone(), ProxyType::Any, 0, 0, 0),
+			Err("caller is not the pure account".into())
+		);
+		let kill = Box::new(RuntimeCall::proxy(super::Call::kill_pure {
+			spawner: alice.clone().into(),
+			proxy_type: ProxyType::Any,
+			index: 0,
+			height: 0,
+			ext_index: 0,
+		}));
+		assert_eq!(Proxy::proxy(&mut runtime, alice.clone(), pure.clone(), kill), Ok(()));
+		assert!(runtime.proxy.proxies(&pure).is_empty());
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+	}
+}
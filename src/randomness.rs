@@ -0,0 +1,247 @@
+use crate::{
+	storage::{Hash64Concat, StorageMap, StorageValue, ValueQuery},
+	support::{self, DispatchError, DispatchResult, GetPallet, Hash, InitializeReport},
+};
+use num::Zero;
+
+#[macros::config]
+pub trait Config:
+	crate::system::Config<AccountId: std::hash::Hash>
+	+ crate::treasury::Config
+	+ GetPallet<Pallet<Self>>
+{
+	/// How long a round of commits and reveals is, in blocks.
+	const ROUND_LENGTH: Self::BlockNumber;
+	/// How many blocks at the start of each round accept commits. The rest of the round accepts
+	/// reveals.
+	const COMMIT_PERIOD: Self::BlockNumber;
+	/// The amount reserved with each commit, and slashed if the secret is not revealed.
+	const DEPOSIT: Self::Balance;
+}
+
+/// The phases of a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, macros::TypeInfo)]
+pub enum Phase {
+	#[default]
+	Commit,
+	Reveal,
+}
+
+/// Randomness from a commit-reveal scheme: participants commit to the hash of a secret, and reveal
+/// the secret once no more commits are accepted. At the end of each round, the revealed secrets
+/// are mixed into the random seed.
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	#[getter(phase)]
+	phase: StorageValue<Phase, ValueQuery>,
+	/// The commitments of this round which are not revealed yet.
+	commitments: StorageMap<Hash64Concat, T::AccountId, Hash>,
+	/// The secrets revealed this round.
+	reveals: StorageMap<Hash64Concat, T::AccountId, Hash>,
+	random_seed: StorageValue<Hash, ValueQuery>,
+	/// A seed which replaces the random seed, for deterministic tests.
+	fixed_seed: StorageValue<Hash>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// The commitment of `who` to `secret`.
+	pub fn commitment_of(who: &T::AccountId, secret: Hash) -> Hash {
+		support::hash_of(&("randomness", who, secret))
+	}
+
+	/// A random value for `subject`, so that different uses of the same seed are independent.
+	///
+	/// The value changes at the end of every round, so callers should not use it twice for the
+	/// same purpose.
+	pub fn random(&self, subject: &[u8]) -> Hash {
+		support::hash_of(&(self.fixed_seed.get().unwrap_or(self.random_seed.get()), subject))
+	}
+
+	/// Derive all randomness from `seed` instead of the reveals, so that it is reproducible.
+	/// Meant for tests.
+	pub fn set_seed(&mut self, seed: Hash) {
+		self.fixed_seed.put(seed);
+	}
+
+	/// Mix the reveals into the random seed, and slash the deposits of the participants who did
+	/// not reveal their secret. A deposit which cannot be slashed does not keep the other deposits
+	/// from being slashed. Returns the errors of those which could not be slashed.
+	fn end_round(runtime: &mut T) -> Vec<DispatchError> {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let reveals: Vec<_> = pallet.reveals.drain().collect();
+		pallet.random_seed.put(support::hash_of(&(pallet.random_seed.get(), reveals)));
+		pallet.phase.put(Phase::Commit);
+
+		let mut failed = Vec::new();
+		for (who, _) in pallet.commitments.drain() {
+			let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+			let result = balances
+				.slash_reserved(&who, T::DEPOSIT)
+				.and_then(|()| crate::treasury::Pallet::<T>::on_unbalanced(runtime, T::DEPOSIT));
+			if let Err(e) = result {
+				failed.push(e);
+			}
+		}
+		failed
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Commit to a secret, by its commitment from `commitment_of`. Reserves the deposit.
+	#[call_index(0)]
+	pub fn commit(runtime: &mut T, caller: T::AccountId, commitment: Hash) -> DispatchResult {
+		let pallet: &Self = runtime.pallet();
+		if pallet.phase() != Phase::Commit {
+			return Err("not in the commit phase".into());
+		}
+		if pallet.commitments.contains_key(&caller) || pallet.reveals.contains_key(&caller) {
+			return Err("already committed".into());
+		}
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.reserve(&caller, T::DEPOSIT)?;
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.commitments.insert(caller, commitment);
+		Ok(())
+	}
+
+	/// Reveal the secret of the caller's commitment, and get the deposit back.
+	#[call_index(1)]
+	pub fn reveal(runtime: &mut T, caller: T::AccountId, secret: Hash) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		if pallet.phase() != Phase::Reveal {
+			return Err("not in the reveal phase".into());
+		}
+		let commitment = pallet.commitments.get(&caller).ok_or("no commitment")?;
+		if Self::commitment_of(&caller, secret) != commitment {
+			return Err("secret does not match the commitment".into());
+		}
+		pallet.commitments.remove(&caller);
+		pallet.reveals.insert(caller.clone(), secret);
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.unreserve(&caller, T::DEPOSIT)
+	}
+}
+
+impl<T: Config> support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> support::Hooks<T> for Pallet<T> {
+	/// End the round at the start of every round, and start the reveal phase once the commit
+	/// period is over.
+	fn on_initialize(runtime: &mut T, block_number: T::BlockNumber) -> InitializeReport {
+		let position = block_number % T::ROUND_LENGTH;
+		if position == T::COMMIT_PERIOD {
+			let pallet: &mut Self = runtime.pallet_mut();
+			pallet.phase.put(Phase::Reveal);
+		} else if position.is_zero() {
+			let failed = Self::end_round(runtime);
+			return InitializeReport { weight: support::CALL_WEIGHT, failed };
+		}
+		InitializeReport::default()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Phase;
+	use crate::{support::Hooks, Runtime};
+
+	type Randomness = super::Pallet<Runtime>;
+
+	#[test]
+	fn commit_and_reveal() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		runtime.balances.set_balance(bob.clone(), 100);
+		let initial = runtime.randomness.random(b"lottery");
+
+		let commitment = Randomness::commitment_of(&alice, 42);
+		assert_eq!(Randomness::commit(&mut runtime, alice.clone(), commitment), Ok(()));
+		assert_eq!(
+			Randomness::commit(&mut runtime, alice.clone(), commitment),
+			Err("already committed".into())
+		);
+		let commitment = Randomness::commitment_of(&bob, 7);
+		assert_eq!(Randomness::commit(&mut runtime, bob.clone(), commitment), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&bob), 10);
+		assert_eq!(
+			Randomness::reveal(&mut runtime, alice.clone(), 42),
+			Err("not in the reveal phase".into())
+		);
+
+		Randomness::on_initialize(&mut runtime, 2);
+		assert_eq!(runtime.randomness.phase(), Phase::Reveal);
+		assert_eq!(
+			Randomness::reveal(&mut runtime, alice.clone(), 7),
+			Err("secret does not match the commitment".into())
+		);
+		assert_eq!(Randomness::reveal(&mut runtime, alice.clone(), 42), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+
+		// Bob did not reveal, so the new round slashes their deposit.
+		Randomness::on_initialize(&mut runtime, 4);
+		assert_eq!(runtime.randomness.phase(), Phase::Commit);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.balances.balance(&bob), 90);
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+		assert_eq!(crate::treasury::Pallet::<Runtime>::pot(&runtime), 8);
+
+		let random = runtime.randomness.random(b"lottery");
+		assert_ne!(random, initial);
+		assert_ne!(random, runtime.randomness.random(b"validators"));
+	}
+
+	#[test]
+	fn failed_slash_does_not_stop_the_round() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		for who in [&alice, &bob] {
+			runtime.balances.set_balance(who.clone(), 100);
+			let commitment = Randomness::commitment_of(who, 42);
+			Randomness::commit(&mut runtime, who.clone(), commitment).unwrap();
+		}
+		// The deposit of Alice is gone by the end of the round, so it cannot be slashed.
+		runtime.balances.unreserve(&alice, 10).unwrap();
+
+		Randomness::on_initialize(&mut runtime, 2);
+		let report = Randomness::on_initialize(&mut runtime, 4);
+		assert_eq!(report.failed, ["Not enough reserved funds.".into()]);
+		assert_eq!(runtime.randomness.phase(), Phase::Commit);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.balances.balance(&bob), 90);
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+		assert_eq!(crate::treasury::Pallet::<Runtime>::pot(&runtime), 8);
+
+		// Both commitments are settled, so both can commit again.
+		for who in [&alice, &bob] {
+			let commitment = Randomness::commitment_of(who, 7);
+			assert_eq!(Randomness::commit(&mut runtime, who.clone(), commitment), Ok(()));
+		}
+	}
+
+	#[test]
+	fn seeded_randomness_is_deterministic() {
+		let mut runtime = Runtime::new();
+		let mut other = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		runtime.randomness.set_seed(1);
+		other.randomness.set_seed(1);
+
+		let commitment = Randomness::commitment_of(&alice, 42);
+		Randomness::commit(&mut runtime, alice.clone(), commitment).unwrap();
+		Randomness::on_initialize(&mut runtime, 2);
+		Randomness::reveal(&mut runtime, alice, 42).unwrap();
+		Randomness::on_initialize(&mut runtime, 4);
+
+		assert_eq!(runtime.randomness.random(b"lottery"), other.randomness.random(b"lottery"));
+		other.randomness.set_seed(2);
+		assert_ne!(runtime.randomness.random(b"lottery"), other.randomness.random(b"lottery"));
+	}
+}
@@ -0,0 +1,448 @@
+use crate::{
+	storage::{Hash64Concat, StorageDoubleMap, StorageMap},
+	support::{Dispatch, DispatchResult, GetPallet, RawOrigin},
+};
+use num::{CheckedAdd, CheckedMul};
+
+#[macros::config]
+pub trait Config:
+	Sized
+	+ crate::system::Config<AccountId: std::hash::Hash, BlockNumber: std::hash::Hash>
+	+ crate::balances::Config<Balance: From<u32> + CheckedMul>
+	+ GetPallet<crate::system::Pallet<Self>>
+	+ GetPallet<crate::balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+	+ Dispatch<Caller = crate::system::Origin<Self>, Call = <Self as Config>::RuntimeCall>
+{
+	/// The overarching call type, which rescuers dispatch on behalf of recovered accounts.
+	type RuntimeCall: std::hash::Hash;
+	/// The amount reserved to make an account recoverable.
+	const CONFIG_DEPOSIT_BASE: Self::Balance;
+	/// The amount reserved for each friend of a recoverable account.
+	const FRIEND_DEPOSIT_FACTOR: Self::Balance;
+	/// The amount reserved by a rescuer to initiate a recovery.
+	const RECOVERY_DEPOSIT: Self::Balance;
+	/// The maximum number of friends of a recoverable account.
+	const MAX_FRIENDS: u32;
+}
+
+/// How an account can be recovered.
+#[derive(Debug, macros::TypeInfo)]
+pub struct RecoveryConfig<T: Config> {
+	/// The number of blocks between the start of a recovery and when it can be claimed.
+	pub delay: T::BlockNumber,
+	pub deposit: T::Balance,
+	/// The friends who can vouch for a rescuer, sorted.
+	pub friends: Vec<T::AccountId>,
+	/// The number of friends who must vouch for a rescuer.
+	pub threshold: u16,
+}
+
+/// A recovery initiated by a rescuer.
+#[derive(Debug, macros::TypeInfo)]
+pub struct ActiveRecovery<T: Config> {
+	/// The block at which the recovery was initiated.
+	pub created: T::BlockNumber,
+	pub deposit: T::Balance,
+	/// The friends who vouched for the rescuer, sorted.
+	pub friends: Vec<T::AccountId>,
+}
+
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	recoverable: StorageMap<Hash64Concat, T::AccountId, RecoveryConfig<T>>,
+	/// The active recoveries, by lost account and rescuer.
+	active_recoveries:
+		StorageDoubleMap<Hash64Concat, T::AccountId, Hash64Concat, T::AccountId, ActiveRecovery<T>>,
+	/// The lost account which each rescuer can dispatch calls for, as long as the recovery is
+	/// active.
+	proxies: StorageMap<Hash64Concat, T::AccountId, T::AccountId>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn recovery_config(&self, account: &T::AccountId) -> Option<&RecoveryConfig<T>> {
+		self.recoverable.get_ref(account)
+	}
+
+	pub fn active_recovery(
+		&self,
+		lost: &T::AccountId,
+		rescuer: &T::AccountId,
+	) -> Option<&ActiveRecovery<T>> {
+		self.active_recoveries.get_ref(lost, rescuer)
+	}
+
+	/// The lost account which `rescuer` recovered, if any.
+	pub fn proxy(&self, rescuer: &T::AccountId) -> Option<&T::AccountId> {
+		self.proxies.get_ref(rescuer)
+	}
+}
+
+// The calls are boxed, since `Call` would otherwise contain itself through `RuntimeCall`.
+#[allow(clippy::boxed_local)]
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Make the caller recoverable by a rescuer once `threshold` of `friends` vouch for them, and
+	/// `delay` blocks passed since the recovery was initiated. Reserves a deposit which grows with
+	/// the number of friends.
+	#[call_index(0)]
+	pub fn create_recovery(
+		runtime: &mut T,
+		caller: T::AccountId,
+		mut friends: Vec<T::AccountId>,
+		threshold: u16,
+		delay: T::BlockNumber,
+	) -> DispatchResult {
+		let pallet: &Self = runtime.pallet();
+		if pallet.recoverable.contains_key(&caller) {
+			return Err("account is already recoverable".into());
+		}
+		if threshold == 0 || usize::from(threshold) > friends.len() {
+			return Err("threshold must be between one and the number of friends".into());
+		}
+		if friends.len() > T::MAX_FRIENDS as usize {
+			return Err("too many friends".into());
+		}
+		friends.sort();
+		if friends.windows(2).any(|pair| pair[0] == pair[1]) {
+			return Err("duplicate friends".into());
+		}
+
+		let friend_count: T::Balance = (friends.len() as u32).into();
+		let deposit = T::FRIEND_DEPOSIT_FACTOR
+			.checked_mul(&friend_count)
+			.and_then(|friends_deposit| friends_deposit.checked_add(&T::CONFIG_DEPOSIT_BASE))
+			.ok_or("Overflow")?;
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.reserve(&caller, deposit)?;
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let config = RecoveryConfig { delay, deposit, friends, threshold };
+		pallet.recoverable.insert(caller, config);
+		Ok(())
+	}
+
+	/// Start recovering `account` as the caller. Reserves a deposit, which the account can take
+	/// with `close_recovery` if the recovery is malicious.
+	#[call_index(1)]
+	pub fn initiate_recovery(
+		runtime: &mut T,
+		caller: T::AccountId,
+		account: T::AccountId,
+	) -> DispatchResult {
+		let pallet: &Self = runtime.pallet();
+		if !pallet.recoverable.contains_key(&account) {
+			return Err("account is not recoverable".into());
+		}
+		if pallet.active_recoveries.contains_key(&account, &caller) {
+			return Err("recovery already started".into());
+		}
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.reserve(&caller, T::RECOVERY_DEPOSIT)?;
+
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		let created = system.block_number();
+		let pallet: &mut Self = runtime.pallet_mut();
+		let recovery =
+			ActiveRecovery { created, deposit: T::RECOVERY_DEPOSIT, friends: Vec::new() };
+		pallet.active_recoveries.insert(account, caller, recovery);
+		Ok(())
+	}
+
+	/// Vouch for `rescuer` to recover `lost`, as a friend of `lost`.
+	#[call_index(2)]
+	pub fn vouch_recovery(
+		&mut self,
+		caller: T::AccountId,
+		lost: T::AccountId,
+		rescuer: T::AccountId,
+	) -> DispatchResult {
+		let config = self.recoverable.get_ref(&lost).ok_or("account is not recoverable")?;
+		if config.friends.binary_search(&caller).is_err() {
+			return Err("not a friend of the account".into());
+		}
+		let recovery = self
+			.active_recoveries
+			.get_mut(&lost, &rescuer)
+			.ok_or("recovery was not started")?;
+		match recovery.friends.binary_search(&caller) {
+			Ok(_) => Err("already vouched".into()),
+			Err(position) => {
+				recovery.friends.insert(position, caller);
+				Ok(())
+			},
+		}
+	}
+
+	/// Claim `account` as the caller, once enough friends vouched and the delay passed. The
+	/// caller can then dispatch calls as the account with `as_recovered`.
+	#[call_index(3)]
+	pub fn claim_recovery(
+		runtime: &mut T,
+		caller: T::AccountId,
+		account: T::AccountId,
+	) -> DispatchResult {
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		let now = system.block_number();
+		let pallet: &Self = runtime.pallet();
+		let config = pallet.recoverable.get_ref(&account).ok_or("account is not recoverable")?;
+		let recovery =
+			pallet.active_recovery(&account, &caller).ok_or("recovery was not started")?;
+		if recovery.created.checked_add(&config.delay).is_none_or(|ready| now < ready) {
+			return Err("recovery is still delayed".into());
+		}
+		if recovery.friends.len() < usize::from(config.threshold) {
+			return Err("not enough friends vouched".into());
+		}
+		if pallet.proxies.contains_key(&caller) {
+			return Err("caller already recovered an account".into());
+		}
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.proxies.insert(caller, account);
+		Ok(())
+	}
+
+	/// Close the recovery of the caller by `rescuer`. The deposit of the rescuer goes to the
+	/// caller, and the rescuer can no longer dispatch calls as the caller if they recovered it.
+	///
+	/// A rescuer who recovered the account can also call this through `as_recovered`, to get
+	/// their deposit back once they moved the funds of the account.
+	#[call_index(4)]
+	pub fn close_recovery(
+		runtime: &mut T,
+		caller: T::AccountId,
+		rescuer: T::AccountId,
+	) -> DispatchResult {
+		let pallet: &Self = runtime.pallet();
+		let recovery = pallet
+			.active_recoveries
+			.get_ref(&caller, &rescuer)
+			.ok_or("recovery was not started")?;
+
+		// The deposit moves from the reserved balance of the rescuer to the caller. Nothing is
+		// changed if it cannot.
+		let deposit = recovery.deposit;
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.balance(&caller).checked_add(&deposit).ok_or("Overflow")?;
+		balances.slash_reserved(&rescuer, deposit)?;
+		balances.deposit(&caller, deposit).expect("checked above; qed");
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.active_recoveries.remove(&caller, &rescuer);
+		if pallet.proxies.get_ref(&rescuer) == Some(&caller) {
+			pallet.proxies.remove(&rescuer);
+		}
+		Ok(())
+	}
+
+	/// Stop the caller from being recoverable, and return the deposit. All recoveries of the
+	/// caller must be closed first.
+	#[call_index(5)]
+	pub fn remove_recovery(runtime: &mut T, caller: T::AccountId) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		if pallet.active_recoveries.iter_prefix(&caller).next().is_some() {
+			return Err("account has active recoveries".into());
+		}
+		let config = pallet.recoverable.take(&caller).ok_or("account is not recoverable")?;
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.unreserve(&caller, config.deposit)
+	}
+
+	/// Dispatch `call` as `account`, which the caller recovered.
+	#[call_index(6)]
+	pub fn as_recovered(
+		runtime: &mut T,
+		caller: T::AccountId,
+		account: T::AccountId,
+		call: Box<<T as Config>::RuntimeCall>,
+	) -> DispatchResult {
+		let pallet: &Self = runtime.pallet();
+		if pallet.proxies.get_ref(&caller) != Some(&account) {
+			return Err("caller did not recover this account".into());
+		}
+		runtime.dispatch(RawOrigin::Signed(account), *call)
+	}
+}
+
+impl<T: Config> crate::support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> crate::support::Hooks<T> for Pallet<T> {}
+
+#[cfg(test)]
+mod tests {
+	use crate::{test_helpers::transfer, Runtime, RuntimeCall};
+
+	type Recovery = super::Pallet<Runtime>;
+
+	/// Make Alice recoverable by two of Bob, Charlie and Dave, after two blocks.
+	fn new_runtime() -> Runtime {
+		let mut runtime = Runtime::new();
+		runtime.balances.set_balance("alice".to_string(), 100);
+		runtime.balances.set_balance("eve".to_string(), 100);
+		let friends = vec!["dave".to_string(), "bob".to_string(), "charlie".to_string()];
+		Recovery::create_recovery(&mut runtime, "alice".to_string(), friends, 2, 2).unwrap();
+		runtime
+	}
+
+	#[test]
+	fn create_recovery() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(bob.clone(), 100);
+
+		let config = runtime.recovery.recovery_config(&alice).unwrap();
+		assert_eq!(config.friends, ["bob".to_string(), "charlie".to_string(), "dave".to_string()]);
+		assert_eq!(config.deposit, 13);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 13);
+		assert_eq!(
+			Recovery::create_recovery(&mut runtime, alice.clone(), vec![bob.clone()], 1, 2),
+			Err("account is already recoverable".into())
+		);
+		assert_eq!(
+			Recovery::create_recovery(&mut runtime, bob.clone(), vec![alice.clone()], 2, 2),
+			Err("threshold must be between one and the number of friends".into())
+		);
+		assert_eq!(
+			Recovery::create_recovery(&mut runtime, bob.clone(), vec![alice.clone(); 2], 1, 2),
+			Err("duplicate friends".into())
+		);
+
+		assert_eq!(Recovery::remove_recovery(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert!(runtime.recovery.recovery_config(&alice).is_none());
+	}
+
+	#[test]
+	fn vouch_and_claim_recovery() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		let eve = "eve".to_string();
+
+		assert_eq!(
+			runtime.recovery.vouch_recovery(bob.clone(), alice.clone(), eve.clone()),
+			Err("recovery was not started".into())
+		);
+		assert_eq!(Recovery::initiate_recovery(&mut runtime, eve.clone(), alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&eve), 10);
+		assert_eq!(
+			runtime.recovery.vouch_recovery(eve.clone(), alice.clone(), eve.clone()),
+			Err("not a friend of the account".into())
+		);
+		assert_eq!(
+			runtime.recovery.vouch_recovery(bob.clone(), alice.clone(), eve.clone()),
+			Ok(())
+		);
+		assert_eq!(
+			runtime.recovery.vouch_recovery(bob.clone(), alice.clone(), eve.clone()),
+			Err("already vouched".into())
+		);
+		assert_eq!(
+			Recovery::claim_recovery(&mut runtime, eve.clone(), alice.clone()),
+			Err("recovery is still delayed".into())
+		);
+
+		runtime.system.inc_block_number().unwrap();
+		runtime.system.inc_block_number().unwrap();
+		assert_eq!(
+			Recovery::claim_recovery(&mut runtime, eve.clone(), alice.clone()),
+			Err("not enough friends vouched".into())
+		);
+		assert_eq!(runtime.recovery.vouch_recovery(charlie, alice.clone(), eve.clone()), Ok(()));
+		assert_eq!(
+			Recovery::as_recovered(&mut runtime, eve.clone(), alice.clone(), transfer("eve", 50)),
+			Err("caller did not recover this account".into())
+		);
+		assert_eq!(Recovery::claim_recovery(&mut runtime, eve.clone(), alice.clone()), Ok(()));
+		assert_eq!(runtime.recovery.proxy(&eve), Some(&alice));
+
+		// Eve can now act as Alice. Closing the recovery gets the deposit back, but also ends the
+		// access of Eve to the account.
+		assert_eq!(
+			Recovery::as_recovered(&mut runtime, eve.clone(), alice.clone(), transfer("eve", 50)),
+			Ok(())
+		);
+		let close = Box::new(RuntimeCall::recovery(super::Call::close_recovery {
+			rescuer: eve.clone().into(),
+		}));
+		assert_eq!(Recovery::as_recovered(&mut runtime, eve.clone(), alice.clone(), close), Ok(()));
+		assert_eq!(runtime.recovery.proxy(&eve), None);
+		assert_eq!(
+			Recovery::as_recovered(&mut runtime, eve.clone(), alice.clone(), transfer("eve", 10)),
+			Err("caller did not recover this account".into())
+		);
+		assert_eq!(runtime.balances.balance(&alice), 47);
+		assert_eq!(runtime.balances.balance(&eve), 140);
+		assert_eq!(runtime.balances.reserved_balance(&eve), 0);
+	}
+
+	#[test]
+	fn close_recovery_removes_a_malicious_rescuer() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let eve = "eve".to_string();
+
+		Recovery::initiate_recovery(&mut runtime, eve.clone(), alice.clone()).unwrap();
+		for friend in ["bob", "charlie"] {
+			runtime
+				.recovery
+				.vouch_recovery(friend.to_string(), alice.clone(), eve.clone())
+				.unwrap();
+		}
+		runtime.system.inc_block_number().unwrap();
+		runtime.system.inc_block_number().unwrap();
+		Recovery::claim_recovery(&mut runtime, eve.clone(), alice.clone()).unwrap();
+
+		// Alice still has her key, and cuts Eve off before Eve moves her funds.
+		assert_eq!(Recovery::close_recovery(&mut runtime, alice.clone(), eve.clone()), Ok(()));
+		assert_eq!(
+			Recovery::as_recovered(&mut runtime, eve.clone(), alice.clone(), transfer("eve", 50)),
+			Err("caller did not recover this account".into())
+		);
+		assert_eq!(Recovery::remove_recovery(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.balance(&alice), 110);
+		assert_eq!(runtime.balances.balance(&eve), 90);
+	}
+
+	#[test]
+	fn close_malicious_recovery() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let eve = "eve".to_string();
+
+		assert_eq!(Recovery::initiate_recovery(&mut runtime, eve.clone(), alice.clone()), Ok(()));
+		assert_eq!(
+			Recovery::initiate_recovery(&mut runtime, eve.clone(), alice.clone()),
+			Err("recovery already started".into())
+		);
+		assert_eq!(
+			Recovery::remove_recovery(&mut runtime, alice.clone()),
+			Err("account has active recoveries".into())
+		);
+
+		// Alice notices, and takes the deposit of Eve, once she can receive it.
+		runtime.balances.set_balance(alice.clone(), u128::MAX);
+		assert_eq!(
+			Recovery::close_recovery(&mut runtime, alice.clone(), eve.clone()),
+			Err("Overflow".into())
+		);
+		assert!(runtime.recovery.active_recovery(&alice, &eve).is_some());
+		assert_eq!(runtime.balances.reserved_balance(&eve), 10);
+		runtime.balances.set_balance(alice.clone(), 87);
+		assert_eq!(Recovery::close_recovery(&mut runtime, alice.clone(), eve.clone()), Ok(()));
+		assert!(runtime.recovery.active_recovery(&alice, &eve).is_none());
+		assert_eq!(runtime.balances.balance(&alice), 97);
+		assert_eq!(runtime.balances.balance(&eve), 90);
+		assert_eq!(runtime.balances.reserved_balance(&eve), 0);
+		assert_eq!(
+			Recovery::claim_recovery(&mut runtime, eve, alice),
+			Err("recovery was not started".into())
+		);
+	}
+}
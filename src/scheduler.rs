@@ -0,0 +1,424 @@
+use crate::{
+	storage::{Hash64Concat, StorageMap, ValueQuery},
+	support::{
+		Dispatch, DispatchResult, GetPallet, GetWeight, InitializeReport, RawOrigin, Weight,
+	},
+	system::Origin,
+};
+use num::{CheckedAdd, Zero};
+
+#[macros::config]
+pub trait Config:
+	Sized
+	+ crate::system::Config
+	+ GetPallet<crate::system::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+	+ Dispatch<Caller = Origin<Self>, Call = <Self as Config>::RuntimeCall>
+{
+	/// The overarching call type, which scheduled tasks dispatch.
+	type RuntimeCall: GetWeight + Clone + std::fmt::Debug;
+	/// The maximum weight of the tasks dispatched in a single block. Tasks which do not fit are
+	/// carried over to the next block.
+	const MAX_WEIGHT: Weight;
+}
+
+/// The priority of a task. Tasks with a lower value are dispatched first.
+pub type Priority = u8;
+
+/// The name of a named task, which can be used to cancel it.
+pub type TaskName = Vec<u8>;
+
+/// How often a periodic task runs: every `period` blocks, `count` times in total.
+pub type Period<BlockNumber> = (BlockNumber, u32);
+
+/// Where a task is stored: the block it is scheduled for, and its index in that block's agenda.
+pub type TaskAddress<BlockNumber> = (BlockNumber, u32);
+
+/// A call scheduled to be dispatched at a future block.
+#[derive(Debug, macros::TypeInfo)]
+pub struct Scheduled<T: Config> {
+	pub maybe_id: Option<TaskName>,
+	pub priority: Priority,
+	pub call: <T as Config>::RuntimeCall,
+	pub maybe_periodic: Option<Period<T::BlockNumber>>,
+	/// The origin which scheduled the task, and which the call is dispatched with.
+	pub origin: Origin<T>,
+}
+
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	/// The tasks scheduled for each block. Dispatched and cancelled tasks leave an empty slot, so
+	/// the address of other tasks does not change.
+	agenda: StorageMap<Hash64Concat, T::BlockNumber, Vec<Option<Scheduled<T>>>, ValueQuery>,
+	/// The address of each named task.
+	#[getter(lookup)]
+	lookup: StorageMap<Hash64Concat, TaskName, TaskAddress<T::BlockNumber>>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn task(&self, address: TaskAddress<T::BlockNumber>) -> Option<&Scheduled<T>> {
+		let (when, index) = address;
+		self.agenda.get_ref(&when)?.get(index as usize)?.as_ref()
+	}
+
+	/// Schedule `call` to be dispatched with `origin` at block `when`.
+	///
+	/// This can be used by other pallets to schedule calls with any origin.
+	pub fn do_schedule(
+		runtime: &mut T,
+		maybe_id: Option<TaskName>,
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		origin: Origin<T>,
+		call: <T as Config>::RuntimeCall,
+	) -> Result<TaskAddress<T::BlockNumber>, String> {
+		let system: &crate::system::Pallet<T> = runtime.pallet();
+		if when <= system.block_number() {
+			return Err("scheduled block is in the past".to_string());
+		}
+		if let Some((period, count)) = maybe_periodic {
+			if period.is_zero() || count == 0 {
+				return Err("invalid period".to_string());
+			}
+		}
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		if let Some(id) = &maybe_id {
+			if pallet.lookup.contains_key(id) {
+				return Err("a task with this name already exists".to_string());
+			}
+		}
+
+		let task = Scheduled { maybe_id, priority, call, maybe_periodic, origin };
+		Ok(pallet.insert(when, task))
+	}
+
+	/// Cancel the task at `address`. Only the origin which scheduled the task, or root, can cancel
+	/// it.
+	pub fn do_cancel(
+		&mut self,
+		origin: &Origin<T>,
+		address: TaskAddress<T::BlockNumber>,
+	) -> Result<Scheduled<T>, String> {
+		let task = self.task(address).ok_or("task does not exist")?;
+		if *origin != RawOrigin::Root && *origin != task.origin {
+			return Err("only the origin which scheduled the task can cancel it".to_string());
+		}
+		Ok(self.take(address).expect("checked above; qed"))
+	}
+
+	/// Store a task in the agenda of block `when`, and return its address.
+	fn insert(&mut self, when: T::BlockNumber, task: Scheduled<T>) -> TaskAddress<T::BlockNumber> {
+		let address = (when, self.agenda.get_ref(&when).map_or(0, Vec::len) as u32);
+		if let Some(id) = &task.maybe_id {
+			self.lookup.insert(id.clone(), address);
+		}
+		self.agenda.mutate(&when, |agenda| agenda.push(Some(task)));
+		address
+	}
+
+	/// Remove the task at `address` from the agenda.
+	fn take(&mut self, address: TaskAddress<T::BlockNumber>) -> Option<Scheduled<T>> {
+		let (when, index) = address;
+		let agenda = self.agenda.get_mut(&when)?;
+		let task = agenda.get_mut(index as usize)?.take()?;
+		if agenda.iter().all(|task| task.is_none()) {
+			self.agenda.remove(&when);
+		}
+		if let Some(id) = &task.maybe_id {
+			self.lookup.remove(id);
+		}
+		Some(task)
+	}
+}
+
+// The calls are boxed, since `Call` would otherwise contain itself through `RuntimeCall`.
+#[allow(clippy::boxed_local)]
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Schedule `call` to be dispatched at block `when`, with the origin of this call.
+	#[call_index(0)]
+	pub fn schedule(
+		runtime: &mut T,
+		origin: Origin<T>,
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		call: Box<<T as Config>::RuntimeCall>,
+	) -> DispatchResult {
+		if origin == RawOrigin::None {
+			return Err("bad origin: tasks cannot be scheduled without an origin".into());
+		}
+		Self::do_schedule(runtime, None, when, maybe_periodic, priority, origin, *call)?;
+		Ok(())
+	}
+
+	/// Cancel the task at index `index` of block `when`.
+	#[call_index(1)]
+	pub fn cancel(
+		runtime: &mut T,
+		origin: Origin<T>,
+		when: T::BlockNumber,
+		index: u32,
+	) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.do_cancel(&origin, (when, index))?;
+		Ok(())
+	}
+
+	/// Schedule `call` like `schedule`, with a name which can be used to cancel it.
+	#[call_index(2)]
+	pub fn schedule_named(
+		runtime: &mut T,
+		origin: Origin<T>,
+		id: TaskName,
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		call: Box<<T as Config>::RuntimeCall>,
+	) -> DispatchResult {
+		if origin == RawOrigin::None {
+			return Err("bad origin: tasks cannot be scheduled without an origin".into());
+		}
+		Self::do_schedule(runtime, Some(id), when, maybe_periodic, priority, origin, *call)?;
+		Ok(())
+	}
+
+	/// Cancel the task named `id`.
+	#[call_index(3)]
+	pub fn cancel_named(runtime: &mut T, origin: Origin<T>, id: TaskName) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let address = pallet.lookup(&id).ok_or("task does not exist")?;
+		pallet.do_cancel(&origin, address)?;
+		Ok(())
+	}
+}
+
+impl<T: Config> crate::support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> crate::support::Hooks<T> for Pallet<T> {
+	/// Dispatch the tasks which are due, by priority, until the weight limit is reached. The
+	/// remaining tasks stay in the agenda and are dispatched in the next blocks. The errors of the
+	/// tasks which failed are reported, with the block and the index in its agenda of each task.
+	fn on_initialize(runtime: &mut T, block_number: T::BlockNumber) -> InitializeReport {
+		let pallet: &Self = runtime.pallet();
+		let mut due = pallet
+			.agenda
+			.iter()
+			.take_while(|(when, _)| **when <= block_number)
+			.flat_map(|(when, agenda)| {
+				agenda.iter().enumerate().filter_map(move |(index, task)| {
+					task.as_ref().map(|task| (task.priority, *when, index as u32))
+				})
+			})
+			.collect::<Vec<_>>();
+		due.sort();
+
+		let mut weight: Weight = 0;
+		let mut failed = Vec::new();
+		for (_, when, index) in due {
+			let pallet: &mut Self = runtime.pallet_mut();
+			let task_weight = pallet.task((when, index)).expect("task is due; qed").call.weight();
+			if weight.saturating_add(task_weight) > T::MAX_WEIGHT {
+				break;
+			}
+			weight += task_weight;
+
+			let task = pallet.take((when, index)).expect("task is due; qed");
+			let Scheduled { maybe_id, priority, call, maybe_periodic, origin } = task;
+
+			// Periodic tasks are scheduled again, until they ran `count` times.
+			if let Some((period, count)) = maybe_periodic {
+				if let Some(next) = when.checked_add(&period).filter(|_| count > 1) {
+					let maybe_periodic = Some((period, count - 1));
+					let call = call.clone();
+					let origin = origin.clone();
+					let task = Scheduled { maybe_id, priority, call, maybe_periodic, origin };
+					pallet.insert(next, task);
+				}
+			}
+
+			// A task which fails is not retried. Its error says which task it was.
+			if let Err(e) = runtime.dispatch(origin, call) {
+				let msg = format!("scheduled task {:?} failed: {}", (when, index), e);
+				failed.push(msg.into());
+			}
+		}
+		InitializeReport { weight, failed }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		support::{self, Hooks, RawOrigin},
+		test_helpers::transfer,
+		types, Runtime, RuntimeCall,
+	};
+
+	type Scheduler = super::Pallet<Runtime>;
+
+	#[test]
+	fn schedule_call() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+
+		assert_eq!(
+			Scheduler::schedule(
+				&mut runtime,
+				RawOrigin::Signed(alice.clone()),
+				0,
+				None,
+				0,
+				transfer("bob", 10)
+			),
+			Err("scheduled block is in the past".into())
+		);
+		assert_eq!(
+			Scheduler::schedule(&mut runtime, RawOrigin::None, 2, None, 0, transfer("bob", 10)),
+			Err("bad origin: tasks cannot be scheduled without an origin".into())
+		);
+		assert_eq!(
+			Scheduler::schedule(
+				&mut runtime,
+				RawOrigin::Signed(alice.clone()),
+				2,
+				None,
+				0,
+				transfer("bob", 10)
+			),
+			Ok(())
+		);
+
+		// The call is dispatched at block 2, as Alice.
+		Scheduler::on_initialize(&mut runtime, 1);
+		assert_eq!(runtime.balances.balance(&bob), 0);
+		Scheduler::on_initialize(&mut runtime, 2);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+		assert_eq!(runtime.balances.balance(&alice), 90);
+		assert!(runtime.scheduler.task((2, 0)).is_none());
+	}
+
+	#[test]
+	fn tasks_over_the_weight_limit_are_carried_over() {
+		let mut runtime = Runtime::new();
+		let alice = RawOrigin::Signed("alice".to_string());
+		runtime.balances.set_balance("alice".to_string(), 100);
+
+		// Only two tasks fit in a block. Lower priority values are dispatched first.
+		assert_eq!(
+			Scheduler::schedule(&mut runtime, alice.clone(), 1, None, 2, transfer("bob", 1)),
+			Ok(())
+		);
+		assert_eq!(
+			Scheduler::schedule(&mut runtime, alice.clone(), 1, None, 1, transfer("charlie", 1)),
+			Ok(())
+		);
+		assert_eq!(
+			Scheduler::schedule(&mut runtime, alice, 1, None, 0, transfer("dave", 1)),
+			Ok(())
+		);
+
+		assert_eq!(
+			Scheduler::on_initialize(&mut runtime, 1).weight,
+			2 * crate::support::CALL_WEIGHT
+		);
+		assert_eq!(runtime.balances.balance(&"dave".to_string()), 1);
+		assert_eq!(runtime.balances.balance(&"charlie".to_string()), 1);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 0);
+		assert!(runtime.scheduler.task((1, 0)).is_some());
+
+		assert_eq!(Scheduler::on_initialize(&mut runtime, 2).weight, crate::support::CALL_WEIGHT);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 1);
+		assert!(runtime.scheduler.task((1, 0)).is_none());
+	}
+
+	#[test]
+	fn failed_tasks_are_reported() {
+		let mut runtime = Runtime::new();
+		let alice = RawOrigin::Signed("alice".to_string());
+		runtime.balances.set_balance("alice".to_string(), 100);
+
+		// Alice cannot pay Charlie once she paid Bob, which is dispatched first.
+		assert_eq!(
+			Scheduler::schedule(&mut runtime, alice.clone(), 1, None, 1, transfer("charlie", 60)),
+			Ok(())
+		);
+		assert_eq!(
+			Scheduler::schedule(&mut runtime, alice, 1, None, 0, transfer("bob", 60)),
+			Ok(())
+		);
+
+		let block = types::Block {
+			header: support::Header { block_number: 1 },
+			extrinsics: vec![support::Extrinsic {
+				caller: None,
+				call: RuntimeCall::timestamp(crate::timestamp::Call::set { now: 6_000 }),
+			}],
+		};
+		let report = runtime.execute_block(block).expect("valid block");
+		assert_eq!(
+			report.initialize_failed,
+			[("scheduler", "scheduled task (1, 0) failed: Not enough funds.".into())]
+		);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 60);
+		assert_eq!(runtime.balances.balance(&"charlie".to_string()), 0);
+		// The task which failed is not retried.
+		assert!(runtime.scheduler.task((1, 0)).is_none());
+	}
+
+	#[test]
+	fn periodic_named_task() {
+		let mut runtime = Runtime::new();
+		let alice = RawOrigin::Signed("alice".to_string());
+		let id = b"payout".to_vec();
+		runtime.balances.set_balance("alice".to_string(), 100);
+
+		// Pay Bob every 2 blocks, 3 times, starting at block 1.
+		assert_eq!(
+			Scheduler::schedule_named(
+				&mut runtime,
+				alice.clone(),
+				id.clone(),
+				1,
+				Some((2, 3)),
+				0,
+				transfer("bob", 5)
+			),
+			Ok(())
+		);
+		assert_eq!(
+			Scheduler::schedule_named(
+				&mut runtime,
+				alice.clone(),
+				id.clone(),
+				1,
+				None,
+				0,
+				transfer("bob", 5)
+			),
+			Err("a task with this name already exists".into())
+		);
+
+		for block_number in 1..=4 {
+			Scheduler::on_initialize(&mut runtime, block_number);
+		}
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 10);
+		assert_eq!(runtime.scheduler.lookup(&id), Some((5, 0)));
+
+		// Only Alice or root can cancel the task.
+		assert_eq!(
+			Scheduler::cancel_named(&mut runtime, RawOrigin::Signed("bob".to_string()), id.clone()),
+			Err("only the origin which scheduled the task can cancel it".into())
+		);
+		assert_eq!(Scheduler::cancel_named(&mut runtime, RawOrigin::Root, id.clone()), Ok(()));
+		assert_eq!(runtime.scheduler.lookup(&id), None);
+
+		Scheduler::on_initialize(&mut runtime, 5);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 10);
+	}
+}
@@ -0,0 +1,565 @@
+use crate::{
+	storage::{Hash64Concat, StorageMap, StorageValue, ValueQuery},
+	support::{
+		self, DispatchError, DispatchResult, EnsureOrigin, GetPallet, InitializeReport, Percent,
+	},
+	system::Origin,
+};
+use num::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Zero};
+use std::collections::BTreeMap;
+
+/// The lock which holds the bonded balance of stakers.
+const STAKING_ID: crate::balances::LockIdentifier = *b"staking ";
+
+#[macros::config]
+pub trait Config:
+	crate::balances::Config<Balance: From<u32> + CheckedMul + CheckedDiv>
+	+ crate::treasury::Config
+	+ GetPallet<Pallet<Self>>
+{
+	/// The origin which may slash validators for offenses.
+	type SlashOrigin: EnsureOrigin<Origin<Self>>;
+	/// How long an era is, in blocks.
+	const ERA_LENGTH: Self::BlockNumber;
+	/// How many validators are elected for each era.
+	const VALIDATOR_COUNT: u32;
+	/// How many eras unbonded funds stay locked.
+	const BONDING_DURATION: EraIndex;
+	/// The reward shared by the validators and their nominators at the end of each era.
+	const ERA_REWARD: Self::Balance;
+}
+
+/// The index of an era.
+pub type EraIndex = u32;
+
+/// Funds which are unbonding, and can be withdrawn from era `era`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, macros::TypeInfo)]
+pub struct UnlockChunk<Balance> {
+	pub value: Balance,
+	pub era: EraIndex,
+}
+
+/// The funds bonded by a staker.
+#[derive(Debug, Clone, PartialEq, Eq, macros::TypeInfo)]
+pub struct StakingLedger<Balance> {
+	/// All the locked funds, including the unbonding ones.
+	pub total: Balance,
+	/// The funds at stake, which can be slashed.
+	pub active: Balance,
+	pub unlocking: Vec<UnlockChunk<Balance>>,
+}
+
+/// The stake backing an elected validator.
+#[derive(Debug, Clone, PartialEq, Eq, macros::TypeInfo)]
+pub struct Exposure<AccountId, Balance> {
+	pub total: Balance,
+	/// The stake of the validator itself.
+	pub own: Balance,
+	/// The stake of each nominator backing the validator.
+	pub others: Vec<(AccountId, Balance)>,
+}
+
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	ledger: StorageMap<Hash64Concat, T::AccountId, StakingLedger<T::Balance>>,
+	/// The accounts which want to validate, and the commission they take from rewards.
+	validators: StorageMap<Hash64Concat, T::AccountId, Percent>,
+	/// The accounts which want to nominate, and the validators they back.
+	nominators: StorageMap<Hash64Concat, T::AccountId, Vec<T::AccountId>>,
+	#[getter(current_era)]
+	current_era: StorageValue<EraIndex, ValueQuery>,
+	/// The validators elected for the current era, and their backing.
+	exposures: StorageMap<Hash64Concat, T::AccountId, Exposure<T::AccountId, T::Balance>>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn ledger(&self, who: &T::AccountId) -> Option<&StakingLedger<T::Balance>> {
+		self.ledger.get_ref(who)
+	}
+
+	pub fn exposure(
+		&self,
+		validator: &T::AccountId,
+	) -> Option<&Exposure<T::AccountId, T::Balance>> {
+		self.exposures.get_ref(validator)
+	}
+
+	/// The validators elected for the current era.
+	pub fn active_validators(&self) -> Vec<&T::AccountId> {
+		self.exposures.iter_keys().collect()
+	}
+
+	/// The active stake of `who`, or zero if they are not bonded.
+	fn active(&self, who: &T::AccountId) -> T::Balance {
+		self.ledger.get_ref(who).map_or(Zero::zero(), |ledger| ledger.active)
+	}
+
+	/// Lock the total bonded funds of `who`, and forget their ledger once nothing is bonded.
+	fn update_ledger(runtime: &mut T, who: &T::AccountId) {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let total = pallet.ledger.get_ref(who).map_or(Zero::zero(), |ledger| ledger.total);
+		if total.is_zero() {
+			pallet.ledger.remove(who);
+			pallet.validators.remove(who);
+			pallet.nominators.remove(who);
+		}
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.set_lock(STAKING_ID, who, total);
+	}
+
+	/// Elect the `VALIDATOR_COUNT` validators with the most backing. The stake of each nominator
+	/// is split evenly between the validators it nominates.
+	fn elect(&mut self) {
+		let mut exposures = self
+			.validators
+			.iter_keys()
+			.map(|validator| {
+				let own = self.active(validator);
+				(validator.clone(), Exposure { total: own, own, others: Vec::new() })
+			})
+			.collect::<BTreeMap<_, _>>();
+
+		for (nominator, targets) in self.nominators.iter() {
+			let targets = targets
+				.iter()
+				.filter(|target| exposures.contains_key(*target))
+				.collect::<Vec<_>>();
+			if targets.is_empty() {
+				continue;
+			}
+			let share = self.active(nominator) / T::Balance::from(targets.len() as u32);
+			for target in targets {
+				let exposure = exposures.get_mut(target).expect("filtered above; qed");
+				exposure.total = exposure.total + share;
+				exposure.others.push((nominator.clone(), share));
+			}
+		}
+
+		// On ties, the validator with the smallest account wins.
+		let mut candidates = exposures
+			.into_iter()
+			.filter(|(_, exposure)| !exposure.total.is_zero())
+			.collect::<Vec<_>>();
+		candidates.sort_by_key(|(_, exposure)| std::cmp::Reverse(exposure.total));
+		candidates.truncate(T::VALIDATOR_COUNT as usize);
+		self.exposures.clear();
+		for (validator, exposure) in candidates {
+			self.exposures.insert(validator, exposure);
+		}
+	}
+
+	/// Pay the reward of the current era. It is shared between the elected validators in
+	/// proportion to their backing, and then between each validator and its nominators in
+	/// proportion to their stake, after the commission of the validator.
+	///
+	/// Nothing is paid if any of the payouts overflows.
+	fn pay_rewards(runtime: &mut T) -> DispatchResult {
+		// The share `numerator / denominator` of `value`.
+		let share = |value: T::Balance, numerator: T::Balance, denominator: T::Balance| {
+			value
+				.checked_mul(&numerator)
+				.and_then(|value| value.checked_div(&denominator))
+				.ok_or("reward overflow")
+		};
+
+		let pallet: &Self = runtime.pallet();
+		let total_stake = pallet
+			.exposures
+			.iter_values()
+			.try_fold(T::Balance::zero(), |total, exposure| total.checked_add(&exposure.total))
+			.ok_or("reward overflow")?;
+		if total_stake.is_zero() {
+			return Ok(());
+		}
+
+		// Nominators of several validators get a payout from each of them.
+		let mut payouts = BTreeMap::<T::AccountId, T::Balance>::new();
+		let mut pay = |who: &T::AccountId, amount: T::Balance| -> DispatchResult {
+			let payout = payouts.entry(who.clone()).or_insert_with(Zero::zero);
+			*payout = payout.checked_add(&amount).ok_or("reward overflow")?;
+			Ok(())
+		};
+		for (validator, exposure) in pallet.exposures.iter() {
+			let reward = share(T::ERA_REWARD, exposure.total, total_stake)?;
+			let commission = pallet
+				.validators
+				.get_ref(validator)
+				.map_or(Zero::zero(), |commission| commission.mul_floor(reward));
+			let rest = reward.checked_sub(&commission).ok_or("reward overflow")?;
+
+			pay(validator, commission)?;
+			pay(validator, share(rest, exposure.own, exposure.total)?)?;
+			for (nominator, stake) in &exposure.others {
+				pay(nominator, share(rest, *stake, exposure.total)?)?;
+			}
+		}
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		for (who, amount) in &payouts {
+			balances.balance(who).checked_add(amount).ok_or("reward overflow")?;
+		}
+		for (who, amount) in payouts {
+			balances.deposit(&who, amount).expect("checked above; qed");
+		}
+		Ok(())
+	}
+
+	/// End the current era: pay its rewards, then start the next one with newly elected
+	/// validators. The next era starts even if the rewards cannot be paid, so that the validators
+	/// are not frozen. Returns the errors of what failed.
+	fn new_era(runtime: &mut T) -> Vec<DispatchError> {
+		let mut failed = Vec::new();
+		if let Err(e) = Self::pay_rewards(runtime) {
+			failed.push(e);
+		}
+		let pallet: &mut Self = runtime.pallet_mut();
+		match pallet.current_era().checked_add(1) {
+			Some(era) => pallet.current_era.put(era),
+			None => failed.push("era overflow".into()),
+		}
+		pallet.elect();
+		failed
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Bond `value` of the free balance of the caller. The funds are locked, and can be used to
+	/// validate or nominate.
+	#[call_index(0)]
+	pub fn bond(runtime: &mut T, caller: T::AccountId, value: T::Balance) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		if pallet.ledger.contains_key(&caller) {
+			return Err("already bonded".into());
+		}
+		if value.is_zero() {
+			return Err("cannot bond nothing".into());
+		}
+		let balances: &crate::balances::Pallet<T> = runtime.pallet();
+		if value > balances.balance(&caller) {
+			return Err("not enough funds to bond".into());
+		}
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let ledger = StakingLedger { total: value, active: value, unlocking: Vec::new() };
+		pallet.ledger.insert(caller.clone(), ledger);
+		Self::update_ledger(runtime, &caller);
+		Ok(())
+	}
+
+	/// Start unbonding `value` of the active stake. The funds can be withdrawn after
+	/// `BONDING_DURATION` eras.
+	#[call_index(1)]
+	pub fn unbond(&mut self, caller: T::AccountId, value: T::Balance) -> DispatchResult {
+		let era = self.current_era().checked_add(T::BONDING_DURATION).ok_or("era overflow")?;
+		let ledger = self.ledger.get_mut(&caller).ok_or("not bonded")?;
+		ledger.active = ledger.active.checked_sub(&value).ok_or("not enough bonded funds")?;
+		ledger.unlocking.push(UnlockChunk { value, era });
+		Ok(())
+	}
+
+	/// Unlock the funds which finished unbonding.
+	#[call_index(2)]
+	pub fn withdraw_unbonded(runtime: &mut T, caller: T::AccountId) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		let current_era = pallet.current_era();
+		let ledger = pallet.ledger.get_mut(&caller).ok_or("not bonded")?;
+
+		let (unlocked, unlocking): (Vec<_>, _) = std::mem::take(&mut ledger.unlocking)
+			.into_iter()
+			.partition(|chunk| chunk.era <= current_era);
+		let value = unlocked
+			.into_iter()
+			.fold(T::Balance::zero(), |total, chunk| total + chunk.value);
+		ledger.total = ledger.total - value;
+		ledger.unlocking = unlocking;
+
+		Self::update_ledger(runtime, &caller);
+		Ok(())
+	}
+
+	/// Ask to be elected as a validator, taking `commission` from the rewards of nominators.
+	#[call_index(3)]
+	pub fn validate(&mut self, caller: T::AccountId, commission: Percent) -> DispatchResult {
+		if !self.ledger.contains_key(&caller) {
+			return Err("not bonded".into());
+		}
+		self.nominators.remove(&caller);
+		self.validators.insert(caller, commission);
+		Ok(())
+	}
+
+	/// Back the validators in `targets` with the active stake of the caller.
+	#[call_index(4)]
+	pub fn nominate(&mut self, caller: T::AccountId, targets: Vec<T::AccountId>) -> DispatchResult {
+		if !self.ledger.contains_key(&caller) {
+			return Err("not bonded".into());
+		}
+		if targets.is_empty() {
+			return Err("no targets".into());
+		}
+		self.validators.remove(&caller);
+		self.nominators.insert(caller, targets);
+		Ok(())
+	}
+
+	/// Stop validating or nominating from the next era.
+	#[call_index(5)]
+	pub fn chill(&mut self, caller: T::AccountId) -> DispatchResult {
+		if !self.ledger.contains_key(&caller) {
+			return Err("not bonded".into());
+		}
+		self.validators.remove(&caller);
+		self.nominators.remove(&caller);
+		Ok(())
+	}
+
+	/// Slash `fraction` of the stake exposed behind an active validator, for an offense. Only
+	/// `SlashOrigin` may slash.
+	///
+	/// The validator and its nominators lose the same fraction of their exposure. The slashed
+	/// funds are handled by the treasury. Nothing is slashed if any of the stakers cannot be, for
+	/// example because of their other locks.
+	#[call_index(6)]
+	pub fn slash(
+		runtime: &mut T,
+		origin: Origin<T>,
+		validator: T::AccountId,
+		fraction: Percent,
+	) -> DispatchResult {
+		T::SlashOrigin::ensure_origin(origin)?;
+		let pallet: &mut Self = runtime.pallet_mut();
+		let exposure = pallet.exposures.get_ref(&validator).ok_or("not an active validator")?;
+		let stakers = std::iter::once((validator.clone(), exposure.own))
+			.chain(exposure.others.iter().cloned())
+			.collect::<Vec<_>>();
+
+		// Every slash is checked before any staker is slashed.
+		let mut slashes = Vec::new();
+		let mut slashed = T::Balance::zero();
+		for (staker, stake) in stakers {
+			let pallet: &Self = runtime.pallet();
+			let Some(ledger) = pallet.ledger.get_ref(&staker) else { continue };
+			// Funds may have been unbonded since the era started.
+			let value = fraction.mul_floor(stake).min(ledger.active);
+			let balances: &crate::balances::Pallet<T> = runtime.pallet();
+			balances.ensure_can_withdraw_locked(STAKING_ID, &staker, value)?;
+			slashed = slashed.checked_add(&value).ok_or("slash overflow")?;
+			slashes.push((staker, value));
+		}
+
+		for (staker, value) in slashes {
+			let pallet: &mut Self = runtime.pallet_mut();
+			let ledger = pallet.ledger.get_mut(&staker).expect("checked above; qed");
+			ledger.active = ledger.active - value;
+			ledger.total = ledger.total - value;
+
+			Self::update_ledger(runtime, &staker);
+			let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+			balances.withdraw(&staker, value).expect("checked above; qed");
+		}
+
+		crate::treasury::Pallet::<T>::on_unbalanced(runtime, slashed)
+	}
+}
+
+impl<T: Config> support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> support::Hooks<T> for Pallet<T> {
+	/// Start a new era every `ERA_LENGTH` blocks.
+	fn on_initialize(runtime: &mut T, block_number: T::BlockNumber) -> InitializeReport {
+		if !(block_number % T::ERA_LENGTH).is_zero() {
+			return InitializeReport::default();
+		}
+		InitializeReport { weight: support::CALL_WEIGHT, failed: Self::new_era(runtime) }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Exposure, StakingLedger, UnlockChunk};
+	use crate::{
+		support::{DispatchError, Hooks, Percent, RawOrigin},
+		Runtime,
+	};
+
+	type Staking = super::Pallet<Runtime>;
+
+	/// Run the hooks of the first block of the next era, and return their errors.
+	fn start_next_era(runtime: &mut Runtime) -> Vec<DispatchError> {
+		let block_number = (runtime.staking.current_era() + 1) * 10;
+		Staking::on_initialize(runtime, block_number).failed
+	}
+
+	#[test]
+	fn bond_and_unbond() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+
+		assert_eq!(
+			Staking::bond(&mut runtime, alice.clone(), 101),
+			Err("not enough funds to bond".into())
+		);
+		assert_eq!(Staking::bond(&mut runtime, alice.clone(), 80), Ok(()));
+		assert_eq!(Staking::bond(&mut runtime, alice.clone(), 10), Err("already bonded".into()));
+		assert_eq!(runtime.balances.locked_balance(&alice), 80);
+
+		assert_eq!(
+			runtime.staking.unbond(alice.clone(), 81),
+			Err("not enough bonded funds".into())
+		);
+		assert_eq!(runtime.staking.unbond(alice.clone(), 30), Ok(()));
+		assert_eq!(
+			runtime.staking.ledger(&alice),
+			Some(&StakingLedger {
+				total: 80,
+				active: 50,
+				unlocking: vec![UnlockChunk { value: 30, era: 2 }]
+			})
+		);
+
+		// The funds stay locked until the bonding duration is over.
+		start_next_era(&mut runtime);
+		assert_eq!(Staking::withdraw_unbonded(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.locked_balance(&alice), 80);
+		start_next_era(&mut runtime);
+		assert_eq!(Staking::withdraw_unbonded(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.locked_balance(&alice), 50);
+
+		// Once everything is withdrawn, the account is not bonded anymore.
+		assert_eq!(runtime.staking.unbond(alice.clone(), 50), Ok(()));
+		start_next_era(&mut runtime);
+		start_next_era(&mut runtime);
+		assert_eq!(Staking::withdraw_unbonded(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.locked_balance(&alice), 0);
+		assert!(runtime.staking.ledger(&alice).is_none());
+	}
+
+	#[test]
+	fn elect_and_reward() {
+		let mut runtime = Runtime::new();
+		let [alice, bob, charlie, dave] = ["alice", "bob", "charlie", "dave"].map(String::from);
+		for (who, stake) in [(&alice, 100), (&bob, 50), (&charlie, 60), (&dave, 100)] {
+			runtime.balances.set_balance(who.clone(), 100);
+			assert_eq!(Staking::bond(&mut runtime, who.clone(), stake), Ok(()));
+		}
+
+		assert_eq!(
+			runtime.staking.validate("eve".to_string(), Percent::from_percent(0)),
+			Err("not bonded".into())
+		);
+		assert_eq!(runtime.staking.validate(alice.clone(), Percent::from_percent(0)), Ok(()));
+		assert_eq!(runtime.staking.validate(bob.clone(), Percent::from_percent(10)), Ok(()));
+		assert_eq!(runtime.staking.validate(charlie.clone(), Percent::from_percent(0)), Ok(()));
+		assert_eq!(runtime.staking.nominate(dave.clone(), vec![]), Err("no targets".into()));
+		assert_eq!(runtime.staking.nominate(dave.clone(), vec![bob.clone()]), Ok(()));
+
+		// Bob has the most backing, thanks to Dave. Charlie is not elected.
+		start_next_era(&mut runtime);
+		assert_eq!(runtime.staking.active_validators(), vec![&alice, &bob]);
+		assert_eq!(
+			runtime.staking.exposure(&bob),
+			Some(&Exposure { total: 150, own: 50, others: vec![(dave.clone(), 100)] })
+		);
+
+		// The reward of 100 is shared 60 for Bob and 40 for Alice. Bob takes 6 of commission, and
+		// shares the rest with Dave.
+		start_next_era(&mut runtime);
+		assert_eq!(runtime.balances.balance(&alice), 140);
+		assert_eq!(runtime.balances.balance(&bob), 124);
+		assert_eq!(runtime.balances.balance(&dave), 136);
+		assert_eq!(runtime.balances.balance(&charlie), 100);
+	}
+
+	#[test]
+	fn failed_rewards_do_not_stop_the_era() {
+		let mut runtime = Runtime::new();
+		let [alice, bob] = ["alice", "bob"].map(String::from);
+		for who in [&alice, &bob] {
+			runtime.balances.set_balance(who.clone(), 100);
+			assert_eq!(Staking::bond(&mut runtime, who.clone(), 100), Ok(()));
+		}
+		assert_eq!(runtime.staking.validate(alice.clone(), Percent::from_percent(0)), Ok(()));
+		assert_eq!(start_next_era(&mut runtime), []);
+		assert_eq!(runtime.staking.active_validators(), vec![&alice]);
+
+		// Alice cannot receive her reward, so nobody is paid, but the next era still starts, with
+		// Bob elected too.
+		runtime.balances.set_balance(alice.clone(), u128::MAX);
+		assert_eq!(runtime.staking.validate(bob.clone(), Percent::from_percent(0)), Ok(()));
+		assert_eq!(start_next_era(&mut runtime), ["reward overflow".into()]);
+		assert_eq!(runtime.staking.current_era(), 2);
+		assert_eq!(runtime.staking.active_validators(), vec![&alice, &bob]);
+		assert_eq!(runtime.balances.balance(&bob), 100);
+	}
+
+	#[test]
+	fn slash_validator_and_nominators() {
+		let mut runtime = Runtime::new();
+		let [bob, dave] = ["bob", "dave"].map(String::from);
+		runtime.balances.set_balance(bob.clone(), 100);
+		runtime.balances.set_balance(dave.clone(), 100);
+		assert_eq!(Staking::bond(&mut runtime, bob.clone(), 50), Ok(()));
+		assert_eq!(Staking::bond(&mut runtime, dave.clone(), 100), Ok(()));
+		assert_eq!(runtime.staking.validate(bob.clone(), Percent::from_percent(10)), Ok(()));
+		assert_eq!(runtime.staking.nominate(dave.clone(), vec![bob.clone()]), Ok(()));
+
+		assert_eq!(
+			Staking::slash(&mut runtime, RawOrigin::Root, bob.clone(), Percent::from_percent(10)),
+			Err("not an active validator".into())
+		);
+		start_next_era(&mut runtime);
+		assert_eq!(
+			Staking::slash(
+				&mut runtime,
+				RawOrigin::Signed(dave.clone()),
+				bob.clone(),
+				Percent::from_percent(10)
+			),
+			Err("bad origin: expected enough approvals of the council".into())
+		);
+		assert_eq!(
+			Staking::slash(&mut runtime, RawOrigin::Root, bob.clone(), Percent::from_percent(10)),
+			Ok(())
+		);
+
+		assert_eq!(runtime.balances.balance(&bob), 95);
+		assert_eq!(runtime.balances.locked_balance(&bob), 45);
+		assert_eq!(runtime.balances.balance(&dave), 90);
+		assert_eq!(runtime.staking.ledger(&dave).map(|ledger| ledger.active), Some(90));
+		// The treasury takes its share of the slash.
+		assert_eq!(crate::treasury::Pallet::<Runtime>::pot(&runtime), 12);
+	}
+
+	#[test]
+	fn slash_fails_on_other_locks() {
+		let mut runtime = Runtime::new();
+		let [bob, dave] = ["bob", "dave"].map(String::from);
+		runtime.balances.set_balance(bob.clone(), 100);
+		runtime.balances.set_balance(dave.clone(), 100);
+		assert_eq!(Staking::bond(&mut runtime, bob.clone(), 50), Ok(()));
+		assert_eq!(Staking::bond(&mut runtime, dave.clone(), 100), Ok(()));
+		assert_eq!(runtime.staking.validate(bob.clone(), Percent::from_percent(10)), Ok(()));
+		assert_eq!(runtime.staking.nominate(dave.clone(), vec![bob.clone()]), Ok(()));
+		start_next_era(&mut runtime);
+
+		// All of Dave's balance is also locked by another pallet, so he cannot be slashed, and
+		// neither is Bob.
+		runtime.balances.set_lock(*b"otherloc", &dave, 100);
+		assert_eq!(
+			Staking::slash(&mut runtime, RawOrigin::Root, bob.clone(), Percent::from_percent(10)),
+			Err("Liquidity restrictions prevent withdrawal.".into())
+		);
+		assert_eq!(runtime.balances.balance(&bob), 100);
+		assert_eq!(runtime.balances.locked_balance(&bob), 50);
+		assert_eq!(runtime.balances.balance(&dave), 100);
+		assert_eq!(
+			runtime.staking.ledger(&dave),
+			Some(&StakingLedger { total: 100, active: 100, unlocking: vec![] })
+		);
+		assert_eq!(crate::treasury::Pallet::<Runtime>::pot(&runtime), 0);
+	}
+}
@@ -0,0 +1,545 @@
+//! Typed storage items for pallets, declared with `#[macros::storage]`.
+//!
+//! Every item has a unique prefix, made of the hashes of the name of its pallet and its own name.
+//! The key of a value is its prefix, and the key of a map entry is the prefix followed by the
+//! hashed keys of the entry, so that every pallet lays out its state in the same way. The values
+//! themselves are kept in memory, typed.
+//!
+//! Reading an item depends on its query kind: a `ValueQuery` returns a value, which is the default
+//! value when the item is empty, and an `OptionQuery` returns `None` when the item is empty. Since
+//! the values are kept in memory, they can also be borrowed with `get_ref` and `get_mut`, whatever
+//! the query kind.
+
+// This is the full API of the storage items, and not every part of it is used by the pallets.
+#![allow(dead_code)]
+
+use crate::{codec::Encode, support};
+use core::{fmt::Debug, marker::PhantomData};
+use std::collections::BTreeMap;
+
+/// The prefix of the keys of a storage item.
+pub type Prefix = [u8; 16];
+
+/// The prefix of the storage item `item` of the pallet `pallet`.
+pub fn storage_prefix(pallet: &str, item: &str) -> Prefix {
+	let mut prefix = [0; 16];
+	prefix[..8].copy_from_slice(&support::hash_of(pallet).to_le_bytes());
+	prefix[8..].copy_from_slice(&support::hash_of(item).to_le_bytes());
+	prefix
+}
+
+/// How the encoded keys of a map are hashed into the keys of its entries.
+pub trait StorageHasher {
+	/// The name of the hasher in the metadata.
+	const NAME: &'static str;
+
+	fn hash(encoded: &[u8]) -> Vec<u8>;
+}
+
+/// Use the encoded key as is. Only for keys which cannot be chosen by users, since it lets them
+/// control the layout of the map.
+pub struct Identity;
+
+impl StorageHasher for Identity {
+	const NAME: &'static str = "Identity";
+
+	fn hash(encoded: &[u8]) -> Vec<u8> {
+		encoded.to_vec()
+	}
+}
+
+/// Use the hash of the encoded key.
+pub struct Hash64;
+
+impl StorageHasher for Hash64 {
+	const NAME: &'static str = "Hash64";
+
+	fn hash(encoded: &[u8]) -> Vec<u8> {
+		support::hash_of(encoded).to_le_bytes().to_vec()
+	}
+}
+
+/// Use the hash of the encoded key, followed by the encoded key itself, so that the key can be
+/// recovered from the key of the entry.
+pub struct Hash64Concat;
+
+impl StorageHasher for Hash64Concat {
+	const NAME: &'static str = "Hash64Concat";
+
+	fn hash(encoded: &[u8]) -> Vec<u8> {
+		let mut hashed = Hash64::hash(encoded);
+		hashed.extend_from_slice(encoded);
+		hashed
+	}
+}
+
+/// A value which does not depend on any state, like the default value of a storage item.
+pub trait Get<V> {
+	fn get() -> V;
+}
+
+/// Get the `Default` value.
+pub struct GetDefault;
+
+impl<V: Default> Get<V> for GetDefault {
+	fn get() -> V {
+		V::default()
+	}
+}
+
+/// What reading a storage item returns. See the module docs.
+pub trait QueryKind<V, OnEmpty> {
+	type Query;
+	/// Whether reading an empty item gives `None`, rather than a value. See `metadata`.
+	const OPTIONAL: bool;
+
+	fn from_optional(value: Option<V>) -> Self::Query;
+	fn into_optional(query: Self::Query) -> Option<V>;
+}
+
+/// Read the value of an item, or the value of `OnEmpty` when the item is empty.
+pub struct ValueQuery;
+
+impl<V, OnEmpty: Get<V>> QueryKind<V, OnEmpty> for ValueQuery {
+	type Query = V;
+	const OPTIONAL: bool = false;
+
+	fn from_optional(value: Option<V>) -> V {
+		value.unwrap_or_else(OnEmpty::get)
+	}
+
+	fn into_optional(query: V) -> Option<V> {
+		Some(query)
+	}
+}
+
+/// Read the value of an item, or `None` when the item is empty.
+pub struct OptionQuery;
+
+impl<V, OnEmpty> QueryKind<V, OnEmpty> for OptionQuery {
+	type Query = Option<V>;
+	const OPTIONAL: bool = true;
+
+	fn from_optional(value: Option<V>) -> Option<V> {
+		value
+	}
+
+	fn into_optional(query: Option<V>) -> Option<V> {
+		query
+	}
+}
+
+/// A single value.
+pub struct StorageValue<V, Q = OptionQuery, OnEmpty = GetDefault> {
+	prefix: Prefix,
+	value: Option<V>,
+	_phantom: PhantomData<(Q, OnEmpty)>,
+}
+
+impl<V, Q: QueryKind<V, OnEmpty>, OnEmpty> StorageValue<V, Q, OnEmpty> {
+	pub fn new(prefix: Prefix) -> Self {
+		Self { prefix, value: None, _phantom: PhantomData }
+	}
+
+	/// The key of the value.
+	pub fn hashed_key(&self) -> Vec<u8> {
+		self.prefix.to_vec()
+	}
+
+	pub fn exists(&self) -> bool {
+		self.value.is_some()
+	}
+
+	pub fn get(&self) -> Q::Query
+	where
+		V: Clone,
+	{
+		Q::from_optional(self.value.clone())
+	}
+
+	pub fn get_ref(&self) -> Option<&V> {
+		self.value.as_ref()
+	}
+
+	pub fn get_mut(&mut self) -> Option<&mut V> {
+		self.value.as_mut()
+	}
+
+	pub fn put(&mut self, value: V) {
+		self.value = Some(value);
+	}
+
+	/// Set the value from a query, so that `None` empties an `OptionQuery` item.
+	pub fn set(&mut self, query: Q::Query) {
+		self.value = Q::into_optional(query);
+	}
+
+	pub fn kill(&mut self) {
+		self.value = None;
+	}
+
+	/// Empty the item, and return what it held.
+	pub fn take(&mut self) -> Q::Query {
+		Q::from_optional(self.value.take())
+	}
+
+	pub fn mutate<R>(&mut self, f: impl FnOnce(&mut Q::Query) -> R) -> R {
+		let mut query = self.take();
+		let result = f(&mut query);
+		self.set(query);
+		result
+	}
+
+	/// Like `mutate`, but the value is only changed if `f` returns `Ok`.
+	pub fn try_mutate<R, E>(
+		&mut self,
+		f: impl FnOnce(&mut Q::Query) -> Result<R, E>,
+	) -> Result<R, E>
+	where
+		V: Clone,
+	{
+		let mut query = self.get();
+		let result = f(&mut query)?;
+		self.set(query);
+		Ok(result)
+	}
+}
+
+impl<V: Debug, Q, OnEmpty> Debug for StorageValue<V, Q, OnEmpty> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		self.value.fmt(f)
+	}
+}
+
+/// A map from keys of type `K` to values of type `V`, whose keys are hashed with `H`.
+pub struct StorageMap<H, K, V, Q = OptionQuery, OnEmpty = GetDefault> {
+	prefix: Prefix,
+	map: BTreeMap<K, V>,
+	_phantom: PhantomData<(H, Q, OnEmpty)>,
+}
+
+impl<H, K: Ord, V, Q: QueryKind<V, OnEmpty>, OnEmpty> StorageMap<H, K, V, Q, OnEmpty> {
+	pub fn new(prefix: Prefix) -> Self {
+		Self { prefix, map: BTreeMap::new(), _phantom: PhantomData }
+	}
+
+	/// The key of the entry for `key`.
+	pub fn hashed_key_for(&self, key: &K) -> Vec<u8>
+	where
+		H: StorageHasher,
+		K: Encode,
+	{
+		let mut hashed_key = self.prefix.to_vec();
+		hashed_key.extend(H::hash(&key.encode()));
+		hashed_key
+	}
+
+	pub fn contains_key(&self, key: &K) -> bool {
+		self.map.contains_key(key)
+	}
+
+	pub fn get(&self, key: &K) -> Q::Query
+	where
+		V: Clone,
+	{
+		Q::from_optional(self.map.get(key).cloned())
+	}
+
+	pub fn get_ref(&self, key: &K) -> Option<&V> {
+		self.map.get(key)
+	}
+
+	pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+		self.map.get_mut(key)
+	}
+
+	pub fn insert(&mut self, key: K, value: V) {
+		self.map.insert(key, value);
+	}
+
+	/// Set the entry for `key` from a query, so that `None` removes an `OptionQuery` entry.
+	pub fn set(&mut self, key: K, query: Q::Query) {
+		match Q::into_optional(query) {
+			Some(value) => self.map.insert(key, value),
+			None => self.map.remove(&key),
+		};
+	}
+
+	pub fn remove(&mut self, key: &K) {
+		self.map.remove(key);
+	}
+
+	/// Remove the entry for `key`, and return what it held.
+	pub fn take(&mut self, key: &K) -> Q::Query {
+		Q::from_optional(self.map.remove(key))
+	}
+
+	pub fn mutate<R>(&mut self, key: &K, f: impl FnOnce(&mut Q::Query) -> R) -> R
+	where
+		K: Clone,
+	{
+		let mut query = self.take(key);
+		let result = f(&mut query);
+		self.set(key.clone(), query);
+		result
+	}
+
+	/// Like `mutate`, but the entry is only changed if `f` returns `Ok`.
+	pub fn try_mutate<R, E>(
+		&mut self,
+		key: &K,
+		f: impl FnOnce(&mut Q::Query) -> Result<R, E>,
+	) -> Result<R, E>
+	where
+		K: Clone,
+		V: Clone,
+	{
+		let mut query = self.get(key);
+		let result = f(&mut query)?;
+		self.set(key.clone(), query);
+		Ok(result)
+	}
+
+	/// The number of entries.
+	pub fn count(&self) -> usize {
+		self.map.len()
+	}
+
+	/// The entries, ordered by key.
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, &V)> {
+		self.map.iter()
+	}
+
+	pub fn iter_keys(&self) -> impl DoubleEndedIterator<Item = &K> {
+		self.map.keys()
+	}
+
+	pub fn iter_values(&self) -> impl DoubleEndedIterator<Item = &V> {
+		self.map.values()
+	}
+
+	pub fn iter_values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> {
+		self.map.values_mut()
+	}
+
+	/// Remove all entries, and return them ordered by key.
+	pub fn drain(&mut self) -> impl DoubleEndedIterator<Item = (K, V)> {
+		std::mem::take(&mut self.map).into_iter()
+	}
+
+	/// Remove all entries.
+	pub fn clear(&mut self) {
+		self.map.clear();
+	}
+}
+
+impl<H, K: Debug, V: Debug, Q, OnEmpty> Debug for StorageMap<H, K, V, Q, OnEmpty> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		self.map.fmt(f)
+	}
+}
+
+/// A map from pairs of keys of types `K1` and `K2` to values of type `V`, whose keys are hashed
+/// with `H1` and `H2`. The entries which share their first key can be iterated and removed
+/// together.
+pub struct StorageDoubleMap<H1, K1, H2, K2, V, Q = OptionQuery, OnEmpty = GetDefault> {
+	prefix: Prefix,
+	map: BTreeMap<K1, BTreeMap<K2, V>>,
+	_phantom: PhantomData<(H1, H2, Q, OnEmpty)>,
+}
+
+impl<H1, K1: Ord, H2, K2: Ord, V, Q: QueryKind<V, OnEmpty>, OnEmpty>
+	StorageDoubleMap<H1, K1, H2, K2, V, Q, OnEmpty>
+{
+	pub fn new(prefix: Prefix) -> Self {
+		Self { prefix, map: BTreeMap::new(), _phantom: PhantomData }
+	}
+
+	/// The key of the entry for `k1` and `k2`.
+	pub fn hashed_key_for(&self, k1: &K1, k2: &K2) -> Vec<u8>
+	where
+		H1: StorageHasher,
+		K1: Encode,
+		H2: StorageHasher,
+		K2: Encode,
+	{
+		let mut hashed_key = self.prefix.to_vec();
+		hashed_key.extend(H1::hash(&k1.encode()));
+		hashed_key.extend(H2::hash(&k2.encode()));
+		hashed_key
+	}
+
+	pub fn contains_key(&self, k1: &K1, k2: &K2) -> bool {
+		self.map.get(k1).is_some_and(|map| map.contains_key(k2))
+	}
+
+	pub fn get(&self, k1: &K1, k2: &K2) -> Q::Query
+	where
+		V: Clone,
+	{
+		Q::from_optional(self.get_ref(k1, k2).cloned())
+	}
+
+	pub fn get_ref(&self, k1: &K1, k2: &K2) -> Option<&V> {
+		self.map.get(k1).and_then(|map| map.get(k2))
+	}
+
+	pub fn get_mut(&mut self, k1: &K1, k2: &K2) -> Option<&mut V> {
+		self.map.get_mut(k1).and_then(|map| map.get_mut(k2))
+	}
+
+	pub fn insert(&mut self, k1: K1, k2: K2, value: V) {
+		self.map.entry(k1).or_default().insert(k2, value);
+	}
+
+	/// Set the entry for `k1` and `k2` from a query, so that `None` removes an `OptionQuery`
+	/// entry.
+	pub fn set(&mut self, k1: K1, k2: K2, query: Q::Query) {
+		match Q::into_optional(query) {
+			Some(value) => self.insert(k1, k2, value),
+			None => self.remove(&k1, &k2),
+		}
+	}
+
+	pub fn remove(&mut self, k1: &K1, k2: &K2) {
+		self.take_value(k1, k2);
+	}
+
+	/// Remove the entry for `k1` and `k2`, and return what it held.
+	pub fn take(&mut self, k1: &K1, k2: &K2) -> Q::Query {
+		Q::from_optional(self.take_value(k1, k2))
+	}
+
+	/// Remove the entry for `k1` and `k2`, and the map of `k1` once it is empty.
+	fn take_value(&mut self, k1: &K1, k2: &K2) -> Option<V> {
+		let map = self.map.get_mut(k1)?;
+		let value = map.remove(k2);
+		if map.is_empty() {
+			self.map.remove(k1);
+		}
+		value
+	}
+
+	pub fn mutate<R>(&mut self, k1: &K1, k2: &K2, f: impl FnOnce(&mut Q::Query) -> R) -> R
+	where
+		K1: Clone,
+		K2: Clone,
+	{
+		let mut query = self.take(k1, k2);
+		let result = f(&mut query);
+		self.set(k1.clone(), k2.clone(), query);
+		result
+	}
+
+	/// Like `mutate`, but the entry is only changed if `f` returns `Ok`.
+	pub fn try_mutate<R, E>(
+		&mut self,
+		k1: &K1,
+		k2: &K2,
+		f: impl FnOnce(&mut Q::Query) -> Result<R, E>,
+	) -> Result<R, E>
+	where
+		K1: Clone,
+		K2: Clone,
+		V: Clone,
+	{
+		let mut query = self.get(k1, k2);
+		let result = f(&mut query)?;
+		self.set(k1.clone(), k2.clone(), query);
+		Ok(result)
+	}
+
+	/// The entries, ordered by keys.
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K1, &K2, &V)> {
+		self.map
+			.iter()
+			.flat_map(|(k1, map)| map.iter().map(move |(k2, value)| (k1, k2, value)))
+	}
+
+	/// The entries whose first key is `k1`, ordered by their second key.
+	pub fn iter_prefix(&self, k1: &K1) -> impl DoubleEndedIterator<Item = (&K2, &V)> {
+		self.map.get(k1).into_iter().flatten()
+	}
+
+	/// Remove the entries whose first key is `k1`, and return them ordered by their second key.
+	pub fn drain_prefix(&mut self, k1: &K1) -> impl DoubleEndedIterator<Item = (K2, V)> {
+		self.map.remove(k1).into_iter().flatten()
+	}
+}
+
+impl<H1, K1: Debug, H2, K2: Debug, V: Debug, Q, OnEmpty> Debug
+	for StorageDoubleMap<H1, K1, H2, K2, V, Q, OnEmpty>
+{
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		self.map.fmt(f)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct InitialCount;
+
+	impl Get<u32> for InitialCount {
+		fn get() -> u32 {
+			7
+		}
+	}
+
+	#[test]
+	fn prefixes_and_hashers_are_pinned() {
+		// Keys are part of the state, so they must be the same on every platform and with every
+		// release of Rust.
+		let prefix = storage_prefix("balances", "Account");
+		assert_eq!(prefix[..8], 0x43941d4a67453915u64.to_le_bytes());
+		assert_eq!(prefix[8..], 0xe48c4de6c2a3d5b9u64.to_le_bytes());
+		let hashed = 0xdf5ef6505ba1c540u64.to_le_bytes();
+		assert_eq!(Hash64::hash(&1u32.encode()), hashed);
+		assert_eq!(Hash64Concat::hash(&1u32.encode()), [&hashed[..], &[1, 0, 0, 0]].concat());
+	}
+
+	#[test]
+	fn query_kinds_and_defaults() {
+		let mut count = StorageValue::<u32, ValueQuery, InitialCount>::new([0; 16]);
+		assert_eq!(count.get(), 7);
+		assert!(!count.exists());
+		count.mutate(|count| *count += 1);
+		assert_eq!(count.take(), 8);
+		assert_eq!(count.get(), 7);
+
+		let mut map = StorageMap::<Hash64Concat, u32, u32>::new([0; 16]);
+		assert_eq!(map.get(&1), None);
+		map.insert(1, 10);
+		assert_eq!(map.try_mutate(&1, |value| value.take().ok_or("empty")), Ok(10));
+		assert_eq!(map.get(&1), None);
+		assert_eq!(map.try_mutate(&1, |value| value.take().ok_or("empty")), Err("empty"));
+	}
+
+	#[test]
+	fn double_maps_iterate_by_prefix() {
+		let mut map = StorageDoubleMap::<Hash64, u32, Identity, u32, u32, ValueQuery>::new([0; 16]);
+		map.insert(1, 2, 12);
+		map.insert(1, 1, 11);
+		map.insert(2, 1, 21);
+		assert_eq!(map.iter_prefix(&1).collect::<Vec<_>>(), vec![(&1, &11), (&2, &12)]);
+		assert_eq!(map.iter().count(), 3);
+		assert_eq!(map.drain_prefix(&1).collect::<Vec<_>>(), vec![(1, 11), (2, 12)]);
+		assert_eq!(map.get(&1, &1), 0);
+		map.remove(&2, &1);
+		assert_eq!(map.iter().count(), 0);
+	}
+
+	#[test]
+	fn keys_are_prefixed_and_hashed() {
+		let prefix = storage_prefix("balances", "accounts");
+		assert_ne!(prefix, storage_prefix("balances", "reserved"));
+		assert_ne!(prefix, storage_prefix("assets", "accounts"));
+
+		let map = StorageMap::<Hash64Concat, u32, u32>::new(prefix);
+		let key = map.hashed_key_for(&42);
+		assert_eq!(key.len(), 16 + 8 + 4);
+		assert!(key.starts_with(&prefix));
+		assert!(key.ends_with(&42u32.to_le_bytes()));
+	}
+}
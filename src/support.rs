@@ -8,11 +8,221 @@ pub struct Header<BlockNumber> {
 }
 
 pub struct Extrinsic<Caller, Call> {
-	pub caller: Caller,
+	/// The account which signed the extrinsic, or `None` for unsigned extrinsics like inherents.
+	pub caller: Option<Caller>,
 	pub call: Call,
 }
 
-pub type DispatchResult = Result<(), String>;
+pub type DispatchResult = Result<(), DispatchError>;
+
+/// Why a call failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DispatchError {
+	/// An error declared by a pallet with `#[macros::error]`.
+	Module(ModuleError),
+	/// Any other error, described by a message. Pallets which do not declare an `Error` enum
+	/// return these.
+	Other(String),
+}
+
+/// An error declared by a pallet with `#[macros::error]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleError {
+	/// The name of the pallet, which is the name of its module.
+	pub pallet: &'static str,
+	/// The index of the error in the `Error` enum of the pallet.
+	pub index: u8,
+	pub name: &'static str,
+	/// The doc comment of the error.
+	pub description: &'static str,
+}
+
+impl core::fmt::Display for DispatchError {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match self {
+			DispatchError::Module(error) if error.description.is_empty() =>
+				write!(f, "{}::{}", error.pallet, error.name),
+			DispatchError::Module(error) => f.write_str(error.description),
+			DispatchError::Other(message) => f.write_str(message),
+		}
+	}
+}
+
+impl From<String> for DispatchError {
+	fn from(message: String) -> Self {
+		DispatchError::Other(message)
+	}
+}
+
+impl From<&str> for DispatchError {
+	fn from(message: &str) -> Self {
+		DispatchError::Other(message.to_string())
+	}
+}
+
+// Helpers which return plain `String` errors can still use `?` on a `DispatchResult`.
+impl From<DispatchError> for String {
+	fn from(error: DispatchError) -> Self {
+		error.to_string()
+	}
+}
+
+/// Who, or what, a call is dispatched by.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, macros::TypeInfo)]
+pub enum RawOrigin<AccountId> {
+	/// The runtime itself, which is allowed to do anything.
+	Root,
+	/// A regular account, like the caller of an extrinsic.
+	Signed(AccountId),
+	/// No one, like for an unsigned extrinsic.
+	None,
+	/// A motion of the council, approved by the first number of members out of the second.
+	Collective(MemberCount, MemberCount),
+}
+
+/// A number of members of a collective, like the council.
+pub type MemberCount = u32;
+
+/// Return the account of a signed origin, or an error for any other origin.
+pub fn ensure_signed<AccountId>(origin: RawOrigin<AccountId>) -> Result<AccountId, String> {
+	match origin {
+		RawOrigin::Signed(who) => Ok(who),
+		_ => Err("bad origin: expected a signed origin".to_string()),
+	}
+}
+
+/// Return an error for any origin other than root.
+pub fn ensure_root<AccountId>(origin: RawOrigin<AccountId>) -> DispatchResult {
+	match origin {
+		RawOrigin::Root => Ok(()),
+		_ => Err("bad origin: expected the root origin".into()),
+	}
+}
+
+/// Return an error for any origin other than none.
+pub fn ensure_none<AccountId>(origin: RawOrigin<AccountId>) -> DispatchResult {
+	match origin {
+		RawOrigin::None => Ok(()),
+		_ => Err("bad origin: expected no origin".into()),
+	}
+}
+
+/// A check on the origin of a call.
+///
+/// Pallets take these in their `Config`, so that the runtime decides who is allowed to do
+/// something, for example root or two thirds of the council.
+pub trait EnsureOrigin<Origin> {
+	/// What a successful check returns.
+	type Success;
+
+	fn ensure_origin(origin: Origin) -> Result<Self::Success, DispatchError>;
+}
+
+/// Allow only the root origin.
+pub struct EnsureRoot;
+
+impl<AccountId> EnsureOrigin<RawOrigin<AccountId>> for EnsureRoot {
+	type Success = ();
+
+	fn ensure_origin(origin: RawOrigin<AccountId>) -> Result<(), DispatchError> {
+		ensure_root(origin)
+	}
+}
+
+/// Allow only a motion of the council approved by at least `N / D` of the members.
+pub struct EnsureProportionAtLeast<const N: MemberCount, const D: MemberCount>;
+
+impl<AccountId, const N: MemberCount, const D: MemberCount> EnsureOrigin<RawOrigin<AccountId>>
+	for EnsureProportionAtLeast<N, D>
+{
+	type Success = ();
+
+	fn ensure_origin(origin: RawOrigin<AccountId>) -> Result<(), DispatchError> {
+		match origin {
+			RawOrigin::Collective(yes, total)
+				if yes as u64 * D as u64 >= N as u64 * total as u64 =>
+				Ok(()),
+			_ => Err("bad origin: expected enough approvals of the council".into()),
+		}
+	}
+}
+
+/// Allow the origins allowed by either `L` or `R`.
+pub struct EitherOf<L, R>(core::marker::PhantomData<(L, R)>);
+
+impl<Origin, L, R> EnsureOrigin<Origin> for EitherOf<L, R>
+where
+	Origin: Clone,
+	L: EnsureOrigin<Origin>,
+	R: EnsureOrigin<Origin, Success = L::Success>,
+{
+	type Success = L::Success;
+
+	fn ensure_origin(origin: Origin) -> Result<Self::Success, DispatchError> {
+		L::ensure_origin(origin.clone()).or_else(|_| R::ensure_origin(origin))
+	}
+}
+
+/// The cost of executing a call, in abstract units.
+pub type Weight = u64;
+
+/// The weight of a single call.
+pub const CALL_WEIGHT: Weight = 1_000;
+
+/// Get the weight of a call, used to limit how much work is done in a block.
+pub trait GetWeight {
+	fn weight(&self) -> Weight;
+}
+
+/// The inherents of a call type. Inherents are the unsigned extrinsics which every block must
+/// include exactly once, like the time of the block, and the only extrinsics which can be unsigned.
+///
+/// `#[macros::call]` implements this for the functions marked with `#[inherent]`, and
+/// `#[macros::runtime]` for `RuntimeCall`, whose `execute_block` rejects blocks with missing,
+/// duplicate or signed inherents, before anything is changed.
+pub trait Inherents {
+	/// The number of inherents.
+	const COUNT: usize;
+
+	/// The position of this call among the inherents, below `COUNT`, if it is one.
+	fn inherent(&self) -> Option<usize>;
+}
+
+/// What happened to the extrinsics of a block, returned by the `execute_block` generated by
+/// `#[macros::runtime]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockReport {
+	/// The indices of the extrinsics which were dispatched successfully.
+	pub applied: Vec<u32>,
+	/// The indices of the extrinsics which failed, with their errors. Signed extrinsics whose
+	/// caller cannot be resolved or cannot pay the fee fail without being dispatched.
+	pub failed: Vec<(u32, DispatchError)>,
+	/// The weight used by the `on_initialize` hooks of the pallets and by the dispatched calls,
+	/// including those which failed.
+	pub weight: Weight,
+	/// The names of the pallets whose `on_initialize` hook failed, with their errors. A hook can
+	/// fail more than once, and its other work is kept, like the extrinsics of the block.
+	pub initialize_failed: Vec<(&'static str, DispatchError)>,
+	/// The names of the pallets whose `on_finalize` hook failed, with their errors. The changes
+	/// of the block are kept, like those of the other hooks.
+	pub finalize_failed: Vec<(&'static str, DispatchError)>,
+}
+
+/// What the `on_initialize` hook of a pallet did, returned to `execute_block`, which adds it to
+/// the `BlockReport` of the block.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InitializeReport {
+	/// The weight used, including by the work which failed.
+	pub weight: Weight,
+	/// The errors of the work which failed. The hook does the rest of its work regardless.
+	pub failed: Vec<DispatchError>,
+}
+
+impl From<Weight> for InitializeReport {
+	fn from(weight: Weight) -> Self {
+		Self { weight, failed: Vec::new() }
+	}
+}
 
 pub trait Dispatch {
 	type Caller;
@@ -20,3 +230,293 @@ pub trait Dispatch {
 
 	fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> DispatchResult;
 }
+
+/// Charge the fee of a signed extrinsic, before it is dispatched.
+///
+/// `#[macros::runtime]` calls this for every signed extrinsic, and skips the extrinsic if the fee
+/// cannot be paid. The runtime implements it, since only the runtime knows which pallets hold the
+/// funds and where the fees go.
+pub trait ChargeTransaction<AccountId, Call> {
+	fn charge_transaction(&mut self, who: &AccountId, call: &Call) -> DispatchResult;
+}
+
+/// Access to a pallet stored in the runtime.
+///
+/// `#[macros::runtime]` implements this for every pallet in the `Runtime` struct, so a pallet which
+/// needs another pallet's state can require it in its `Config` and reach it through `T`.
+pub trait GetPallet<P> {
+	fn pallet(&self) -> &P;
+	fn pallet_mut(&mut self) -> &mut P;
+}
+
+/// An instance of a pallet, so that a runtime can include the same pallet more than once, for
+/// example `balances` for two different tokens.
+///
+/// Instantiable pallets are generic over `I: Instance = ()`, after `T`. `#[macros::storage]` adds
+/// the `PREFIX` of the instance to the name of the pallet in the prefixes of its storage items, so
+/// that each instance has its own storage.
+pub trait Instance: 'static {
+	const PREFIX: &'static str;
+}
+
+/// The default instance, for a pallet which is included once. Its storage has the same prefixes as
+/// if the pallet was not instantiable.
+impl Instance for () {
+	const PREFIX: &'static str = "";
+}
+
+/// A second instance of a pallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instance1;
+
+impl Instance for Instance1 {
+	const PREFIX: &'static str = "Instance1";
+}
+
+/// Logic which pallets run when a block is executed, in addition to their calls.
+///
+/// `#[macros::runtime]` calls these hooks for every pallet, so each pallet must implement this
+/// trait, even if it only uses the default implementations.
+pub trait Hooks<T: crate::system::Config> {
+	/// Called at the start of each block, before its extrinsics are executed. Returns the weight
+	/// used, and the errors of the work which failed, which do not undo the block either.
+	fn on_initialize(_runtime: &mut T, _block_number: T::BlockNumber) -> InitializeReport {
+		InitializeReport::default()
+	}
+
+	/// Called at the end of each block, after its extrinsics are executed. An error does not undo
+	/// the block, and is returned in its `BlockReport`.
+	fn on_finalize(_runtime: &mut T, _block_number: T::BlockNumber) -> DispatchResult {
+		Ok(())
+	}
+}
+
+/// The optional parts of a pallet. `#[macros::runtime]` checks them against the `#[event]`,
+/// `#[error]` and `#[genesis]` markers of the field of the pallet, so that a pallet is not
+/// included without its events, errors or genesis config.
+///
+/// Like `Hooks`, each pallet must implement this trait, even if it has none of them.
+/// `#[macros::pallet]` implements it from the sections of the pallet.
+pub trait PalletInfo {
+	/// Whether the pallet declares an `Event` enum with `#[macros::event]`.
+	const HAS_EVENT: bool = false;
+	/// Whether the pallet declares an `Error` enum with `#[macros::error]`.
+	const HAS_ERROR: bool = false;
+	/// Whether the pallet has a `GenesisConfig`, which implements `BuildGenesis`.
+	const HAS_GENESIS: bool = false;
+}
+
+/// The initial state of a pallet, like the balances of the first accounts, which is set when the
+/// runtime is created with `Runtime::from_genesis`, for the pallets marked with `#[genesis]`.
+pub trait BuildGenesis<P> {
+	/// Write the initial state into the storage of `pallet`.
+	fn build(&self, pallet: &mut P);
+}
+
+/// The hash used to identify calls and derive accounts.
+///
+/// Note that this is not a cryptographic hash, which is fine for our simple state machine.
+pub type Hash = u64;
+
+/// Hash any value which implements `std::hash::Hash`, with `StableHasher`.
+pub fn hash_of<T: std::hash::Hash + ?Sized>(value: &T) -> Hash {
+	use std::hash::Hasher;
+	let mut hasher = StableHasher::default();
+	value.hash(&mut hasher);
+	hasher.finish()
+}
+
+/// The 64 bit FNV-1a hash, which gives the same hashes on every platform and with every release
+/// of Rust, unlike the `DefaultHasher` of the standard library. Hashes end up in the state, as
+/// account ids and storage keys, so they must not change.
+///
+/// Integers are hashed as their little endian bytes, and `usize` and `isize` as 64 bit integers.
+pub struct StableHasher(u64);
+
+impl StableHasher {
+	const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+	const PRIME: u64 = 0x0000_0100_0000_01b3;
+}
+
+impl Default for StableHasher {
+	fn default() -> Self {
+		Self(Self::OFFSET_BASIS)
+	}
+}
+
+impl std::hash::Hasher for StableHasher {
+	fn finish(&self) -> u64 {
+		self.0
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		for byte in bytes {
+			self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(Self::PRIME);
+		}
+	}
+
+	fn write_u16(&mut self, i: u16) {
+		self.write(&i.to_le_bytes())
+	}
+
+	fn write_u32(&mut self, i: u32) {
+		self.write(&i.to_le_bytes())
+	}
+
+	fn write_u64(&mut self, i: u64) {
+		self.write(&i.to_le_bytes())
+	}
+
+	fn write_u128(&mut self, i: u128) {
+		self.write(&i.to_le_bytes())
+	}
+
+	fn write_usize(&mut self, i: usize) {
+		self.write_u64(i as u64)
+	}
+
+	fn write_i16(&mut self, i: i16) {
+		self.write(&i.to_le_bytes())
+	}
+
+	fn write_i32(&mut self, i: i32) {
+		self.write(&i.to_le_bytes())
+	}
+
+	fn write_i64(&mut self, i: i64) {
+		self.write(&i.to_le_bytes())
+	}
+
+	fn write_i128(&mut self, i: i128) {
+		self.write(&i.to_le_bytes())
+	}
+
+	fn write_isize(&mut self, i: isize) {
+		self.write_i64(i as i64)
+	}
+}
+
+/// Create an account id from a hash, for accounts which are not controlled by a single user, like
+/// multisig accounts.
+pub trait FromHash {
+	fn from_hash(hash: Hash) -> Self;
+}
+
+impl FromHash for String {
+	fn from_hash(hash: Hash) -> Self {
+		format!("{:016x}", hash)
+	}
+}
+
+/// A filter on values of type `T`, used for example to restrict the calls a proxy can make.
+pub trait InstanceFilter<T> {
+	fn filter(&self, value: &T) -> bool;
+}
+
+/// Resolve an address to the account it refers to. Addresses can be shorter than accounts, for
+/// example an index from the indices pallet.
+///
+/// `#[macros::runtime]` resolves the caller of every signed extrinsic with this, and
+/// `#[macros::call]` resolves every call argument of type `T::AccountId`, before they are
+/// dispatched. The runtime implements it, since only the runtime knows which pallets hold the
+/// addresses.
+pub trait Lookup {
+	type Source;
+	type Target;
+
+	fn lookup(&self, source: Self::Source) -> Result<Self::Target, DispatchError>;
+}
+
+/// The address of an account: either the account itself, or its index.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, macros::Encode, macros::Decode, macros::TypeInfo)]
+pub enum MultiAddress<AccountId, AccountIndex> {
+	Id(AccountId),
+	Index(AccountIndex),
+}
+
+impl<AccountId, AccountIndex> From<AccountId> for MultiAddress<AccountId, AccountIndex> {
+	fn from(who: AccountId) -> Self {
+		Self::Id(who)
+	}
+}
+
+/// A fraction between zero and one hundred percent, used for example to configure shares of funds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Percent(u8);
+
+impl Percent {
+	/// Create a fraction from a number of percent. Values above one hundred are capped.
+	pub const fn from_percent(percent: u8) -> Self {
+		Self(if percent > 100 { 100 } else { percent })
+	}
+
+	/// The number of percent in this fraction.
+	pub const fn deconstruct(self) -> u8 {
+		self.0
+	}
+
+	/// Multiply `value` by this fraction, rounding down.
+	pub fn mul_floor<N>(self, value: N) -> N
+	where
+		N: Copy + From<u8> + num::traits::NumOps,
+	{
+		let (percent, hundred) = (N::from(self.0), N::from(100));
+		// Split the value, so that the multiplication cannot overflow.
+		value / hundred * percent + value % hundred * percent / hundred
+	}
+}
+
+impl crate::codec::Encode for Percent {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		self.0.encode_to(dest);
+	}
+}
+
+impl crate::codec::Decode for Percent {
+	fn decode(input: &mut &[u8]) -> Result<Self, String> {
+		match u8::decode(input)? {
+			percent @ 0..=100 => Ok(Self(percent)),
+			_ => Err("invalid percent".to_string()),
+		}
+	}
+}
+
+// Like its encoding, a fraction is described as its number of percent.
+impl crate::metadata::TypeInfo for Percent {
+	fn type_info(registry: &mut crate::metadata::TypeRegistry) -> crate::metadata::TypeMetadata {
+		let percent = crate::metadata::FieldMetadata { name: None, ty: registry.register::<u8>() };
+		crate::metadata::TypeMetadata {
+			path: concat!(module_path!(), "::Percent"),
+			def: crate::metadata::TypeDef::Composite(vec![percent]),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::hash::Hasher;
+
+	#[test]
+	fn stable_hasher_is_fnv1a() {
+		// The test vectors of the FNV-1a 64 bit hash.
+		let fnv = |bytes: &[u8]| {
+			let mut hasher = StableHasher::default();
+			hasher.write(bytes);
+			hasher.finish()
+		};
+		assert_eq!(fnv(b""), 0xcbf29ce484222325);
+		assert_eq!(fnv(b"a"), 0xaf63dc4c8601ec8c);
+		assert_eq!(fnv(b"foobar"), 0x85944171f73967e8);
+	}
+
+	#[test]
+	fn hash_of_is_pinned() {
+		// Integers are hashed as little endian bytes, strings are followed by `0xff`, and vectors
+		// start with their length as a `u64`.
+		assert_eq!(hash_of(&0u64), 0xa8c7f832281a39c5);
+		assert_eq!(hash_of("treasury"), 0x2599860414f896bd);
+		assert_eq!(hash_of(&("multisig", vec!["alice".to_string()], 1u16)), 0xac3fba2cfe492219);
+		assert_eq!(String::from_hash(hash_of("treasury")), "2599860414f896bd");
+	}
+}
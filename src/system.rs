@@ -1,41 +1,57 @@
-use std::collections::BTreeMap;
-
+use crate::storage::{Hash64Concat, StorageMap, StorageValue, ValueQuery};
 use num::{CheckedAdd, One, Zero};
 
+#[macros::config]
 pub trait Config {
-	type AccountId: Ord + std::fmt::Debug + std::fmt::Display;
-	type BlockNumber: Zero + One + CheckedAdd + Copy + std::fmt::Debug + std::fmt::Display;
-	type Nonce: Copy + Zero + One + std::fmt::Debug + CheckedAdd + std::fmt::Display;
+	type AccountId: Ord + Clone + std::fmt::Debug + std::fmt::Display;
+	type BlockNumber: Zero
+		+ One
+		+ CheckedAdd
+		+ Copy
+		+ Ord
+		+ Default
+		+ std::fmt::Debug
+		+ std::fmt::Display;
+	type Nonce: Copy + Zero + One + Default + std::fmt::Debug + CheckedAdd + std::fmt::Display;
+	/// The overarching event type, generated by `#[macros::runtime]`.
+	type RuntimeEvent: std::fmt::Debug;
 }
 
+/// The origin of calls in a runtime which uses this system pallet.
+pub type Origin<T> = crate::support::RawOrigin<<T as Config>::AccountId>;
+
+#[macros::storage]
 #[derive(Debug)]
 pub struct Pallet<T: Config> {
-	block_number: T::BlockNumber,
-	nonce: BTreeMap<T::AccountId, T::Nonce>,
+	#[getter(block_number)]
+	block_number: StorageValue<T::BlockNumber, ValueQuery>,
+	#[getter(nonce)]
+	nonce: StorageMap<Hash64Concat, T::AccountId, T::Nonce, ValueQuery>,
+	/// The index of the extrinsic currently being executed in this block.
+	#[getter(extrinsic_index)]
+	#[setter(set_extrinsic_index)]
+	extrinsic_index: StorageValue<u32, ValueQuery>,
+	/// The events deposited by the pallets in this block.
+	events: StorageValue<Vec<T::RuntimeEvent>, ValueQuery>,
 }
 
 impl<T: Config> Pallet<T> {
-	pub fn new() -> Self {
-		Self { block_number: Zero::zero(), nonce: BTreeMap::new() }
-	}
-
-	pub fn block_number(&self) -> T::BlockNumber {
-		self.block_number
+	/// The number of the next block, or an error if it overflows.
+	pub fn next_block_number(&self) -> Result<T::BlockNumber, String> {
+		let block_number = self.block_number();
+		block_number.checked_add(&One::one()).ok_or_else(|| {
+			format!("Block number {} will overflow, upgrade necessary", block_number)
+		})
 	}
 
 	pub fn inc_block_number(&mut self) -> Result<(), String> {
-		self.block_number = self.block_number.checked_add(&One::one()).ok_or_else(|| {
-			format!("Block number {} will overflow, upgrade necessary", self.block_number)
-		})?;
+		let block_number = self.next_block_number()?;
+		self.block_number.put(block_number);
 		Ok(())
 	}
 
-	pub fn nonce(&self, who: &T::AccountId) -> T::Nonce {
-		*self.nonce.get(who).unwrap_or(&Zero::zero())
-	}
-
 	pub fn inc_nonce(&mut self, who: T::AccountId) -> Result<(), String> {
-		let current_nonce = *self.nonce.get(&who).unwrap_or(&Zero::zero());
+		let current_nonce = self.nonce(&who);
 		let new_nonce = current_nonce.checked_add(&One::one()).ok_or_else(|| {
 			format!("Nonce {} for {} will overflow, upgrade necessary", current_nonce, who)
 		})?;
@@ -44,6 +60,21 @@ impl<T: Config> Pallet<T> {
 
 		Ok(())
 	}
+
+	pub fn events(&self) -> &[T::RuntimeEvent] {
+		self.events.get_ref().map_or(&[], Vec::as_slice)
+	}
+
+	/// Deposit an event for this block. Pallets deposit their events through the
+	/// `deposit_event` generated by `#[macros::event]`.
+	pub fn deposit_event(&mut self, event: T::RuntimeEvent) {
+		self.events.mutate(|events| events.push(event));
+	}
+
+	/// Remove the events of the previous block.
+	pub fn reset_events(&mut self) {
+		self.events.kill();
+	}
 }
 
 #[cfg(test)]
@@ -53,6 +84,7 @@ mod test {
 		type AccountId = &'static str;
 		type BlockNumber = u32;
 		type Nonce = u32;
+		type RuntimeEvent = ();
 	}
 
 	#[test]
@@ -66,6 +98,28 @@ mod test {
 		assert_eq!(incremented_block_number, starting_block_number + 1);
 	}
 
+	#[test]
+	fn invalid_blocks_are_rejected() {
+		let mut runtime = crate::Runtime::new();
+		let block = |block_number| crate::types::Block {
+			header: crate::support::Header { block_number },
+			extrinsics: vec![],
+		};
+
+		assert_eq!(
+			runtime.execute_block(block(2)),
+			Err("block number 2 does not match the expected block number 1".into())
+		);
+		// The rejected block has no effect.
+		assert_eq!(runtime.system.block_number(), 0);
+
+		runtime.system.block_number.put(u32::MAX);
+		assert_eq!(
+			runtime.execute_block(block(0)),
+			Err(format!("Block number {} will overflow, upgrade necessary", u32::MAX).into())
+		);
+	}
+
 	#[test]
 	fn nonce() {
 		let mut system = super::Pallet::<TestConfig>::new();
@@ -77,4 +131,4 @@ mod test {
 		let incremented_nonce_number = system.nonce(&alice);
 		assert_eq!(incremented_nonce_number, starting_nonce + 1);
 	}
-}
\ No newline at end of file
+}
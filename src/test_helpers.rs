@@ -0,0 +1,12 @@
+//! Helpers shared by the tests of the pallets.
+
+use crate::RuntimeCall;
+
+/// A call which transfers `amount` native tokens to `to`. It is boxed, like the calls which
+/// pallets dispatch on behalf of others.
+pub fn transfer(to: &str, amount: u128) -> Box<RuntimeCall> {
+	Box::new(RuntimeCall::balances(crate::balances::Call::transfer {
+		to: to.to_string().into(),
+		amount,
+	}))
+}
@@ -0,0 +1,133 @@
+use crate::{
+	storage::{StorageValue, ValueQuery},
+	support::{self, DispatchResult, GetPallet},
+	system::Origin,
+};
+use core::fmt::Debug;
+use num::{CheckedAdd, Zero};
+
+#[macros::config]
+pub trait Config: Sized + crate::system::Config + GetPallet<Pallet<Self>> {
+	/// The type used to represent time, for example milliseconds since the Unix epoch.
+	type Moment: Zero + CheckedAdd + Copy + Ord + Default + Debug + std::hash::Hash;
+	/// The minimum time between two blocks.
+	const MINIMUM_PERIOD: Self::Moment;
+}
+
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	/// The time of the current block. Other pallets can use this to express deadlines.
+	#[getter(now)]
+	now: StorageValue<T::Moment, ValueQuery>,
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Set the time of the current block.
+	///
+	/// This is an inherent, so every block includes it exactly once, unsigned. The time must be
+	/// at least `MINIMUM_PERIOD` after the time of the previous block.
+	#[call_index(0)]
+	#[inherent]
+	pub fn set(&mut self, origin: Origin<T>, now: T::Moment) -> DispatchResult {
+		support::ensure_none(origin)?;
+
+		let prev = self.now();
+		if !prev.is_zero() {
+			let earliest = prev.checked_add(&T::MINIMUM_PERIOD).ok_or("timestamp overflow")?;
+			if now < earliest {
+				return Err("timestamp must increment by at least the minimum period".into());
+			}
+		}
+
+		self.now.put(now);
+		Ok(())
+	}
+}
+
+impl<T: Config> support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> support::Hooks<T> for Pallet<T> {}
+
+#[cfg(test)]
+mod tests {
+	use crate::{support, support::RawOrigin, types, Runtime, RuntimeCall};
+
+	fn block(block_number: u32, extrinsics: Vec<types::Extrinsic>) -> types::Block {
+		types::Block { header: support::Header { block_number }, extrinsics }
+	}
+
+	fn set(now: u64) -> types::Extrinsic {
+		support::Extrinsic { caller: None, call: RuntimeCall::timestamp(super::Call::set { now }) }
+	}
+
+	#[test]
+	fn set_timestamp() {
+		let mut runtime = Runtime::new();
+
+		assert_eq!(
+			runtime.timestamp.set(RawOrigin::Signed("alice".to_string()), 6_000),
+			Err("bad origin: expected no origin".into())
+		);
+		assert_eq!(runtime.timestamp.set(RawOrigin::None, 6_000), Ok(()));
+		assert_eq!(runtime.timestamp.now(), 6_000);
+	}
+
+	#[test]
+	fn blocks_must_set_the_timestamp() {
+		let mut runtime = Runtime::new();
+
+		assert_eq!(
+			runtime.execute_block(block(1, vec![set(6_000)])).map(|report| report.applied),
+			Ok(vec![0])
+		);
+		assert_eq!(runtime.timestamp.now(), 6_000);
+
+		// Blocks which do not set the timestamp exactly once, unsigned, are rejected before any
+		// of their extrinsics are executed.
+		let signed = support::Extrinsic { caller: Some("alice".to_string().into()), ..set(12_000) };
+		let unsigned = support::Extrinsic {
+			caller: None,
+			call: RuntimeCall::balances(crate::balances::Call::transfer {
+				to: "bob".to_string().into(),
+				amount: 10,
+			}),
+		};
+		assert_eq!(
+			runtime.execute_block(block(2, vec![])),
+			Err("the block does not include every inherent".into())
+		);
+		assert_eq!(
+			runtime.execute_block(block(2, vec![set(12_000), set(18_000)])),
+			Err("extrinsic 1 is an inherent which is already included".into())
+		);
+		assert_eq!(
+			runtime.execute_block(block(2, vec![signed])),
+			Err("extrinsic 0 is an inherent, which must be unsigned".into())
+		);
+		assert_eq!(
+			runtime.execute_block(block(2, vec![set(12_000), unsigned])),
+			Err("extrinsic 1 is unsigned, but is not an inherent".into())
+		);
+		assert_eq!(runtime.system.block_number(), 1);
+		assert_eq!(runtime.timestamp.now(), 6_000);
+	}
+
+	#[test]
+	fn time_must_move_forward() {
+		let mut runtime = Runtime::new();
+
+		assert_eq!(
+			runtime.execute_block(block(1, vec![set(6_000)])).map(|report| report.applied),
+			Ok(vec![0])
+		);
+
+		assert_eq!(
+			runtime.timestamp.set(RawOrigin::None, 8_000),
+			Err("timestamp must increment by at least the minimum period".into())
+		);
+		assert_eq!(runtime.timestamp.set(RawOrigin::None, 9_000), Ok(()));
+		assert_eq!(runtime.timestamp.now(), 9_000);
+	}
+}
@@ -0,0 +1,453 @@
+use crate::{
+	storage::{Hash64Concat, StorageMap, StorageValue, ValueQuery},
+	support::{self, DispatchResult, EnsureOrigin, FromHash, GetPallet, InitializeReport, Percent},
+	system::Origin,
+};
+use num::{traits::NumOps, CheckedAdd, CheckedSub, Zero};
+
+#[macros::config]
+pub trait Config:
+	Sized
+	+ crate::system::Config<
+		AccountId: FromHash,
+		BlockNumber: core::ops::Rem<Output = <Self as crate::system::Config>::BlockNumber>,
+	> + crate::balances::Config<Balance: From<u8> + NumOps>
+	+ GetPallet<crate::balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The origin which may approve spend proposals.
+	type ApproveOrigin: EnsureOrigin<Origin<Self>>;
+	/// The origin which may reject spend proposals.
+	type RejectOrigin: EnsureOrigin<Origin<Self>>;
+	/// The share of a proposed spend which is bonded by the proposer.
+	const PROPOSAL_BOND: Percent;
+	/// The minimum amount bonded by the proposer of a spend.
+	const PROPOSAL_BOND_MINIMUM: Self::Balance;
+	/// How often approved spends are paid out, in blocks.
+	const SPEND_PERIOD: Self::BlockNumber;
+	/// The share of the funds left in the pot after a spend period which is burned.
+	const BURN: Percent;
+	/// The share of transaction fees and slashes which goes to the pot. The rest is burned.
+	const FEE_SHARE: Percent;
+}
+
+/// The index of a spend proposal.
+pub type ProposalIndex = u32;
+
+/// A proposal to spend funds from the pot.
+#[derive(Debug, macros::TypeInfo)]
+pub struct Proposal<T: Config> {
+	/// The account which proposed the spend, and bonded a deposit for it.
+	pub proposer: T::AccountId,
+	/// The amount to pay.
+	pub value: T::Balance,
+	/// The account to pay.
+	pub beneficiary: T::AccountId,
+	/// The amount reserved from the proposer. It is returned when the spend is paid out, and
+	/// slashed when the proposal is rejected.
+	pub bond: T::Balance,
+}
+
+#[macros::storage]
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	/// The number of proposals made so far, used as the index of the next proposal.
+	proposal_count: StorageValue<ProposalIndex, ValueQuery>,
+	/// Proposals which were not paid out or rejected yet.
+	proposals: StorageMap<Hash64Concat, ProposalIndex, Proposal<T>>,
+	/// Proposals which were approved, and wait for the next spend period to be paid out.
+	approvals: StorageValue<Vec<ProposalIndex>, ValueQuery>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn proposal(&self, proposal_id: ProposalIndex) -> Option<&Proposal<T>> {
+		self.proposals.get_ref(&proposal_id)
+	}
+
+	pub fn approvals(&self) -> &[ProposalIndex] {
+		self.approvals.get_ref().map(Vec::as_slice).unwrap_or_default()
+	}
+
+	/// The account of the pot. No one has its key, so only this pallet can spend from it.
+	pub fn account_id() -> T::AccountId {
+		T::AccountId::from_hash(support::hash_of(&"treasury"))
+	}
+
+	/// The funds available in the pot, which are not locked.
+	pub fn pot(runtime: &T) -> T::Balance {
+		let balances: &crate::balances::Pallet<T> = runtime.pallet();
+		let account = Self::account_id();
+		balances
+			.balance(&account)
+			.checked_sub(&balances.locked_balance(&account))
+			.unwrap_or_default()
+	}
+
+	/// Handle `amount` which was removed from an account, like a transaction fee or a slash.
+	/// `FEE_SHARE` of it goes to the pot, and the rest is burned.
+	pub fn on_unbalanced(runtime: &mut T, amount: T::Balance) -> DispatchResult {
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.deposit(&Self::account_id(), T::FEE_SHARE.mul_floor(amount))
+	}
+
+	/// Charge a transaction fee from `who`. See `on_unbalanced` for where the fee goes.
+	pub fn charge_fee(runtime: &mut T, who: &T::AccountId, fee: T::Balance) -> DispatchResult {
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.withdraw(who, fee).map_err(|_| "not enough funds to pay the fee")?;
+		Self::on_unbalanced(runtime, fee)
+	}
+
+	/// Pay `proposal` from the pot `account`, and return the bond of its proposer. Nothing is
+	/// changed if this fails.
+	fn pay_out(runtime: &mut T, account: &T::AccountId, proposal: &Proposal<T>) -> DispatchResult {
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		if balances.reserved_balance(&proposal.proposer) < proposal.bond {
+			return Err("Not enough reserved funds.".into());
+		}
+		// The bond is returned once the beneficiary is paid, who may be the proposer, so check
+		// that it can be before paying.
+		let paid_to_proposer =
+			if proposal.beneficiary == proposal.proposer { proposal.value } else { Zero::zero() };
+		balances
+			.balance(&proposal.proposer)
+			.checked_add(&paid_to_proposer)
+			.and_then(|balance| balance.checked_add(&proposal.bond))
+			.ok_or("Overflow")?;
+
+		balances.withdraw(account, proposal.value)?;
+		if let Err(e) = balances.deposit(&proposal.beneficiary, proposal.value) {
+			balances.deposit(account, proposal.value).expect("withdrawn above; qed");
+			return Err(e);
+		}
+		balances
+			.unreserve(&proposal.proposer, proposal.bond)
+			.expect("checked above; qed");
+		Ok(())
+	}
+
+	/// Pay out the approved proposals which fit in the pot, in the order they were approved, and
+	/// burn `BURN` of what is left. Returns the weight of the proposals paid out, and the errors of
+	/// those whose payout failed. The other approved proposals wait for the next spend period.
+	fn spend_funds(runtime: &mut T) -> InitializeReport {
+		let account = Self::account_id();
+		let pallet: &mut Self = runtime.pallet_mut();
+		let approvals = pallet.approvals.take();
+
+		let mut report = InitializeReport::default();
+		for proposal_id in approvals {
+			let budget = Self::pot(runtime);
+			let pallet: &mut Self = runtime.pallet_mut();
+			let proposal =
+				pallet.proposals.get_ref(&proposal_id).expect("approved proposals exist; qed");
+
+			// Proposals which do not fit in the pot wait for the next spend period.
+			if proposal.value > budget {
+				pallet.approvals.mutate(|approvals| approvals.push(proposal_id));
+				continue;
+			}
+
+			// Proposals whose payout fails are kept, and also wait for the next spend period.
+			let proposal = pallet.proposals.take(&proposal_id).expect("checked above; qed");
+			if let Err(e) = Self::pay_out(runtime, &account, &proposal) {
+				report.failed.push(e);
+				let pallet: &mut Self = runtime.pallet_mut();
+				pallet.proposals.insert(proposal_id, proposal);
+				pallet.approvals.mutate(|approvals| approvals.push(proposal_id));
+				continue;
+			}
+			report.weight = report.weight.saturating_add(support::CALL_WEIGHT);
+		}
+
+		let burn = T::BURN.mul_floor(Self::pot(runtime));
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.withdraw(&account, burn).expect("burn is a share of the pot; qed");
+
+		report
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Propose to pay `value` from the pot to `beneficiary`.
+	///
+	/// The proposer bonds `PROPOSAL_BOND` of the value, and at least `PROPOSAL_BOND_MINIMUM`,
+	/// which is lost if the proposal is rejected.
+	#[call_index(0)]
+	pub fn propose_spend(
+		runtime: &mut T,
+		caller: T::AccountId,
+		value: T::Balance,
+		beneficiary: T::AccountId,
+	) -> DispatchResult {
+		let bond = T::PROPOSAL_BOND.mul_floor(value).max(T::PROPOSAL_BOND_MINIMUM);
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances
+			.reserve(&caller, bond)
+			.map_err(|_| "not enough funds to bond the proposal")?;
+
+		let pallet: &mut Self = runtime.pallet_mut();
+		let proposal_id = pallet.proposal_count.get();
+		pallet
+			.proposal_count
+			.put(proposal_id.checked_add(1).ok_or("too many proposals")?);
+		pallet
+			.proposals
+			.insert(proposal_id, Proposal { proposer: caller, value, beneficiary, bond });
+
+		Ok(())
+	}
+
+	/// Approve a proposal, to be paid out at the next spend period. Only `ApproveOrigin` may
+	/// approve.
+	#[call_index(1)]
+	pub fn approve_proposal(
+		&mut self,
+		origin: Origin<T>,
+		proposal_id: ProposalIndex,
+	) -> DispatchResult {
+		T::ApproveOrigin::ensure_origin(origin)?;
+		if !self.proposals.contains_key(&proposal_id) {
+			return Err("proposal does not exist".into());
+		}
+		if self.approvals().contains(&proposal_id) {
+			return Err("proposal is already approved".into());
+		}
+
+		self.approvals.mutate(|approvals| approvals.push(proposal_id));
+		Ok(())
+	}
+
+	/// Reject a proposal, and slash the bond of the proposer. Only `RejectOrigin` may reject.
+	#[call_index(2)]
+	pub fn reject_proposal(
+		runtime: &mut T,
+		origin: Origin<T>,
+		proposal_id: ProposalIndex,
+	) -> DispatchResult {
+		T::RejectOrigin::ensure_origin(origin)?;
+		let pallet: &mut Self = runtime.pallet_mut();
+		let proposal = pallet.proposals.take(&proposal_id).ok_or("proposal does not exist")?;
+		pallet
+			.approvals
+			.mutate(|approvals| approvals.retain(|approved| *approved != proposal_id));
+
+		let balances: &mut crate::balances::Pallet<T> = runtime.pallet_mut();
+		balances.slash_reserved(&proposal.proposer, proposal.bond)?;
+		Self::on_unbalanced(runtime, proposal.bond)
+	}
+}
+
+impl<T: Config> support::PalletInfo for Pallet<T> {}
+
+impl<T: Config> support::Hooks<T> for Pallet<T> {
+	/// Pay out the approved proposals at the start of every spend period.
+	fn on_initialize(runtime: &mut T, block_number: T::BlockNumber) -> InitializeReport {
+		if !(block_number % T::SPEND_PERIOD).is_zero() {
+			return InitializeReport::default();
+		}
+		Self::spend_funds(runtime)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		support::{self, Hooks, RawOrigin},
+		types, Runtime, RuntimeCall,
+	};
+
+	type Treasury = super::Pallet<Runtime>;
+
+	#[test]
+	fn propose_and_reject() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+
+		assert_eq!(
+			Treasury::propose_spend(&mut runtime, bob.clone(), 100, alice.clone()),
+			Err("not enough funds to bond the proposal".into())
+		);
+		// The bond is 5% of the value.
+		assert_eq!(Treasury::propose_spend(&mut runtime, alice.clone(), 100, bob.clone()), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 5);
+		// The bond is at least the minimum.
+		assert_eq!(Treasury::propose_spend(&mut runtime, alice.clone(), 10, bob.clone()), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 6);
+
+		assert_eq!(
+			runtime.treasury.approve_proposal(RawOrigin::Signed(alice.clone()), 0),
+			Err("bad origin: expected enough approvals of the council".into())
+		);
+		assert_eq!(
+			Treasury::reject_proposal(&mut runtime, RawOrigin::Signed(alice.clone()), 0),
+			Err("bad origin: expected enough approvals of the council".into())
+		);
+		// Root, or two thirds of the council, may approve.
+		assert_eq!(
+			runtime.treasury.approve_proposal(RawOrigin::Collective(3, 5), 1),
+			Err("bad origin: expected enough approvals of the council".into())
+		);
+		assert_eq!(runtime.treasury.approve_proposal(RawOrigin::Collective(2, 3), 1), Ok(()));
+		assert_eq!(
+			Treasury::reject_proposal(&mut runtime, RawOrigin::Root, 2),
+			Err("proposal does not exist".into())
+		);
+
+		// The slashed bond goes to the pot, except for the share which is burned.
+		assert_eq!(Treasury::reject_proposal(&mut runtime, RawOrigin::Root, 0), Ok(()));
+		assert!(runtime.treasury.proposal(0).is_none());
+		assert_eq!(runtime.balances.reserved_balance(&alice), 1);
+		assert_eq!(runtime.balances.balance(&alice), 94);
+		assert_eq!(Treasury::pot(&runtime), 4);
+	}
+
+	#[test]
+	fn spend_approved_proposals() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		runtime.balances.set_balance(Treasury::account_id(), 100);
+
+		assert_eq!(Treasury::propose_spend(&mut runtime, alice.clone(), 60, bob.clone()), Ok(()));
+		assert_eq!(
+			Treasury::propose_spend(&mut runtime, alice.clone(), 50, charlie.clone()),
+			Ok(())
+		);
+		assert_eq!(runtime.treasury.approve_proposal(RawOrigin::Root, 1), Ok(()));
+		assert_eq!(runtime.treasury.approve_proposal(RawOrigin::Root, 0), Ok(()));
+		assert_eq!(
+			runtime.treasury.approve_proposal(RawOrigin::Root, 0),
+			Err("proposal is already approved".into())
+		);
+
+		// Nothing happens outside of a spend period.
+		Treasury::on_initialize(&mut runtime, 3);
+		assert_eq!(Treasury::pot(&runtime), 100);
+
+		// Only the first approved proposal fits in the pot. Half of the rest is burned.
+		Treasury::on_initialize(&mut runtime, 4);
+		assert_eq!(runtime.balances.balance(&charlie), 50);
+		assert_eq!(runtime.balances.balance(&bob), 0);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 3);
+		assert_eq!(Treasury::pot(&runtime), 25);
+		assert_eq!(runtime.treasury.approvals(), &[0]);
+
+		// The pot is refilled, and the remaining proposal is paid at the next spend period.
+		runtime.balances.set_balance(Treasury::account_id(), 80);
+		Treasury::on_initialize(&mut runtime, 8);
+		assert_eq!(runtime.balances.balance(&bob), 60);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(Treasury::pot(&runtime), 10);
+		assert!(runtime.treasury.approvals().is_empty());
+	}
+
+	#[test]
+	fn failed_payouts_are_kept() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		runtime.balances.set_balance(bob.clone(), u128::MAX - 10);
+		runtime.balances.set_balance(Treasury::account_id(), 100);
+
+		assert_eq!(Treasury::propose_spend(&mut runtime, alice.clone(), 60, alice.clone()), Ok(()));
+		assert_eq!(Treasury::propose_spend(&mut runtime, alice.clone(), 20, bob.clone()), Ok(()));
+		assert_eq!(runtime.treasury.approve_proposal(RawOrigin::Root, 0), Ok(()));
+		assert_eq!(runtime.treasury.approve_proposal(RawOrigin::Root, 1), Ok(()));
+
+		// Most of the pot is locked, so it cannot pay the first proposal, and bob cannot receive
+		// the second. Nothing is paid, and half of what is left is burned.
+		runtime.balances.set_lock(*b"testlock", &Treasury::account_id(), 50);
+		assert_eq!(Treasury::pot(&runtime), 50);
+		let report = Treasury::on_initialize(&mut runtime, 4);
+		assert_eq!(report.weight, 0);
+		assert_eq!(report.failed, ["Overflow".into()]);
+		assert_eq!(runtime.balances.balance(&bob), u128::MAX - 10);
+		assert_eq!(runtime.balances.balance(&Treasury::account_id()), 75);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 4);
+		assert_eq!(runtime.treasury.approvals(), &[0, 1]);
+		assert!(runtime.treasury.proposal(1).is_some());
+
+		// Both are paid once they can be.
+		runtime.balances.remove_lock(*b"testlock", &Treasury::account_id());
+		runtime.balances.set_balance(bob.clone(), 0);
+		runtime.balances.set_balance(Treasury::account_id(), 100);
+		assert_eq!(Treasury::on_initialize(&mut runtime, 8).weight, 2 * support::CALL_WEIGHT);
+		assert_eq!(runtime.balances.balance(&alice), 160);
+		assert_eq!(runtime.balances.balance(&bob), 20);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert!(runtime.treasury.approvals().is_empty());
+	}
+
+	#[test]
+	fn payouts_which_cannot_return_the_bond_are_kept() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+		runtime.balances.set_balance(Treasury::account_id(), 100);
+		assert_eq!(Treasury::propose_spend(&mut runtime, alice.clone(), 60, bob.clone()), Ok(()));
+		assert_eq!(runtime.treasury.approve_proposal(RawOrigin::Root, 0), Ok(()));
+
+		// The bond of Alice cannot be returned, so Bob is not paid, and the proposal is kept.
+		runtime.balances.set_balance(alice.clone(), u128::MAX);
+		let report = Treasury::on_initialize(&mut runtime, 4);
+		assert_eq!(report.failed, ["Overflow".into()]);
+		assert_eq!(runtime.balances.balance(&bob), 0);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 3);
+		assert_eq!(runtime.treasury.approvals(), &[0]);
+
+		// It is paid once, when the bond can be returned.
+		runtime.balances.set_balance(alice.clone(), 0);
+		runtime.balances.set_balance(Treasury::account_id(), 100);
+		assert_eq!(Treasury::on_initialize(&mut runtime, 8), support::CALL_WEIGHT.into());
+		assert_eq!(Treasury::on_initialize(&mut runtime, 12), 0.into());
+		assert_eq!(runtime.balances.balance(&bob), 60);
+		assert_eq!(runtime.balances.balance(&alice), 3);
+		assert!(runtime.treasury.approvals().is_empty());
+	}
+
+	#[test]
+	fn fees_go_to_the_pot() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(alice.clone(), 100);
+
+		let transfer = |caller: &str| types::Extrinsic {
+			caller: Some(caller.to_string().into()),
+			call: RuntimeCall::balances(crate::balances::Call::transfer {
+				to: "charlie".to_string().into(),
+				amount: 10,
+			}),
+		};
+		let block = types::Block {
+			header: support::Header { block_number: 1 },
+			extrinsics: vec![
+				support::Extrinsic {
+					caller: None,
+					call: RuntimeCall::timestamp(crate::timestamp::Call::set { now: 6_000 }),
+				},
+				transfer(&alice),
+				// Bob cannot pay the fee, so his extrinsic is skipped.
+				transfer(&bob),
+			],
+		};
+		let report = runtime.execute_block(block).expect("valid block");
+		assert_eq!(report.applied, [0, 1]);
+		assert_eq!(report.failed, [(2, "not enough funds to pay the fee".into())]);
+		// Bob's extrinsic is not dispatched, so it uses no weight.
+		assert_eq!(report.weight, 2 * support::CALL_WEIGHT);
+
+		assert_eq!(runtime.balances.balance(&alice), 85);
+		assert_eq!(runtime.system.nonce(&alice), 1);
+		assert_eq!(runtime.system.nonce(&bob), 0);
+		// 80% of the fee goes to the pot.
+		assert_eq!(Treasury::pot(&runtime), 4);
+	}
+}
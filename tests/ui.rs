@@ -0,0 +1,17 @@
+//! UI tests of the macros, which check the errors they give for invalid pallets and runtimes
+//! against the `.stderr` file next to each test, and that valid pallets and runtimes compile and
+//! run.
+//!
+//! The tests include the modules of the runtime which the generated code refers to with
+//! `tests/ui/prelude.rs`, and runtimes use the pallet in `tests/ui/pallet.rs`. After changing a
+//! diagnostic, update the `.stderr` files with `TRYBUILD=overwrite cargo test --test ui`.
+
+#[test]
+fn ui() {
+	let t = trybuild::TestCases::new();
+	t.pass("tests/ui/pass/*.rs");
+	t.compile_fail("tests/ui/call/*.rs");
+	t.compile_fail("tests/ui/error/*.rs");
+	t.compile_fail("tests/ui/pallet/*.rs");
+	t.compile_fail("tests/ui/runtime/*.rs");
+}
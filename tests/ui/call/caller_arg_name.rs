@@ -0,0 +1,18 @@
+include!("../prelude.rs");
+
+pub trait Config: system::Config {}
+
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	counters: storage::StorageMap<storage::Hash64Concat, T::AccountId, u32>,
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	#[call_index(0)]
+	pub fn increment(&mut self, _who: T::AccountId) -> support::DispatchResult {
+		Ok(())
+	}
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+include!("../prelude.rs");
+
+pub trait Config: system::Config {}
+
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	now: storage::StorageValue<(T::BlockNumber, u64)>,
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	#[call_index(0)]
+	#[inherent(required)]
+	pub fn set(&mut self, origin: system::Origin<T>, now: u64) -> support::DispatchResult {
+		support::ensure_none(origin)?;
+		self.now.put((Default::default(), now));
+		Ok(())
+	}
+}
+
+fn main() {}
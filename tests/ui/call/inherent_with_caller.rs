@@ -0,0 +1,20 @@
+include!("../prelude.rs");
+
+pub trait Config: system::Config {}
+
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	now: storage::StorageValue<(T::BlockNumber, u64)>,
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	#[call_index(0)]
+	#[inherent]
+	pub fn set(&mut self, _caller: T::AccountId, now: u64) -> support::DispatchResult {
+		self.now.put((Default::default(), now));
+		Ok(())
+	}
+}
+
+fn main() {}
@@ -0,0 +1,52 @@
+//! A small pallet for the runtimes of the UI tests, which uses all the macros of a pallet.
+
+use crate::{
+	storage::{Hash64Concat, StorageMap, ValueQuery},
+	support::DispatchResult,
+};
+
+#[macros::config]
+pub trait Config: Sized + crate::system::Config<RuntimeEvent: From<Event<Self>>> {
+	/// The largest value of a counter.
+	const LIMIT: u32;
+}
+
+#[macros::event]
+pub enum Event<T: Config> {
+	/// The counter of an account was incremented.
+	Incremented { who: T::AccountId, value: u32 },
+}
+
+#[macros::error]
+pub enum Error<T> {
+	/// The counter is at `Config::LIMIT`.
+	AtLimit,
+}
+
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// The counter of each account.
+	#[getter(counter)]
+	counters: StorageMap<Hash64Concat, T::AccountId, u32, ValueQuery>,
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Increment the counter of the caller.
+	#[call_index(0)]
+	pub fn increment(&mut self, caller: T::AccountId) -> DispatchResult {
+		let value = self.counter(&caller) + 1;
+		if value > T::LIMIT {
+			return Err(Error::<T>::AtLimit.into());
+		}
+		self.counters.insert(caller, value);
+		Ok(())
+	}
+}
+
+impl<T: Config> crate::support::PalletInfo for Pallet<T> {
+	const HAS_EVENT: bool = true;
+	const HAS_ERROR: bool = true;
+}
+
+impl<T: Config> crate::support::Hooks<T> for Pallet<T> {}
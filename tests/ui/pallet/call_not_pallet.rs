@@ -0,0 +1,21 @@
+include!("../prelude.rs");
+
+#[macros::pallet]
+pub mod pallet {
+	use crate::storage::StorageValue;
+
+	#[pallet::config]
+	pub trait Config: crate::system::Config {}
+
+	#[pallet::storage]
+	pub struct Pallet<T: Config> {
+		owner: StorageValue<T::AccountId>,
+	}
+
+	pub struct Calls<T>(T);
+
+	#[pallet::call]
+	impl<T: Config> Calls<T> {}
+}
+
+fn main() {}
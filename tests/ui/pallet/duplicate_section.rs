@@ -0,0 +1,26 @@
+include!("../prelude.rs");
+
+#[macros::pallet]
+pub mod pallet {
+	use crate::storage::StorageValue;
+
+	#[pallet::config]
+	pub trait Config: crate::system::Config {}
+
+	#[pallet::storage]
+	pub struct Pallet<T: Config> {
+		owner: StorageValue<T::AccountId>,
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		NotOwner,
+	}
+
+	#[pallet::error]
+	pub enum Errors<T> {
+		NoOwner,
+	}
+}
+
+fn main() {}
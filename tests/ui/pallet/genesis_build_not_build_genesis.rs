@@ -0,0 +1,28 @@
+include!("../prelude.rs");
+
+#[macros::pallet]
+pub mod pallet {
+	use crate::storage::StorageValue;
+
+	#[pallet::config]
+	pub trait Config: crate::system::Config {}
+
+	#[pallet::storage]
+	pub struct Pallet<T: Config> {
+		owner: StorageValue<T::AccountId>,
+	}
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub owner: T::AccountId,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisConfig<T> {
+		pub fn build(&self, pallet: &mut Pallet<T>) {
+			pallet.owner.put(self.owner.clone());
+		}
+	}
+}
+
+fn main() {}
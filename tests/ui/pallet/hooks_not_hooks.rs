@@ -0,0 +1,23 @@
+include!("../prelude.rs");
+
+#[macros::pallet]
+pub mod pallet {
+	use crate::storage::StorageValue;
+
+	#[pallet::config]
+	pub trait Config: crate::system::Config {}
+
+	#[pallet::storage]
+	pub struct Pallet<T: Config> {
+		owner: StorageValue<T::AccountId>,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Default for Pallet<T> {
+		fn default() -> Self {
+			Self::new()
+		}
+	}
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+include!("../prelude.rs");
+
+#[macros::pallet]
+pub mod pallet {
+	use crate::storage::StorageValue;
+
+	#[pallet::storage]
+	pub struct Pallet<T: crate::system::Config> {
+		owner: StorageValue<T::AccountId>,
+	}
+}
+
+fn main() {}
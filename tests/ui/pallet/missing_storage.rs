@@ -0,0 +1,9 @@
+include!("../prelude.rs");
+
+#[macros::pallet]
+pub mod pallet {
+	#[pallet::config]
+	pub trait Config: crate::system::Config {}
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+include!("../prelude.rs");
+
+#[macros::pallet]
+pub struct Pallet;
+
+fn main() {}
@@ -0,0 +1,24 @@
+include!("../prelude.rs");
+
+#[macros::pallet]
+pub mod pallet {
+	use crate::storage::StorageValue;
+
+	#[pallet::config]
+	pub trait Config: crate::system::Config {}
+
+	#[pallet::storage]
+	pub struct Pallet<T: Config> {
+		owner: StorageValue<T::AccountId>,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		pub fn claim(&mut self, caller: T::AccountId) -> crate::support::DispatchResult {
+			self.owner.put(caller);
+			Ok(())
+		}
+	}
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+include!("../prelude.rs");
+
+#[macros::pallet]
+pub mod pallet {
+	use crate::storage::StorageValue;
+
+	#[pallet::config]
+	pub trait Config: crate::system::Config {}
+
+	#[pallet::storage]
+	pub struct Module<T: Config> {
+		owner: StorageValue<T::AccountId>,
+	}
+}
+
+fn main() {}
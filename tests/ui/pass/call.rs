@@ -0,0 +1,96 @@
+include!("../prelude.rs");
+
+use storage::{Hash64Concat, StorageMap, StorageValue, ValueQuery};
+use support::{DispatchResult, GetPallet, Instance};
+use system::Origin;
+
+pub trait Config<I: Instance = ()>: Sized + system::Config + GetPallet<Pallet<Self, I>> {
+	type Counter: Default + core::ops::AddAssign + From<u8>;
+}
+
+#[macros::storage]
+pub struct Pallet<T: Config<I>, I: Instance = ()> {
+	owner: StorageValue<T::AccountId>,
+	counters: StorageMap<Hash64Concat, T::AccountId, T::Counter, ValueQuery>,
+}
+
+#[macros::call]
+impl<T: Config<I>, I: Instance> Pallet<T, I> {
+	/// A call on the pallet, signed by `caller`.
+	#[call_index(0)]
+	pub fn increment(&mut self, caller: T::AccountId) -> DispatchResult {
+		self.counters.mutate(&caller, |counter| *counter += 1.into());
+		Ok(())
+	}
+
+	/// A call with the origin, and an account which is given as an address.
+	#[call_index(1)]
+	pub fn set_owner(&mut self, origin: Origin<T>, owner: T::AccountId) -> DispatchResult {
+		support::ensure_root(origin)?;
+		self.owner.put(owner);
+		Ok(())
+	}
+
+	/// A call on the runtime, whose arguments are unused.
+	#[call_index(7)]
+	pub fn noop(_runtime: &mut T, _caller: T::AccountId, _value: u32) -> DispatchResult {
+		Ok(())
+	}
+
+	/// A call on the runtime, with an unused origin.
+	#[call_index(2)]
+	pub fn reset(runtime: &mut T, _origin: Origin<T>) -> DispatchResult {
+		let pallet: &mut Self = runtime.pallet_mut();
+		pallet.owner.kill();
+		Ok(())
+	}
+}
+
+// A runtime with only this pallet, whose accounts are given as addresses.
+pub struct Runtime {
+	pallet: Pallet<Runtime>,
+}
+
+impl system::Config for Runtime {
+	type AccountId = String;
+	type BlockNumber = u32;
+	type Nonce = u32;
+	type RuntimeEvent = ();
+}
+
+impl Config for Runtime {
+	type Counter = u32;
+}
+
+impl GetPallet<Pallet<Runtime>> for Runtime {
+	fn pallet(&self) -> &Pallet<Runtime> {
+		&self.pallet
+	}
+
+	fn pallet_mut(&mut self) -> &mut Pallet<Runtime> {
+		&mut self.pallet
+	}
+}
+
+impl support::Lookup for Runtime {
+	type Source = support::MultiAddress<String, u32>;
+	type Target = String;
+
+	fn lookup(&self, address: Self::Source) -> Result<String, support::DispatchError> {
+		match address {
+			support::MultiAddress::Id(who) => Ok(who),
+			support::MultiAddress::Index(_) => Err("unknown index".into()),
+		}
+	}
+}
+
+fn main() {
+	// Calls are compared and printed by their arguments, which are held as addresses.
+	let set_owner = |owner| Call::<Runtime>::set_owner { owner };
+	let call = set_owner(support::MultiAddress::Id("bob".to_string()));
+	assert_eq!(call.clone(), call);
+	assert_ne!(set_owner(support::MultiAddress::Index(0)), call);
+	assert_ne!(Call::<Runtime>::noop { _value: 0 }, Call::<Runtime>::noop { _value: 1 });
+	assert_eq!(format!("{:?}", call), r#"set_owner { owner: Id("bob") }"#);
+	assert_eq!(format!("{:?}", Call::<Runtime>::increment {}), "increment");
+}
@@ -0,0 +1,198 @@
+include!("../prelude.rs");
+
+mod counter {
+	pub use pallet::*;
+
+	/// A pallet like the one in `tests/ui/pallet.rs`, in one module.
+	#[macros::pallet]
+	pub mod pallet {
+		use crate::{
+			storage::{Hash64Concat, StorageMap, StorageValue, ValueQuery},
+			support::{BuildGenesis, DispatchResult, GetPallet, InitializeReport},
+		};
+
+		#[pallet::config]
+		pub trait Config:
+			crate::system::Config
+			+ GetPallet<crate::system::Pallet<Self>>
+			+ GetPallet<Pallet<Self>>
+		{
+			/// The largest value of a counter.
+			const LIMIT: u32;
+		}
+
+		#[pallet::event]
+		pub enum Event<T: Config> {
+			/// The counter of an account was incremented.
+			Incremented { who: T::AccountId, value: u32 },
+		}
+
+		#[pallet::error]
+		pub enum Error<T> {
+			/// The counter is at `Config::LIMIT`.
+			AtLimit,
+		}
+
+		#[pallet::storage]
+		pub struct Pallet<T: Config> {
+			/// The counter of each account.
+			#[getter(counter)]
+			counters: StorageMap<Hash64Concat, T::AccountId, u32, ValueQuery>,
+			/// The number of blocks started.
+			#[getter(blocks)]
+			blocks: StorageValue<u32, ValueQuery>,
+		}
+
+		/// The counters of the first accounts.
+		#[pallet::genesis_config]
+		pub struct GenesisConfig<T: Config> {
+			pub counters: Vec<(T::AccountId, u32)>,
+		}
+
+		#[pallet::genesis_build]
+		impl<T: Config> BuildGenesis<Pallet<T>> for GenesisConfig<T> {
+			fn build(&self, pallet: &mut Pallet<T>) {
+				for (who, value) in &self.counters {
+					pallet.counters.insert(who.clone(), *value);
+				}
+			}
+		}
+
+		#[pallet::call]
+		impl<T: Config> Pallet<T> {
+			/// Increment the counter of the caller.
+			#[call_index(0)]
+			pub fn increment(runtime: &mut T, caller: T::AccountId) -> DispatchResult {
+				let pallet: &mut Self = runtime.pallet_mut();
+				let value = pallet.counter(&caller) + 1;
+				if value > T::LIMIT {
+					return Err(Error::<T>::AtLimit.into());
+				}
+				pallet.counters.insert(caller.clone(), value);
+				Self::deposit_event(runtime, Event::Incremented { who: caller, value });
+				Ok(())
+			}
+		}
+
+		#[pallet::hooks]
+		impl<T: Config> crate::support::Hooks<T> for Pallet<T> {
+			fn on_initialize(runtime: &mut T, _block_number: T::BlockNumber) -> InitializeReport {
+				let pallet: &mut Self = runtime.pallet_mut();
+				pallet.blocks.put(pallet.blocks() + 1);
+				InitializeReport::default()
+			}
+		}
+	}
+}
+
+mod minimal {
+	pub use pallet::*;
+
+	/// A pallet with only the sections which are required.
+	#[macros::pallet]
+	pub mod pallet {
+		use crate::storage::StorageValue;
+
+		#[pallet::config]
+		pub trait Config: crate::system::Config {}
+
+		#[pallet::storage]
+		pub struct Pallet<T: Config> {
+			#[getter(owner)]
+			owner: StorageValue<T::AccountId>,
+		}
+	}
+}
+
+mod types {
+	pub type AccountId = String;
+	pub type BlockNumber = u32;
+	pub type Extrinsic = crate::support::Extrinsic<AccountId, crate::RuntimeCall>;
+	pub type Header = crate::support::Header<BlockNumber>;
+	pub type Block = crate::support::Block<Header, Extrinsic>;
+}
+
+#[macros::runtime]
+pub struct Runtime {
+	system: system::Pallet<Self>,
+	#[pallet_index(0)]
+	#[event]
+	#[error]
+	#[genesis]
+	counter: counter::Pallet<Self>,
+	#[pallet_index(1)]
+	minimal: minimal::Pallet<Self>,
+}
+
+impl system::Config for Runtime {
+	type AccountId = types::AccountId;
+	type BlockNumber = types::BlockNumber;
+	type Nonce = u32;
+	type RuntimeEvent = RuntimeEvent;
+}
+
+impl counter::Config for Runtime {
+	const LIMIT: u32 = 2;
+}
+
+impl minimal::Config for Runtime {}
+
+impl support::Lookup for Runtime {
+	type Source = types::AccountId;
+	type Target = types::AccountId;
+
+	fn lookup(&self, who: types::AccountId) -> Result<types::AccountId, support::DispatchError> {
+		Ok(who)
+	}
+}
+
+impl support::ChargeTransaction<types::AccountId, RuntimeCall> for Runtime {
+	fn charge_transaction(
+		&mut self,
+		_who: &types::AccountId,
+		_call: &RuntimeCall,
+	) -> support::DispatchResult {
+		Ok(())
+	}
+}
+
+fn main() {
+	let alice = "alice".to_string();
+	let mut runtime = Runtime::from_genesis(RuntimeGenesisConfig {
+		counter: counter::GenesisConfig { counters: vec![(alice.clone(), 1)] },
+	});
+	assert_eq!(runtime.counter.counter(&alice), 1);
+	assert_eq!(runtime.minimal.owner(), None);
+
+	let increment = || support::Extrinsic {
+		caller: Some(alice.clone()),
+		call: RuntimeCall::counter(counter::Call::increment {}),
+	};
+	let block = types::Block {
+		header: support::Header { block_number: 1 },
+		extrinsics: vec![increment(), increment()],
+	};
+	let report = runtime.execute_block(block).unwrap();
+	assert_eq!(report.applied, [0]);
+	assert_eq!(report.failed, [(1, counter::Error::<Runtime>::AtLimit.into())]);
+	assert_eq!(runtime.counter.counter(&alice), 2);
+	assert_eq!(runtime.counter.blocks(), 1);
+	let event = counter::Event::Incremented { who: alice, value: 2 };
+	assert_eq!(runtime.system.events(), [RuntimeEvent::counter(event)]);
+
+	// The name of the pallet is the module around its sections, in errors and in storage.
+	let error = support::DispatchError::from(counter::Error::<Runtime>::AtLimit);
+	assert!(matches!(error, support::DispatchError::Module(error) if error.pallet == "counter"));
+
+	let metadata = Runtime::metadata();
+	let (counter, minimal) = (&metadata.pallets[1], &metadata.pallets[2]);
+	assert_eq!(counter.name, "counter");
+	assert_eq!(counter.calls[0].name, "increment");
+	assert_eq!(counter.events[0].name, "Incremented");
+	assert_eq!(counter.errors[0].name, "AtLimit");
+	assert_eq!(counter.constants[0].value, "2");
+	assert_eq!(counter.storage.len(), 2);
+	assert_eq!(minimal.name, "minimal");
+	assert!(minimal.calls.is_empty());
+	assert_eq!(minimal.storage.len(), 1);
+}
@@ -0,0 +1,89 @@
+include!("../prelude.rs");
+
+#[path = "../pallet.rs"]
+mod counter;
+
+mod types {
+	pub type AccountId = String;
+	pub type BlockNumber = u32;
+	pub type Extrinsic = crate::support::Extrinsic<AccountId, crate::RuntimeCall>;
+	pub type Header = crate::support::Header<BlockNumber>;
+	pub type Block = crate::support::Block<Header, Extrinsic>;
+}
+
+#[macros::runtime]
+pub struct Runtime {
+	system: system::Pallet<Self>,
+	#[pallet_index(3)]
+	#[event]
+	#[error]
+	counter: counter::Pallet<Self>,
+}
+
+impl system::Config for Runtime {
+	type AccountId = types::AccountId;
+	type BlockNumber = types::BlockNumber;
+	type Nonce = u32;
+	type RuntimeEvent = RuntimeEvent;
+}
+
+impl counter::Config for Runtime {
+	const LIMIT: u32 = 1;
+}
+
+// Accounts are given directly, and extrinsics are free.
+impl support::Lookup for Runtime {
+	type Source = types::AccountId;
+	type Target = types::AccountId;
+
+	fn lookup(&self, who: types::AccountId) -> Result<types::AccountId, support::DispatchError> {
+		Ok(who)
+	}
+}
+
+impl support::ChargeTransaction<types::AccountId, RuntimeCall> for Runtime {
+	fn charge_transaction(
+		&mut self,
+		_who: &types::AccountId,
+		_call: &RuntimeCall,
+	) -> support::DispatchResult {
+		Ok(())
+	}
+}
+
+fn main() {
+	use codec::{Decode, Encode};
+
+	let mut runtime = Runtime::new();
+	let alice = "alice".to_string();
+	let increment = || support::Extrinsic {
+		caller: Some(alice.clone()),
+		call: RuntimeCall::counter(counter::Call::increment {}),
+	};
+	let block = types::Block {
+		header: support::Header { block_number: 1 },
+		extrinsics: vec![increment(), increment()],
+	};
+	let report = runtime.execute_block(block).unwrap();
+	// The second increment fails, since the limit is 1, but the nonce is still incremented.
+	assert_eq!(report.applied, [0]);
+	assert_eq!(report.failed, [(1, counter::Error::<Runtime>::AtLimit.into())]);
+	assert_eq!(report.weight, 2 * support::CALL_WEIGHT);
+	assert_eq!(runtime.counter.counter(&alice), 1);
+	assert_eq!(runtime.system.nonce(&alice), 2);
+
+	let call = RuntimeCall::counter(counter::Call::increment {});
+	assert_eq!(call.encode(), [3, 0]);
+	assert_eq!(RuntimeCall::decode(&mut &call.encode()[..]), Ok(call));
+
+	let event = counter::Event::Incremented { who: alice, value: 1 };
+	assert!(matches!(RuntimeEvent::from(event), RuntimeEvent::counter(_)));
+
+	let metadata = Runtime::metadata();
+	let counter = &metadata.pallets[1];
+	assert_eq!((counter.name, counter.index), ("counter", Some(3)));
+	assert_eq!(counter.calls[0].name, "increment");
+	assert_eq!(counter.events[0].name, "Incremented");
+	assert_eq!(counter.errors[0].name, "AtLimit");
+	assert_eq!(counter.constants[0].value, "1");
+}
@@ -0,0 +1,76 @@
+include!("../prelude.rs");
+
+#[path = "../pallet.rs"]
+mod counter;
+
+// The runtime is in its own module, with its own block type, and the system pallet is imported
+// and stored in a field with another name, after the other pallets.
+mod runtime {
+	use crate::{counter, support, system::Pallet as SystemPallet};
+
+	pub type Extrinsic = support::Extrinsic<String, RuntimeCall>;
+	pub type RuntimeBlock = support::Block<support::Header<u32>, Extrinsic>;
+
+	#[macros::runtime(block = RuntimeBlock, system = crate::system)]
+	pub struct Runtime {
+		#[pallet_index(0)]
+		#[event]
+		#[error]
+		pub counter: counter::Pallet<Self>,
+		#[pallet(system)]
+		pub frame: SystemPallet<Self>,
+	}
+
+	impl crate::system::Config for Runtime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type RuntimeEvent = RuntimeEvent;
+	}
+
+	impl counter::Config for Runtime {
+		const LIMIT: u32 = 10;
+	}
+
+	impl support::Lookup for Runtime {
+		type Source = String;
+		type Target = String;
+
+		fn lookup(&self, who: String) -> Result<String, support::DispatchError> {
+			Ok(who)
+		}
+	}
+
+	impl support::ChargeTransaction<String, RuntimeCall> for Runtime {
+		fn charge_transaction(
+			&mut self,
+			_who: &String,
+			_call: &RuntimeCall,
+		) -> support::DispatchResult {
+			Ok(())
+		}
+	}
+}
+
+fn main() {
+	use runtime::{Runtime, RuntimeBlock, RuntimeCall};
+
+	let mut runtime = Runtime::new();
+	let alice = "alice".to_string();
+	let block = RuntimeBlock {
+		header: support::Header { block_number: 1 },
+		extrinsics: vec![support::Extrinsic {
+			caller: Some(alice.clone()),
+			call: RuntimeCall::counter(counter::Call::increment {}),
+		}],
+	};
+	let report = runtime.execute_block(block).unwrap();
+	assert_eq!(report.applied, [0]);
+	assert_eq!(runtime.frame.block_number(), 1);
+	assert_eq!(runtime.frame.nonce(&alice), 1);
+	assert_eq!(runtime.counter.counter(&alice), 1);
+
+	let metadata = Runtime::metadata();
+	let names = metadata.pallets.iter().map(|pallet| pallet.name).collect::<Vec<_>>();
+	assert_eq!(names, ["frame", "counter"]);
+}
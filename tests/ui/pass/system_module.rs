@@ -0,0 +1,119 @@
+include!("../prelude.rs");
+
+// The system pallet, reached through another module than the root of the crate, with the modules
+// the generated code refers to next to it.
+mod frame {
+	pub(crate) use crate::{codec, metadata, support, system};
+}
+
+// A pallet which is written against the system pallet of `frame`.
+mod counter {
+	use crate::{
+		frame,
+		storage::{Hash64Concat, StorageMap, ValueQuery},
+		support::{DispatchResult, GetPallet},
+	};
+	use frame::system::Origin;
+
+	#[macros::config]
+	pub trait Config: Sized + frame::system::Config + GetPallet<Pallet<Self>> {}
+
+	#[macros::storage]
+	pub struct Pallet<T: Config> {
+		#[getter(counter)]
+		counters: StorageMap<Hash64Concat, T::AccountId, u32, ValueQuery>,
+	}
+
+	#[macros::call]
+	impl<T: Config> Pallet<T> {
+		#[call_index(0)]
+		pub fn increment(&mut self, caller: T::AccountId) -> DispatchResult {
+			self.counters.mutate(&caller, |counter| *counter += 1);
+			Ok(())
+		}
+
+		#[call_index(1)]
+		pub fn reset(runtime: &mut T, origin: Origin<T>, who: T::AccountId) -> DispatchResult {
+			crate::support::ensure_root(origin)?;
+			let pallet: &mut Self = runtime.pallet_mut();
+			pallet.counters.remove(&who);
+			Ok(())
+		}
+	}
+
+	impl<T: Config> crate::support::PalletInfo for Pallet<T> {}
+
+	impl<T: Config> crate::support::Hooks<T> for Pallet<T> {}
+}
+
+mod types {
+	pub type Extrinsic = crate::support::Extrinsic<String, crate::RuntimeCall>;
+	pub type Block = crate::support::Block<crate::support::Header<u32>, Extrinsic>;
+}
+
+use frame::system::Pallet as System;
+
+#[macros::runtime(system = frame::system)]
+pub struct Runtime {
+	system: System<Self>,
+	#[pallet_index(0)]
+	counter: counter::Pallet<Self>,
+}
+
+impl frame::system::Config for Runtime {
+	type AccountId = String;
+	type BlockNumber = u32;
+	type Nonce = u32;
+	type RuntimeEvent = ();
+}
+
+impl counter::Config for Runtime {}
+
+impl support::Lookup for Runtime {
+	type Source = String;
+	type Target = String;
+
+	fn lookup(&self, who: String) -> Result<String, support::DispatchError> {
+		Ok(who)
+	}
+}
+
+impl support::ChargeTransaction<String, RuntimeCall> for Runtime {
+	fn charge_transaction(
+		&mut self,
+		_who: &String,
+		_call: &RuntimeCall,
+	) -> support::DispatchResult {
+		Ok(())
+	}
+}
+
+fn main() {
+	let mut runtime = Runtime::new();
+	let alice = "alice".to_string();
+	let extrinsic = |caller: Option<&str>, call| support::Extrinsic {
+		caller: caller.map(str::to_string),
+		call: RuntimeCall::counter(call),
+	};
+	let block = types::Block {
+		header: support::Header { block_number: 1 },
+		extrinsics: vec![
+			extrinsic(Some("alice"), counter::Call::increment {}),
+			extrinsic(Some("alice"), counter::Call::increment {}),
+		],
+	};
+	let report = runtime.execute_block(block).unwrap();
+	assert_eq!(report.applied, [0, 1]);
+	assert_eq!(runtime.system.nonce(&alice), 2);
+	assert_eq!(runtime.counter.counter(&alice), 2);
+
+	// The origin of calls is the one of the system pallet of `frame`.
+	let origin: frame::system::Origin<Runtime> = support::RawOrigin::Root;
+	let reset = RuntimeCall::counter(counter::Call::reset { who: alice.clone() });
+	assert_eq!(support::Dispatch::dispatch(&mut runtime, origin, reset), Ok(()));
+	assert_eq!(runtime.counter.counter(&alice), 0);
+
+	let metadata = Runtime::metadata();
+	let names = metadata.pallets.iter().map(|pallet| pallet.name).collect::<Vec<_>>();
+	assert_eq!(names, ["system", "counter"]);
+}
@@ -0,0 +1,12 @@
+// The modules of the runtime which the code generated by the macros refers to, like
+// `crate::support::Dispatch`.
+#[path = "../../src/codec.rs"]
+mod codec;
+#[path = "../../src/metadata.rs"]
+mod metadata;
+#[path = "../../src/storage.rs"]
+mod storage;
+#[path = "../../src/support.rs"]
+mod support;
+#[path = "../../src/system.rs"]
+mod system;
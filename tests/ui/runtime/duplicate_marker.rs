@@ -0,0 +1,26 @@
+include!("../prelude.rs");
+
+#[path = "../pallet.rs"]
+mod counter;
+
+#[macros::runtime]
+pub struct Runtime {
+	system: system::Pallet<Self>,
+	#[pallet_index(0)]
+	#[event]
+	#[event]
+	counter: counter::Pallet<Self>,
+}
+
+impl system::Config for Runtime {
+	type AccountId = String;
+	type BlockNumber = u32;
+	type Nonce = u32;
+	type RuntimeEvent = counter::Event<Self>;
+}
+
+impl counter::Config for Runtime {
+	const LIMIT: u32 = 1;
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+include!("../prelude.rs");
+
+#[path = "../pallet.rs"]
+mod counter;
+
+#[macros::runtime]
+pub struct Runtime {
+	#[pallet(system)]
+	system: system::Pallet<Self>,
+	#[pallet(system)]
+	frame: system::Pallet<Self>,
+}
+
+impl system::Config for Runtime {
+	type AccountId = String;
+	type BlockNumber = u32;
+	type Nonce = u32;
+	type RuntimeEvent = counter::Event<Self>;
+}
+
+impl counter::Config for Runtime {
+	const LIMIT: u32 = 1;
+}
+
+fn main() {}
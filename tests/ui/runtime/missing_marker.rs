@@ -0,0 +1,50 @@
+include!("../prelude.rs");
+
+#[path = "../pallet.rs"]
+mod counter;
+
+mod types {
+	pub type Extrinsic = crate::support::Extrinsic<String, crate::RuntimeCall>;
+	pub type Block = crate::support::Block<crate::support::Header<u32>, Extrinsic>;
+}
+
+// The pallet has errors, but is not marked with `#[error]`.
+#[macros::runtime]
+pub struct Runtime {
+	system: system::Pallet<Self>,
+	#[pallet_index(0)]
+	#[event]
+	counter: counter::Pallet<Self>,
+}
+
+impl system::Config for Runtime {
+	type AccountId = String;
+	type BlockNumber = u32;
+	type Nonce = u32;
+	type RuntimeEvent = RuntimeEvent;
+}
+
+impl counter::Config for Runtime {
+	const LIMIT: u32 = 1;
+}
+
+impl support::Lookup for Runtime {
+	type Source = String;
+	type Target = String;
+
+	fn lookup(&self, who: String) -> Result<String, support::DispatchError> {
+		Ok(who)
+	}
+}
+
+impl support::ChargeTransaction<String, RuntimeCall> for Runtime {
+	fn charge_transaction(
+		&mut self,
+		_who: &String,
+		_call: &RuntimeCall,
+	) -> support::DispatchResult {
+		Ok(())
+	}
+}
+
+fn main() {}